@@ -0,0 +1,49 @@
+//! `#[derive(FromSignalArgs)]`, re-exported by `ev-stream-gtk-rs` with the `derive` feature.
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Implements `FromSignalArgs`, extracting the signal arguments into the fields in the
+/// order they are declared. The first field receives the emitting object.
+///
+/// The conversion returns `None` if the number of arguments differs from the number of
+/// fields, or if an argument doesn't have the type of its field.
+#[proc_macro_derive(FromSignalArgs)]
+pub fn derive_from_signal_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return syn::Error::new(
+                input.ident.span(),
+                "FromSignalArgs can only be derived for structs",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+    let args: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect();
+    let body = match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote!(Self { #(#names: #args.get().ok()?),* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#args.get().ok()?),*)),
+        Fields::Unit => quote!(Self),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::ev_stream_gtk_rs::FromSignalArgs for #name #ty_generics #where_clause {
+            fn from_signal_args(args: &[::ev_stream_gtk_rs::glib::Value]) -> ::std::option::Option<Self> {
+                match args {
+                    [#(#args),*] => ::std::option::Option::Some(#body),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    }
+    .into()
+}
@@ -1,4 +1,4 @@
-use ev_stream_gtk_rs::ev_stream;
+use ev_stream_gtk_rs::{ev_stream, widgets, EvStreamExt};
 use futures::future::RemoteHandle;
 use futures::join;
 use futures::prelude::*;
@@ -7,10 +7,7 @@ use gtk::glib;
 use gtk::prelude::*;
 use std::time::Duration;
 
-fn search_in_background(
-    text: String,
-    search_status_label: gtk::Label,
-) -> Option<RemoteHandle<()>> {
+fn search_in_background(text: String, search_status_label: gtk::Label) -> Option<RemoteHandle<()>> {
     glib::MainContext::default()
         .spawn_local_with_handle(async move {
             search_status_label.set_text(&format!("Searching {}", text));
@@ -40,18 +37,23 @@ fn on_activate(application: &gtk::Application) {
             })
         });
 
-    // This can probably be implemented using a `debounce` adapter (currently missing from
-    // `futures` crate).
-    // The `RemoteHandle` ensures oldest searches get cancelled when a new one comes.
-    let searches_fut = ev_stream!(entry, search_changed, |entry|).fold(
-        None::<RemoteHandle<()>>,
-        move |_state, entry| {
-            future::ready(search_in_background(
-                entry.text().to_string(),
-                search_status_label.clone(),
-            ))
-        },
-    );
+    // Debounced like `search_changed`, instead of a new search on every keystroke.
+    // The `RemoteHandle` ensures oldest searches get cancelled when a new one comes, or
+    // when the search is stopped (by pressing Escape).
+    let texts = widgets::entry_text_stream(&entry)
+        .debounce(Duration::from_millis(150))
+        .map(Some);
+    let stops = widgets::search_stopped_stream(&entry).map(|()| None);
+    let searches_fut =
+        stream::select(texts, stops).fold(None::<RemoteHandle<()>>, move |_state, text| {
+            future::ready(match text {
+                Some(text) => search_in_background(text, search_status_label.clone()),
+                None => {
+                    search_status_label.set_text("");
+                    None
+                }
+            })
+        });
 
     glib::MainContext::default().spawn_local(async move {
         join!(clicks_fut, searches_fut);
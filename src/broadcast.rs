@@ -0,0 +1,367 @@
+//! Sharing the items of a stream between several consumers.
+use crate::channel::{self, Receiver, Sender};
+use crate::{Connection, EvStream, EvStreamControl};
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll, Waker};
+use futures_util::StreamExt;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+struct Subscribers<T> {
+    senders: Vec<Sender<T>>,
+    replay: VecDeque<T>,
+    replay_len: usize,
+    // Cleared once the source ended: new subscribers get the replay, then end.
+    feeding: bool,
+}
+
+/// Delivers every item of an [EvStream] to each of its subscribers.
+///
+/// Created with [EvStream::broadcast]. The source is consumed by a task spawned on the
+/// thread-default `MainContext`, so items flow even when nobody subscribed. Dropping the
+/// `Broadcast` (and all its clones) aborts the task, disconnecting the source; the
+/// subscribers then end after their buffered items.
+#[derive(Clone)]
+pub struct Broadcast<T> {
+    shared: Rc<Shared<T>>,
+}
+
+struct Shared<T> {
+    subscribers: Rc<RefCell<Subscribers<T>>>,
+    connection: Rc<Connection>,
+    task: glib::JoinHandle<()>,
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<T: Clone + 'static> Broadcast<T> {
+    pub(crate) fn new(mut source: EvStream<T>) -> Self {
+        let connection = source.connection.clone();
+        let subscribers = Rc::new(RefCell::new(Subscribers {
+            senders: Vec::new(),
+            replay: VecDeque::new(),
+            replay_len: 0,
+            feeding: true,
+        }));
+        let task = glib::MainContext::ref_thread_default().spawn_local({
+            let subscribers = subscribers.clone();
+            async move {
+                while let Some(item) = source.next().await {
+                    let mut subscribers = subscribers.borrow_mut();
+                    subscribers
+                        .senders
+                        .retain(|sender| sender.send(item.clone()).is_ok());
+                    if subscribers.replay_len > 0 {
+                        if subscribers.replay.len() == subscribers.replay_len {
+                            subscribers.replay.pop_front();
+                        }
+                        subscribers.replay.push_back(item);
+                    }
+                }
+                let mut subscribers = subscribers.borrow_mut();
+                subscribers.feeding = false;
+                subscribers.senders.clear();
+            }
+        });
+        Self {
+            shared: Rc::new(Shared {
+                subscribers,
+                connection,
+                task,
+            }),
+        }
+    }
+
+    /// Keeps the latest `n` items, delivered to each new subscriber before the items
+    /// emitted after it subscribed.
+    pub fn replay(self, n: usize) -> Self {
+        {
+            let mut subscribers = self.shared.subscribers.borrow_mut();
+            subscribers.replay_len = n;
+            while subscribers.replay.len() > n {
+                subscribers.replay.pop_front();
+            }
+        }
+        self
+    }
+
+    /// Disconnects the source and ends the subscribed streams, after the items they
+    /// buffered. Streams subscribed later only yield the replayed items.
+    pub fn close(&self) {
+        self.shared.connection.disconnect();
+        self.shared.task.abort();
+        let mut subscribers = self.shared.subscribers.borrow_mut();
+        subscribers.feeding = false;
+        subscribers.senders.clear();
+    }
+
+    /// A new stream receiving the items emitted from now on, preceded by the replayed
+    /// ones if any.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (s, r) = channel::channel();
+        let mut subscribers = self.shared.subscribers.borrow_mut();
+        for item in &subscribers.replay {
+            let _ = s.send(item.clone());
+        }
+        if subscribers.feeding {
+            subscribers.senders.push(s);
+        }
+        r
+    }
+}
+
+/// Aborts the task feeding the halves of [unzip] once both are dropped.
+struct Pump(glib::JoinHandle<()>);
+
+impl Drop for Pump {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// One of the streams returned by [unzip_ev](crate::EvStreamExt::unzip_ev).
+#[must_use = "streams do nothing unless polled"]
+pub struct Unzip<T> {
+    receiver: Receiver<T>,
+    _pump: Rc<Pump>,
+}
+
+impl<T> Stream for Unzip<T> {
+    type Item = T;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.receiver.size_hint()
+    }
+}
+
+pub(crate) fn unzip<S, A, B>(stream: S) -> (Unzip<A>, Unzip<B>)
+where
+    S: Stream<Item = (A, B)> + 'static,
+    A: 'static,
+    B: 'static,
+{
+    let (left, left_receiver) = channel::channel();
+    let (right, right_receiver) = channel::channel();
+    let task = glib::MainContext::ref_thread_default().spawn_local(async move {
+        let mut stream = std::pin::pin!(stream);
+        while let Some((a, b)) = stream.next().await {
+            let left_alive = left.send(a).is_ok();
+            let right_alive = right.send(b).is_ok();
+            if !left_alive && !right_alive {
+                break;
+            }
+        }
+    });
+    let pump = Rc::new(Pump(task));
+    (
+        Unzip {
+            receiver: left_receiver,
+            _pump: pump.clone(),
+        },
+        Unzip {
+            receiver: right_receiver,
+            _pump: pump,
+        },
+    )
+}
+
+/// Progress of the first item of [split_first](crate::EvStreamExt::split_first).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum First {
+    Pending,
+    Taken,
+    // The future was dropped before resolving: the remainder skips the first item.
+    Abandoned,
+}
+
+struct Split<S> {
+    stream: RefCell<Pin<Box<S>>>,
+    first: Cell<First>,
+    // The remainder, polled before the first item was taken.
+    rest_waker: Cell<Option<Waker>>,
+}
+
+/// `Future` returned by [split_first](crate::EvStreamExt::split_first).
+#[must_use = "futures do nothing unless polled"]
+pub struct SplitFirst<S> {
+    split: Rc<Split<S>>,
+}
+
+/// `Stream` returned by [split_first](crate::EvStreamExt::split_first).
+#[must_use = "streams do nothing unless polled"]
+pub struct SplitRest<S> {
+    split: Rc<Split<S>>,
+}
+
+impl<S: Stream> Future for SplitFirst<S> {
+    type Output = Option<S::Item>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let split = &self.split;
+        if split.first.get() != First::Pending {
+            return Poll::Ready(None);
+        }
+        let item = futures_core::ready!(split.stream.borrow_mut().as_mut().poll_next(cx));
+        split.first.set(First::Taken);
+        if let Some(waker) = split.rest_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<S> Drop for SplitFirst<S> {
+    fn drop(&mut self) {
+        if self.split.first.get() == First::Pending {
+            self.split.first.set(First::Abandoned);
+            if let Some(waker) = self.split.rest_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<S: Stream> Stream for SplitRest<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let split = &self.split;
+        let mut stream = split.stream.borrow_mut();
+        match split.first.get() {
+            First::Pending => {
+                split.rest_waker.set(Some(cx.waker().clone()));
+                return Poll::Pending;
+            }
+            First::Abandoned => match futures_core::ready!(stream.as_mut().poll_next(cx)) {
+                Some(_) => split.first.set(First::Taken),
+                None => return Poll::Ready(None),
+            },
+            First::Taken => {}
+        }
+        stream.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.split.stream.borrow().size_hint();
+        if self.split.first.get() == First::Taken {
+            (lower, upper)
+        } else {
+            (
+                lower.saturating_sub(1),
+                upper.map(|upper| upper.saturating_sub(1)),
+            )
+        }
+    }
+}
+
+delegate_ev_stream_control!(SplitRest<S>, split.stream.borrow());
+
+pub(crate) fn split_first<S>(stream: S) -> (SplitFirst<S>, SplitRest<S>) {
+    let split = Rc::new(Split {
+        stream: RefCell::new(Box::pin(stream)),
+        first: Cell::new(First::Pending),
+        rest_waker: Cell::new(None),
+    });
+    (
+        SplitFirst {
+            split: split.clone(),
+        },
+        SplitRest { split },
+    )
+}
+
+struct Tee<S: Stream> {
+    stream: RefCell<Pin<Box<S>>>,
+    // Per share: the items it still has to yield, its waker, and whether it's alive.
+    queues: [Cell<VecDeque<S::Item>>; 2],
+    wakers: [Cell<Option<Waker>>; 2],
+    alive: [Cell<bool>; 2],
+    ended: Cell<bool>,
+}
+
+/// One of the streams returned by [share_rc](crate::EvStreamExt::share_rc).
+#[must_use = "streams do nothing unless polled"]
+pub struct SharedEvStream<S: Stream> {
+    tee: Rc<Tee<S>>,
+    side: usize,
+}
+
+impl<S> Stream for SharedEvStream<S>
+where
+    S: Stream,
+    S::Item: Clone,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let (tee, side) = (&self.tee, self.side);
+        let other = 1 - side;
+        let mut queue = tee.queues[side].take();
+        let item = queue.pop_front();
+        tee.queues[side].set(queue);
+        if item.is_some() {
+            return Poll::Ready(item);
+        }
+        if tee.ended.get() {
+            return Poll::Ready(None);
+        }
+        let Poll::Ready(item) = tee.stream.borrow_mut().as_mut().poll_next(cx) else {
+            // The source only wakes the last task which polled it: the other share is
+            // woken by this one when an item comes.
+            tee.wakers[side].set(Some(cx.waker().clone()));
+            return Poll::Pending;
+        };
+        match &item {
+            Some(item) if tee.alive[other].get() => {
+                let mut queue = tee.queues[other].take();
+                queue.push_back(item.clone());
+                tee.queues[other].set(queue);
+            }
+            Some(_) => {}
+            None => tee.ended.set(true),
+        }
+        if let Some(waker) = tee.wakers[other].take() {
+            waker.wake();
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<S: Stream> Drop for SharedEvStream<S> {
+    fn drop(&mut self) {
+        self.tee.alive[self.side].set(false);
+        self.tee.queues[self.side].take();
+        self.tee.wakers[self.side].take();
+        // The source may only know the waker of this share: the other one polls it
+        // again, to be woken by it from now on.
+        if let Some(waker) = self.tee.wakers[1 - self.side].take() {
+            waker.wake();
+        }
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] SharedEvStream<S>, tee.stream.borrow());
+
+pub(crate) fn share_rc<S: Stream>(stream: S) -> (SharedEvStream<S>, SharedEvStream<S>) {
+    let tee = Rc::new(Tee {
+        stream: RefCell::new(Box::pin(stream)),
+        queues: Default::default(),
+        wakers: Default::default(),
+        alive: [Cell::new(true), Cell::new(true)],
+        ended: Cell::new(false),
+    });
+    (
+        SharedEvStream {
+            tee: tee.clone(),
+            side: 0,
+        },
+        SharedEvStream { tee, side: 1 },
+    )
+}
@@ -0,0 +1,461 @@
+//! Single-threaded channel carrying the items from the connected callback to the [EvStream](crate::EvStream).
+//!
+//! Unlike `futures_channel::mpsc`, the capacity and the overflow policy can be changed
+//! from the receiving side after the callback has been connected.
+//!
+//! All the shared state lives in `Cell`s: the queue is moved out and back in around each
+//! operation, so the hot paths (sending and polling) pay for no borrow tracking and can't
+//! panic on overlapping borrows, whatever the callback or the consumer do.
+//!
+//! The receiver of an [EvStream](crate::EvStream) is only dropped with it, after its
+//! callbacks were disconnected, or blocked until the running emission is over: they never
+//! run again once sending fails. The handlers given away by
+//! [EvStream::into_raw_parts](crate::EvStream::into_raw_parts) are the exception: the
+//! first send failing disconnects them, through [Receiver::on_send_failure].
+//!
+//! Neither path allocates in the steady state: the queue keeps its buffer (a bounded
+//! channel never grows past its capacity), and the waker is only cloned when the
+//! receiver is polled from a different task.
+//!
+//! A [Receiver] can also be made from a `futures_channel::mpsc::UnboundedReceiver`, which
+//! [EvStream::new](crate::EvStream::new) used to take: its items are moved into the
+//! channel whenever the receiver is polled, so the bound and the overflow policy apply
+//! to them too.
+use futures_channel::mpsc;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll, Waker};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+
+/// What to do with a new item when a bounded channel is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Discard the new item.
+    DropNewest,
+    /// Discard the oldest buffered item to make room for the new one.
+    DropOldest,
+}
+
+/// `T::clone`, stored once a subscriber needs copies of the items.
+type CloneItem<T> = fn(&T) -> T;
+
+struct Shared<T> {
+    queue: Cell<VecDeque<T>>,
+    capacity: Cell<Option<usize>>,
+    overflow: Cell<Overflow>,
+    last_send_dropped: Cell<bool>,
+    dropped: Cell<usize>,
+    strict: Cell<bool>,
+    // Other channels getting a copy of each item, see `Receiver::subscribe`.
+    subscribers: Cell<Vec<Sender<T>>>,
+    clone_item: Cell<Option<CloneItem<T>>>,
+    waker: Cell<Option<Waker>>,
+    // Run after the next successful send, see `Receiver::on_next_send`.
+    on_send: Cell<Option<Box<dyn FnOnce()>>>,
+    // Run by the first send failing, see `Receiver::on_send_failure`. Outlives the receiver.
+    on_failure: Cell<Option<Box<dyn FnOnce()>>>,
+    senders: Cell<usize>,
+    receiver_alive: Cell<bool>,
+}
+
+/// Number of items buffered in an unbounded channel above which a warning is logged,
+/// with the `debug-subscriptions` feature: the consumer is likely stuck or dead.
+#[cfg(feature = "debug-subscriptions")]
+pub const DIVERGENCE_WARNING_THRESHOLD: usize = 1024;
+
+/// Sending half, moved inside the connected callback.
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+/// Receiving half, owned by the [EvStream](crate::EvStream).
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+    // Kept out of `Shared`, which the sender it holds points to.
+    forwarded: Cell<Option<Forwarded<T>>>,
+}
+
+/// `mpsc` channel forwarded into a [Receiver], see its `From` impl.
+struct Forwarded<T> {
+    receiver: mpsc::UnboundedReceiver<T>,
+    sender: Sender<T>,
+}
+
+/// Creates new [Sender]s for a channel, as long as it's open. Doesn't keep it alive.
+pub(crate) struct SenderSource<T> {
+    shared: Weak<Shared<T>>,
+}
+
+impl<T> SenderSource<T> {
+    /// A new sender, unless the receiver was dropped or all the senders already were.
+    pub(crate) fn sender(&self) -> Option<Sender<T>> {
+        let shared = self.shared.upgrade()?;
+        if !shared.receiver_alive.get() || shared.senders.get() == 0 {
+            return None;
+        }
+        shared.senders.set(shared.senders.get() + 1);
+        Some(Sender { shared })
+    }
+}
+
+/// Error returned by [Sender::send] when the [Receiver] was dropped.
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a closed channel")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+/// Error returned by [Receiver::try_recv] once no item can arrive anymore.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// All the senders were dropped and the buffered items were consumed.
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("receiving on a closed and empty channel")
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
+/// Creates an unbounded channel.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(Shared {
+        queue: Cell::new(VecDeque::new()),
+        capacity: Cell::new(None),
+        overflow: Cell::new(Overflow::DropNewest),
+        last_send_dropped: Cell::new(false),
+        dropped: Cell::new(0),
+        strict: Cell::new(false),
+        subscribers: Cell::new(Vec::new()),
+        clone_item: Cell::new(None),
+        waker: Cell::new(None),
+        on_send: Cell::new(None),
+        on_failure: Cell::new(None),
+        senders: Cell::new(1),
+        receiver_alive: Cell::new(true),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver {
+            shared,
+            forwarded: Cell::new(None),
+        },
+    )
+}
+
+impl<T> Sender<T> {
+    /// Buffers `item`, applying the overflow policy if the channel is full, or discards it
+    /// while a [GlobalPause](crate::GlobalPause) dropping the items is held.
+    /// Fails only if the receiver was dropped.
+    ///
+    /// # Panics
+    /// Instead of failing, if the channel was made strict with [Receiver::set_strict],
+    /// unless the `catch-send-panic` feature is enabled.
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        let shared = &self.shared;
+        if !shared.receiver_alive.get() {
+            if let Some(on_failure) = shared.on_failure.take() {
+                on_failure();
+            }
+            #[cfg(not(feature = "catch-send-panic"))]
+            assert!(
+                !shared.strict.get(),
+                "item of type `{}` sent after its stream was dropped",
+                std::any::type_name::<T>()
+            );
+            #[cfg(feature = "catch-send-panic")]
+            if shared.strict.get() {
+                glib::g_warning!(
+                    "ev-stream-gtk-rs",
+                    "item of type `{}` sent after its stream was dropped",
+                    std::any::type_name::<T>()
+                );
+            }
+            return Err(SendError(item));
+        }
+        if crate::pause::drops_items() {
+            return Ok(());
+        }
+        let mut subscribers = shared.subscribers.take();
+        if let (false, Some(clone_item)) = (subscribers.is_empty(), shared.clone_item.get()) {
+            subscribers.retain(|subscriber| subscriber.send(clone_item(&item)).is_ok());
+        }
+        shared.subscribers.set(subscribers);
+        let mut queue = shared.queue.take();
+        let dropped = match shared.capacity.get() {
+            Some(capacity) if queue.len() >= capacity => {
+                if shared.overflow.get() == Overflow::DropOldest {
+                    queue.pop_front();
+                    queue.push_back(item);
+                }
+                true
+            }
+            _ => {
+                queue.push_back(item);
+                false
+            }
+        };
+        #[cfg(feature = "debug-subscriptions")]
+        if shared.capacity.get().is_none() && queue.len() == DIVERGENCE_WARNING_THRESHOLD {
+            glib::g_warning!(
+                "ev-stream-gtk-rs",
+                "{} items of type `{}` emitted but not consumed: is the stream still polled?",
+                queue.len(),
+                std::any::type_name::<T>()
+            );
+        }
+        shared.queue.set(queue);
+        shared.last_send_dropped.set(dropped);
+        if dropped {
+            shared.dropped.set(shared.dropped.get() + 1);
+        }
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+        if let Some(on_send) = shared.on_send.take() {
+            on_send();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.set(self.shared.senders.get() + 1);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let senders = self.shared.senders.get() - 1;
+        self.shared.senders.set(senders);
+        if senders == 0 {
+            self.shared.subscribers.take();
+            if let Some(waker) = self.shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Bounds the channel to `capacity` items, handling the excess with `overflow`.
+    /// Applies to the items sent from now on.
+    pub fn set_bound(&self, capacity: usize, overflow: Overflow) {
+        assert!(capacity > 0, "channel capacity must be at least 1");
+        self.shared.capacity.set(Some(capacity));
+        self.shared.overflow.set(overflow);
+    }
+
+    /// Makes [Sender::send] panic when the receiver was dropped, instead of failing,
+    /// which the callbacks ignore. Meant to catch, during development, callbacks which
+    /// outlive their stream.
+    ///
+    /// With the `catch-send-panic` feature, it logs a warning instead, and the send fails
+    /// as usual: the handlers given away by
+    /// [EvStream::into_raw_parts](crate::EvStream::into_raw_parts) are disconnected.
+    pub fn set_strict(&self, strict: bool) {
+        self.shared.strict.set(strict);
+    }
+
+    /// The bound set with [set_bound](Receiver::set_bound), `None` if unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.shared.capacity.get()
+    }
+
+    /// Pre-allocates room for `additional` more items.
+    pub fn reserve(&self, additional: usize) {
+        let mut queue = self.shared.queue.take();
+        queue.reserve(additional);
+        self.shared.queue.set(queue);
+    }
+
+    /// Takes the next buffered item without waiting, `Ok(None)` if there is none yet or
+    /// a [GlobalPause](crate::GlobalPause) is held.
+    pub fn try_recv(&self) -> Result<Option<T>, TryRecvError> {
+        if crate::pause::is_paused() {
+            return Ok(None);
+        }
+        self.forward_ready();
+        let mut queue = self.shared.queue.take();
+        let item = queue.pop_front();
+        self.shared.queue.set(queue);
+        match item {
+            Some(item) => Ok(Some(item)),
+            None if self.is_closed() => Err(TryRecvError::Disconnected),
+            None => Ok(None),
+        }
+    }
+
+    /// A new receiver getting a clone of each item sent from now on, besides this one.
+    /// It ends once this channel is closed or this receiver dropped, after the items it
+    /// buffered.
+    pub fn subscribe(&self) -> Receiver<T>
+    where
+        T: Clone,
+    {
+        let (s, r) = channel();
+        if !self.is_closed() {
+            self.shared.clone_item.set(Some(T::clone));
+            let mut subscribers = self.shared.subscribers.take();
+            subscribers.push(s);
+            self.shared.subscribers.set(subscribers);
+        }
+        r
+    }
+
+    /// Calls `f` once the next item is sent, from inside the sending callback.
+    pub(crate) fn on_next_send(&self, f: impl FnOnce() + 'static) {
+        self.shared.on_send.set(Some(Box::new(f)));
+    }
+
+    /// Calls `f` once a send fails because the receiver was dropped, from inside the
+    /// sending callback.
+    pub(crate) fn on_send_failure(&self, f: impl FnOnce() + 'static) {
+        self.shared.on_failure.set(Some(Box::new(f)));
+    }
+
+    pub(crate) fn sender_source(&self) -> SenderSource<T> {
+        SenderSource {
+            shared: Rc::downgrade(&self.shared),
+        }
+    }
+
+    /// Whether all the senders were dropped, so no new item can arrive.
+    pub fn is_closed(&self) -> bool {
+        self.forward_ready();
+        self.shared.senders.get() == 0
+    }
+
+    /// Sends the items of the forwarded `mpsc` channel, if any, as long as `next` yields
+    /// them. Once that channel ended, its sender is dropped, closing this one.
+    fn forward(&self, mut next: impl FnMut(&mut mpsc::UnboundedReceiver<T>) -> Poll<Option<T>>) {
+        let Some(mut forwarded) = self.forwarded.take() else {
+            return;
+        };
+        while let Poll::Ready(item) = next(&mut forwarded.receiver) {
+            let Some(item) = item else {
+                return;
+            };
+            let _ = forwarded.sender.send(item);
+        }
+        self.forwarded.set(Some(forwarded));
+    }
+
+    /// [forward](Receiver::forward) without registering for a wakeup.
+    fn forward_ready(&self) {
+        // Polling would replace the waker of the task. `try_recv`, replacing `try_next`,
+        // needs futures-channel 0.3.31.
+        #[allow(deprecated)]
+        self.forward(|receiver| match receiver.try_next() {
+            Ok(item) => Poll::Ready(item),
+            Err(_) => Poll::Pending,
+        });
+    }
+
+    /// Whether the most recent send had to drop an item because the channel was full.
+    pub fn last_send_dropped(&self) -> bool {
+        self.shared.last_send_dropped.get()
+    }
+
+    /// Reads the number of buffered items, for as long as the channel exists.
+    #[cfg(feature = "debug-subscriptions")]
+    pub(crate) fn pending_probe(&self) -> impl Fn() -> usize + 'static
+    where
+        T: 'static,
+    {
+        let shared = Rc::downgrade(&self.shared);
+        move || {
+            shared.upgrade().map_or(0, |shared| {
+                let queue = shared.queue.take();
+                let len = queue.len();
+                shared.queue.set(queue);
+                len
+            })
+        }
+    }
+
+    /// How many items were dropped because the channel was full, since its creation.
+    pub fn dropped_count(&self) -> usize {
+        self.shared.dropped.get()
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        // Here rather than in the EvStream, so that its subscribers are paused too.
+        if crate::pause::poll_paused(cx) {
+            return Poll::Pending;
+        }
+        self.forward(|receiver| Pin::new(receiver).poll_next(cx));
+        let shared = &self.shared;
+        let mut queue = shared.queue.take();
+        let item = queue.pop_front();
+        shared.queue.set(queue);
+        if item.is_some() {
+            return Poll::Ready(item);
+        }
+        if shared.senders.get() == 0 {
+            return Poll::Ready(None);
+        }
+        // Polled again by the same task most of the time: keep the stored waker then.
+        let waker = match shared.waker.take() {
+            Some(waker) if waker.will_wake(cx.waker()) => waker,
+            _ => cx.waker().clone(),
+        };
+        shared.waker.set(Some(waker));
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.forward_ready();
+        let queue = self.shared.queue.take();
+        let len = queue.len();
+        self.shared.queue.set(queue);
+        if self.shared.senders.get() == 0 {
+            (len, Some(len))
+        } else {
+            (len, None)
+        }
+    }
+}
+
+/// Receives the items sent into `receiver`, e.g. by a callback written for the versions
+/// of [EvStream::new](crate::EvStream::new) taking an `mpsc` channel.
+impl<T> From<mpsc::UnboundedReceiver<T>> for Receiver<T> {
+    fn from(receiver: mpsc::UnboundedReceiver<T>) -> Self {
+        let (sender, this) = channel();
+        this.forwarded.set(Some(Forwarded { receiver, sender }));
+        this
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.set(false);
+        self.shared.queue.take();
+        self.shared.subscribers.take();
+    }
+}
@@ -0,0 +1,133 @@
+//! Virtual time for the timer-based combinators, with the `manual-clock` feature.
+use futures_channel::oneshot;
+use std::cell::Cell;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+thread_local! {
+    // Weak, so that a clock dropped out of order can't stay installed.
+    static INSTALLED: Cell<Option<Weak<Clock>>> = const { Cell::new(None) };
+}
+
+#[derive(Default)]
+struct Clock {
+    now: Cell<Duration>,
+    next_id: Cell<u64>,
+    // Deadline, id and sender of each pending timer.
+    timers: Cell<Vec<(Duration, u64, oneshot::Sender<()>)>>,
+}
+
+/// Replaces the main loop timeouts of the timer-based combinators (`debounce`,
+/// `throttle`, `delay_each`...) created on this thread while it's installed: they fire
+/// when [advance](ManualClock::advance) moves past their deadline, so that tests don't
+/// sleep.
+///
+/// The tasks woken by a timer still run on their `MainContext`, iterate it after
+/// advancing. Only the timers are virtual: the combinators comparing `Instant`s (e.g.
+/// `dedupe_within`) and [EvStream::next_timeout](crate::EvStream::next_timeout) keep
+/// using the real time. Dropping the clock fires its pending timers, which no
+/// [advance](ManualClock::advance) could reach anymore, and restores the clock it
+/// replaced, if that one is still alive. Dropped while a clock installed after it is
+/// still alive, it leaves that one installed.
+///
+/// # Examples
+/// ```ignore
+/// let clock = ManualClock::install();
+/// let mut searches = texts.debounce(Duration::from_millis(300));
+/// clock.advance(Duration::from_millis(300));
+/// ```
+pub struct ManualClock {
+    clock: Rc<Clock>,
+    previous: Option<Weak<Clock>>,
+}
+
+impl ManualClock {
+    /// Installs a new clock, at time zero, on the calling thread.
+    pub fn install() -> Self {
+        let clock = Rc::new(Clock::default());
+        let previous = INSTALLED.with(|installed| installed.replace(Some(Rc::downgrade(&clock))));
+        Self { clock, previous }
+    }
+
+    /// Moves the time forward by `duration`, firing the timers whose deadline is reached,
+    /// earliest first.
+    pub fn advance(&self, duration: Duration) {
+        let now = self.clock.now.get() + duration;
+        self.clock.now.set(now);
+        let mut timers = self.clock.timers.take();
+        timers.sort_by_key(|&(deadline, id, _)| (deadline, id));
+        let pending = timers.split_off(timers.partition_point(|&(deadline, ..)| deadline <= now));
+        self.clock.timers.set(pending);
+        for (_, _, s) in timers {
+            let _ = s.send(());
+        }
+    }
+
+    /// The time elapsed since the clock was installed.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now.get()
+    }
+
+    /// The number of timers which didn't fire yet.
+    pub fn pending_timers(&self) -> usize {
+        let timers = self.clock.timers.take();
+        let count = timers.len();
+        self.clock.timers.set(timers);
+        count
+    }
+}
+
+impl Drop for ManualClock {
+    fn drop(&mut self) {
+        INSTALLED.with(|installed| {
+            let current = installed.take();
+            if current
+                .as_ref()
+                .is_some_and(|current| current.as_ptr() == Rc::as_ptr(&self.clock))
+            {
+                installed.set(self.previous.take());
+            } else {
+                installed.set(current);
+            }
+        });
+    }
+}
+
+/// Timer registered on a [ManualClock], see [schedule].
+pub(crate) struct Scheduled {
+    clock: Weak<Clock>,
+    id: u64,
+}
+
+impl Scheduled {
+    /// Removes the timer from its clock, if it didn't fire.
+    pub(crate) fn cancel(&self) {
+        if let Some(clock) = self.clock.upgrade() {
+            let mut timers = clock.timers.take();
+            timers.retain(|&(_, id, _)| id != self.id);
+            clock.timers.set(timers);
+        }
+    }
+}
+
+/// Registers a timer firing in `duration` on the clock installed on the thread, if any.
+pub(crate) fn schedule(duration: Duration) -> Option<(Scheduled, oneshot::Receiver<()>)> {
+    let clock = INSTALLED
+        .with(|installed| {
+            let clock = installed.take();
+            installed.set(clock.clone());
+            clock
+        })?
+        .upgrade()?;
+    let (s, fired) = oneshot::channel();
+    let id = clock.next_id.get();
+    clock.next_id.set(id + 1);
+    let mut timers = clock.timers.take();
+    timers.push((clock.now.get() + duration, id, s));
+    clock.timers.set(timers);
+    let scheduled = Scheduled {
+        clock: Rc::downgrade(&clock),
+        id,
+    };
+    Some((scheduled, fired))
+}
@@ -0,0 +1,384 @@
+use crate::{EvStream, Overflow};
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use futures_util::future::Either;
+use pin_project_lite::pin_project;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Resolves with the first item emitted by any of `streams`, or `None` once all of them
+/// ended without emitting.
+///
+/// Every stream is dropped (and so disconnected) as soon as the future resolves.
+pub fn select_first<T>(streams: Vec<EvStream<T>>) -> impl Future<Output = Option<T>> {
+    let mut streams = streams;
+    let mut next = 0;
+    std::future::poll_fn(move |cx| {
+        let item = futures_core::ready!(poll_round_robin(&mut streams, &mut next, cx));
+        streams.clear();
+        Poll::Ready(item)
+    })
+}
+
+/// Resolves with the first item of `stream`, or `None` if the stream ends first, e.g.
+/// because the object was finalized.
+///
+/// The stream is disconnected as soon as the item is sent, without waiting for the
+/// future to be polled, and dropping the future early disconnects it too. See also
+/// [ev_once](crate::ev_once).
+pub fn once<T>(stream: EvStream<T>) -> impl Future<Output = Option<T>> {
+    let connection = Rc::downgrade(&stream.connection);
+    stream.receiver.on_next_send(move || {
+        if let Some(connection) = connection.upgrade() {
+            connection.disconnect();
+        }
+    });
+    select_first(vec![stream.bounded(1, Overflow::DropNewest)])
+}
+
+/// Polls `streams` starting from `next`, so that a busy stream can't starve the others.
+/// Ended streams are removed; yields `None` once all of them ended.
+fn poll_round_robin<T>(
+    streams: &mut Vec<EvStream<T>>,
+    next: &mut usize,
+    cx: &mut Context<'_>,
+) -> Poll<Option<T>> {
+    let mut cursor = *next;
+    let mut pending = 0;
+    while pending < streams.len() {
+        let i = cursor % streams.len();
+        match Pin::new(&mut streams[i]).poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                *next = i + 1;
+                return Poll::Ready(Some(item));
+            }
+            Poll::Ready(None) => {
+                drop(streams.remove(i));
+                cursor = i;
+            }
+            Poll::Pending => {
+                pending += 1;
+                cursor = i + 1;
+            }
+        }
+    }
+    if streams.is_empty() {
+        Poll::Ready(None)
+    } else {
+        Poll::Pending
+    }
+}
+
+/// Interleaves the items of several streams of the same type, polling them in turn.
+///
+/// Ends once all the streams ended. Dropping it disconnects every stream.
+pub fn merge<T>(streams: Vec<EvStream<T>>) -> Merge<T> {
+    Merge { streams, next: 0 }
+}
+
+/// `Stream` returned by [merge].
+#[must_use = "streams do nothing unless polled"]
+pub struct Merge<T> {
+    streams: Vec<EvStream<T>>,
+    next: usize,
+}
+
+impl<T> Stream for Merge<T> {
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        poll_round_robin(&mut this.streams, &mut this.next, cx)
+    }
+}
+
+/// Yields the latest item of each stream, in the order of `streams`, every time one of
+/// them emits, once all of them emitted at least once.
+///
+/// Ends once all the streams ended, or as soon as one ends without having emitted.
+/// Dropping it disconnects every stream.
+pub fn combine_latest_vec<T: Clone>(streams: Vec<EvStream<T>>) -> CombineLatestVec<T> {
+    let latest = streams.iter().map(|_| None).collect();
+    CombineLatestVec {
+        streams: streams.into_iter().map(Some).collect(),
+        latest,
+        next: 0,
+    }
+}
+
+/// `Stream` returned by [combine_latest_vec].
+#[must_use = "streams do nothing unless polled"]
+pub struct CombineLatestVec<T> {
+    // `None` once ended, to keep the indices of the other streams.
+    streams: Vec<Option<EvStream<T>>>,
+    latest: Vec<Option<T>>,
+    next: usize,
+}
+
+// The latest items are never pinned.
+impl<T> Unpin for CombineLatestVec<T> {}
+
+impl<T: Clone> Stream for CombineLatestVec<T> {
+    type Item = Vec<T>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let len = this.streams.len();
+        let mut pending = 0;
+        let mut cursor = this.next;
+        while pending < len {
+            let i = cursor % len;
+            cursor = i + 1;
+            let Some(stream) = &mut this.streams[i] else {
+                pending += 1;
+                continue;
+            };
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    this.latest[i] = Some(item);
+                    pending = 0;
+                    if let Some(latest) = this.latest.iter().cloned().collect() {
+                        this.next = cursor;
+                        return Poll::Ready(Some(latest));
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.streams[i] = None;
+                    if this.latest[i].is_none() {
+                        // Nothing can be combined anymore.
+                        this.streams.clear();
+                        this.latest.clear();
+                        return Poll::Ready(None);
+                    }
+                    pending += 1;
+                }
+                Poll::Pending => pending += 1,
+            }
+        }
+        if this.streams.iter().all(Option::is_none) {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Interleaves two streams of different types, tagging each item with its source.
+///
+/// Ends once both streams ended.
+pub fn merge_either<A, B>(a: EvStream<A>, b: EvStream<B>) -> MergeEither<A, B> {
+    MergeEither {
+        a: Some(a),
+        b: Some(b),
+        b_first: false,
+    }
+}
+
+/// `Stream` returned by [merge_either].
+#[must_use = "streams do nothing unless polled"]
+pub struct MergeEither<A, B> {
+    a: Option<EvStream<A>>,
+    b: Option<EvStream<B>>,
+    // Set once `a` yielded, so that a busy stream can't starve the other one, like
+    // `poll_round_robin`.
+    b_first: bool,
+}
+
+impl<A, B> Stream for MergeEither<A, B> {
+    type Item = Either<A, B>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        for b_turn in [this.b_first, !this.b_first] {
+            let item = if b_turn {
+                poll_side(&mut this.b, cx).map(Either::Right)
+            } else {
+                poll_side(&mut this.a, cx).map(Either::Left)
+            };
+            if item.is_some() {
+                this.b_first = !b_turn;
+                return Poll::Ready(item);
+            }
+        }
+        if this.a.is_none() && this.b.is_none() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Polls `stream` if it didn't end yet, setting it to `None` once it does.
+fn poll_side<T>(stream: &mut Option<EvStream<T>>, cx: &mut Context<'_>) -> Option<T> {
+    let inner = stream.as_mut()?;
+    match Pin::new(inner).poll_next(cx) {
+        Poll::Ready(Some(item)) => Some(item),
+        Poll::Ready(None) => {
+            *stream = None;
+            None
+        }
+        Poll::Pending => None,
+    }
+}
+
+/// Merges two streams of the same type, always yielding the buffered items of `high`
+/// before the ones of `low`.
+///
+/// Useful when an event must take precedence, like a cancellation over the input it
+/// cancels. Ends once both streams ended.
+pub fn merge_with_priority<T>(high: EvStream<T>, low: EvStream<T>) -> MergeWithPriority<T> {
+    MergeWithPriority {
+        high: Some(high),
+        low: Some(low),
+    }
+}
+
+/// `Stream` returned by [merge_with_priority].
+#[must_use = "streams do nothing unless polled"]
+pub struct MergeWithPriority<T> {
+    high: Option<EvStream<T>>,
+    low: Option<EvStream<T>>,
+}
+
+impl<T> Stream for MergeWithPriority<T> {
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        for stream in [&mut this.high, &mut this.low] {
+            if let Some(inner) = stream {
+                match Pin::new(inner).poll_next(cx) {
+                    Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+                    Poll::Ready(None) => *stream = None,
+                    Poll::Pending => {}
+                }
+            }
+        }
+        if this.high.is_none() && this.low.is_none() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Keeps a subscription pointed at the latest object yielded by `objects`.
+///
+/// Every new object is connected with `connect` and the stream of the previous one is
+/// disconnected, so the callback is never attached twice. The items the previous
+/// stream had already buffered are still yielded, before the ones of the new object.
+/// Ends once `objects` ended and the stream of the last object ended too.
+///
+/// # Examples
+/// ```ignore
+/// let clicks = follow_object(buttons, |btn: &gtk::Button| ev_stream!(btn, clicked, |_| ()));
+/// ```
+pub fn follow_object<O, T, St, F>(objects: St, connect: F) -> FollowObject<St, F, T>
+where
+    St: Stream<Item = O>,
+    F: FnMut(&O) -> EvStream<T>,
+{
+    FollowObject {
+        objects,
+        connect,
+        streams: VecDeque::new(),
+        objects_done: false,
+    }
+}
+
+pin_project! {
+    /// `Stream` returned by [follow_object].
+    #[must_use = "streams do nothing unless polled"]
+    pub struct FollowObject<St, F, T> {
+        #[pin]
+        objects: St,
+        connect: F,
+        // The current stream is the last one, the others are disconnected and only
+        // have their buffered items left.
+        streams: VecDeque<EvStream<T>>,
+        objects_done: bool,
+    }
+}
+
+impl<O, T, St, F> Stream for FollowObject<St, F, T>
+where
+    St: Stream<Item = O>,
+    F: FnMut(&O) -> EvStream<T>,
+{
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if !*this.objects_done {
+            while let Poll::Ready(object) = this.objects.as_mut().poll_next(cx) {
+                let Some(object) = object else {
+                    *this.objects_done = true;
+                    break;
+                };
+                if let Some(previous) = this.streams.back() {
+                    previous.disconnect();
+                }
+                this.streams.push_back((this.connect)(&object));
+            }
+        }
+        while let Some(stream) = this.streams.front_mut() {
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+                Poll::Ready(None) => drop(this.streams.pop_front()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        if *this.objects_done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Item of [DropMarkers].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<T> {
+    /// An item of the stream.
+    Item(T),
+    /// That many items were dropped because the stream was full.
+    Dropped(usize),
+}
+
+/// `Stream` returned by [EvStream::with_drop_markers].
+///
+/// The drops are noticed when polling, so an [Event::Dropped] comes right before the
+/// first item received after them, not necessarily at the exact position of the gap.
+#[must_use = "streams do nothing unless polled"]
+pub struct DropMarkers<T> {
+    stream: EvStream<T>,
+    reported: usize,
+}
+
+impl<T> DropMarkers<T> {
+    pub(crate) fn new(stream: EvStream<T>) -> Self {
+        let reported = stream.dropped_count();
+        Self { stream, reported }
+    }
+}
+
+impl<T> Stream for DropMarkers<T> {
+    type Item = Event<T>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let dropped = this.stream.dropped_count();
+        if dropped > this.reported {
+            let count = dropped - this.reported;
+            this.reported = dropped;
+            return Poll::Ready(Some(Event::Dropped(count)));
+        }
+        Pin::new(&mut this.stream)
+            .poll_next(cx)
+            .map(|item| item.map(Event::Item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.stream.size_hint();
+        let markers = usize::from(self.stream.dropped_count() > self.reported);
+        (low + markers, high.map(|high| high + markers))
+    }
+}
+
+delegate_ev_stream_control!([T] DropMarkers<T>, stream);
@@ -0,0 +1,313 @@
+//! Connection state of an [EvStream](crate::EvStream), kept apart from its channel so that
+//! it can be shared with the handles controlling the stream from outside.
+use crate::{Object, SignalHandlerId};
+use glib::prelude::*;
+use glib::thread_guard::ThreadGuard;
+use glib::WeakRef;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of the [Connection::id]s, shared by all the threads.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Callbacks run once, when the stream gets disconnected.
+type Hooks = Cell<Vec<Box<dyn FnOnce()>>>;
+
+/// What has to be undone on the object when disconnecting.
+pub(crate) enum Handle {
+    Signals(Vec<SignalHandlerId>),
+    Teardown(Box<dyn FnOnce(&Object)>),
+    // For sources which aren't an object, see `source_stream`.
+    Unbound(Box<dyn FnOnce()>),
+}
+
+pub(crate) struct Connection {
+    id: u64,
+    // Upcast, which loses nothing the disconnection needs: glib looks the handlers up by
+    // instance and handler id, whatever the class they were connected through.
+    // Both replaced when the stream is rebound to another object.
+    object: Cell<WeakRef<Object>>,
+    // The instance the handlers were connected to, checked before disconnecting them.
+    instance: Cell<*mut glib::gobject_ffi::GObject>,
+    handle: Cell<Option<Handle>>,
+    // Blocking isn't idempotent in glib, unblocking a handler which isn't blocked warns.
+    blocked: Cell<bool>,
+    disconnect_hooks: Rc<Hooks>,
+    finalize_notify: Cell<Option<glib::object::WeakRefNotify<Object>>>,
+    // Handlers on other objects sending into the same channel, following this connection.
+    linked: Cell<Vec<Connection>>,
+}
+
+impl Connection {
+    pub(crate) fn new(object: WeakRef<Object>, handle: Handle) -> Self {
+        let instance = object
+            .upgrade()
+            .map_or(std::ptr::null_mut(), |obj| obj.as_ptr());
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            object: Cell::new(object),
+            instance: Cell::new(instance),
+            handle: Cell::new(Some(handle)),
+            blocked: Cell::new(false),
+            disconnect_hooks: Rc::default(),
+            finalize_notify: Cell::new(None),
+            linked: Cell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn object(&self) -> Option<Object> {
+        let object = self.object.take();
+        let obj = object.upgrade();
+        self.object.set(object);
+        obj
+    }
+
+    pub(crate) fn weak_object(&self) -> WeakRef<Object> {
+        let object = self.object.take();
+        let weak = object.clone();
+        self.object.set(object);
+        weak
+    }
+
+    /// See [EvStream::disconnect](crate::EvStream::disconnect).
+    pub(crate) fn disconnect(&self) {
+        match (self.handle.take(), self.connected_object()) {
+            (Some(Handle::Unbound(release)), _) => release(),
+            (Some(handle), Some(obj)) => self.release(&obj, handle),
+            _ => {}
+        }
+        if let Some(notify) = self.finalize_notify.take() {
+            notify.disconnect();
+        }
+        for linked in self.linked.take() {
+            linked.disconnect();
+        }
+        run_hooks(&self.disconnect_hooks);
+    }
+
+    /// Makes `linked` be blocked and disconnected along with this connection.
+    pub(crate) fn link(&self, linked: Connection) {
+        let mut all = self.linked.take();
+        linked.set_blocked(self.blocked.get());
+        all.push(linked);
+        self.linked.set(all);
+    }
+
+    /// Replaces the handle of a connected stream with `handle`, undoing the previous
+    /// one. The disconnect hooks don't run. Returns `handle` if the stream isn't
+    /// connected anymore.
+    pub(crate) fn replace_handle(&self, handle: Handle) -> Result<(), Handle> {
+        let Some(obj) = self.connected_object() else {
+            return Err(handle);
+        };
+        let Some(previous) = self.handle.take() else {
+            return Err(handle);
+        };
+        self.handle.set(Some(handle));
+        self.blocked.set(false);
+        self.release(&obj, previous);
+        Ok(())
+    }
+
+    /// Moves a connected stream to `obj`, whose callbacks are undone by `handle`, undoing
+    /// the previous ones. The disconnect hooks now run when `obj` is finalized. Returns
+    /// `handle` if the stream isn't connected anymore.
+    pub(crate) fn rebind(&self, obj: &Object, handle: Handle) -> Result<(), Handle> {
+        let Some(previous_obj) = self.connected_object() else {
+            return Err(handle);
+        };
+        let Some(previous) = self.handle.take() else {
+            return Err(handle);
+        };
+        self.release(&previous_obj, previous);
+        self.object.set(obj.downgrade());
+        self.instance.set(obj.as_ptr());
+        self.handle.set(Some(handle));
+        self.blocked.set(false);
+        if let Some(notify) = self.finalize_notify.take() {
+            notify.disconnect();
+            let hooks = self.disconnect_hooks.clone();
+            self.finalize_notify.set(Some(
+                obj.add_weak_ref_notify_local(move || run_hooks(&hooks)),
+            ));
+        }
+        Ok(())
+    }
+
+    fn release(&self, obj: &Object, handle: Handle) {
+        match handle {
+            Handle::Signals(signal_ids) if is_emitting(obj) => {
+                // The handler may be on the stack of the running emission: block it now,
+                // so it won't send anything anymore, and disconnect it once the emission
+                // is over.
+                for signal_id in &signal_ids {
+                    obj.block_signal(signal_id);
+                }
+                // On the thread-default context, like the timers: the global default one
+                // may be owned by another thread, or not iterated at all.
+                let mut pending =
+                    ThreadGuard::new(Some((obj.downgrade(), self.instance.get(), signal_ids)));
+                let source = glib::idle_source_new(None, glib::Priority::DEFAULT_IDLE, move || {
+                    if let Some((object, instance, signal_ids)) = pending.get_mut().take() {
+                        if let Some(obj) = object.upgrade().filter(|obj| obj.as_ptr() == instance) {
+                            for signal_id in signal_ids {
+                                obj.disconnect(signal_id);
+                            }
+                        }
+                    }
+                    glib::ControlFlow::Break
+                });
+                source.attach(Some(&glib::MainContext::ref_thread_default()));
+            }
+            Handle::Signals(signal_ids) => {
+                for signal_id in signal_ids {
+                    obj.disconnect(signal_id);
+                }
+            }
+            Handle::Teardown(teardown) => teardown(obj),
+            Handle::Unbound(release) => release(),
+        }
+    }
+
+    /// See [EvStream::on_disconnect](crate::EvStream::on_disconnect).
+    pub(crate) fn on_disconnect(&self, f: impl FnOnce() + 'static) {
+        if !self.is_connected() {
+            f();
+            return;
+        }
+        let mut hooks = self.disconnect_hooks.take();
+        hooks.push(Box::new(f));
+        self.disconnect_hooks.set(hooks);
+        let notify = self.finalize_notify.take().or_else(|| {
+            let hooks = self.disconnect_hooks.clone();
+            self.object()
+                .map(|obj| obj.add_weak_ref_notify_local(move || run_hooks(&hooks)))
+        });
+        self.finalize_notify.set(notify);
+    }
+
+    /// Hands the signal handlers over, unblocked, if they are still connected. They won't
+    /// be disconnected by [disconnect](Connection::disconnect) anymore.
+    pub(crate) fn take_signals(&self) -> Option<(Object, Vec<SignalHandlerId>)> {
+        if self.has_linked() {
+            return None;
+        }
+        let obj = self.connected_object()?;
+        self.set_blocked(false);
+        match self.handle.take() {
+            Some(Handle::Signals(signal_ids)) => Some((obj, signal_ids)),
+            handle => {
+                self.handle.set(handle);
+                None
+            }
+        }
+    }
+
+    /// The raw ids of the signal handlers, if the stream was created from signal handlers.
+    pub(crate) fn raw_signal_ids(&self) -> Vec<glib::ffi::gulong> {
+        let handle = self.handle.take();
+        let ids = match &handle {
+            Some(Handle::Signals(signal_ids)) => signal_ids
+                .iter()
+                // SAFETY: only used to check on the handlers, never to disconnect them.
+                .map(|signal_id| unsafe { signal_id.as_raw() })
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.handle.set(handle);
+        ids
+    }
+
+    /// Blocks or unblocks the signal handlers. Other kinds of sources are left alone.
+    pub(crate) fn set_blocked(&self, blocked: bool) {
+        if self.blocked.replace(blocked) == blocked {
+            return;
+        }
+        let handle = self.handle.take();
+        if let (Some(obj), Some(Handle::Signals(signal_ids))) = (self.connected_object(), &handle) {
+            for signal_id in signal_ids {
+                if blocked {
+                    obj.block_signal(signal_id);
+                } else {
+                    obj.unblock_signal(signal_id);
+                }
+            }
+        }
+        self.handle.set(handle);
+        let linked = self.linked.take();
+        for linked in &linked {
+            linked.set_blocked(blocked);
+        }
+        self.linked.set(linked);
+    }
+
+    /// Whether the handlers are still connected to a live object, or any of the linked
+    /// ones are.
+    pub(crate) fn is_connected(&self) -> bool {
+        let handle = self.handle.take();
+        let connected = match handle {
+            Some(Handle::Unbound(_)) => true,
+            Some(_) => self.connected_object().is_some(),
+            None => false,
+        };
+        self.handle.set(handle);
+        let linked = self.linked.take();
+        let connected = connected || linked.iter().any(Connection::is_connected);
+        self.linked.set(linked);
+        connected
+    }
+
+    fn has_linked(&self) -> bool {
+        let linked = self.linked.take();
+        let has_linked = !linked.is_empty();
+        self.linked.set(linked);
+        has_linked
+    }
+
+    /// The object, only if it's still the instance the callbacks were connected to.
+    ///
+    /// The weak reference is cleared when the object is finalized, so this is only a
+    /// guard: a handler id must never be disconnected from another object which could
+    /// have been allocated at the same address.
+    pub(crate) fn connected_object(&self) -> Option<Object> {
+        self.object()
+            .filter(|obj| obj.as_ptr() == self.instance.get())
+    }
+}
+
+fn run_hooks(hooks: &Hooks) {
+    for hook in hooks.take() {
+        hook();
+    }
+}
+
+/// Whether a signal is currently being emitted on `object`.
+fn is_emitting(object: &Object) -> bool {
+    unsafe { !glib::gobject_ffi::g_signal_get_invocation_hint(object.as_ptr()).is_null() }
+}
+
+/// Disconnects the handlers of `object` with the raw ids `signal_ids` which are still
+/// connected. Ids are never reused, so the others were disconnected by their owner.
+pub(crate) fn disconnect_raw_handlers(object: &Object, signal_ids: &[glib::ffi::gulong]) {
+    for &signal_id in signal_ids {
+        unsafe {
+            if glib::gobject_ffi::g_signal_handler_is_connected(object.as_ptr(), signal_id)
+                != glib::ffi::GFALSE
+            {
+                glib::gobject_ffi::g_signal_handler_disconnect(object.as_ptr(), signal_id);
+            }
+        }
+    }
+}
+
+pub(crate) fn handler_is_connected(object: &Object, signal_id: &SignalHandlerId) -> bool {
+    unsafe {
+        glib::gobject_ffi::g_signal_handler_is_connected(object.as_ptr(), signal_id.as_raw())
+            != glib::ffi::GFALSE
+    }
+}
@@ -0,0 +1,124 @@
+//! Consuming the signals of an object from another thread, and the other way around.
+use crate::channel::SendError;
+use crate::connection::Handle;
+use crate::EvStream;
+use futures_channel::{mpsc, oneshot};
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use futures_util::StreamExt;
+use std::pin::Pin;
+
+/// `Send` stream returned by [cross_thread_stream], receiving the items of an
+/// [EvStream] living on another thread.
+#[must_use = "streams do nothing unless polled"]
+pub struct CrossThreadStream<T> {
+    items: mpsc::UnboundedReceiver<T>,
+    // Dropped with the stream, which makes the forwarding task disconnect the source.
+    _consumer: oneshot::Receiver<()>,
+}
+
+impl<T> Stream for CrossThreadStream<T> {
+    type Item = T;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.items.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+/// Runs `connect` on the thread owning `ctx`, and forwards the items of the returned
+/// [EvStream] to the returned stream, which can be consumed from any thread.
+///
+/// The object must belong to the thread owning `ctx`: `connect` usually upgrades a
+/// `glib::SendWeakRef` created there, returning `None` if the object is gone. Nothing
+/// is connected until `ctx` is iterated, unless the calling thread owns it. Dropping
+/// the returned stream disconnects the `EvStream` from the thread owning `ctx`.
+pub fn cross_thread_stream<T, F>(ctx: &glib::MainContext, connect: F) -> CrossThreadStream<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Option<EvStream<T>> + Send + 'static,
+{
+    let (s, items) = mpsc::unbounded();
+    let (consumer_alive, consumer) = oneshot::channel();
+    let owner = ctx.clone();
+    ctx.invoke(move || {
+        if let Some(source) = connect() {
+            owner.spawn_local(forward(source, s, consumer_alive));
+        }
+    });
+    CrossThreadStream {
+        items,
+        _consumer: consumer,
+    }
+}
+
+async fn forward<T>(
+    source: EvStream<T>,
+    s: mpsc::UnboundedSender<T>,
+    mut consumer_alive: oneshot::Sender<()>,
+) {
+    let mut source = source.take_until(consumer_alive.cancellation());
+    while let Some(item) = source.next().await {
+        if s.unbounded_send(item).is_err() {
+            break;
+        }
+    }
+}
+
+/// Sending half of [main_thread_channel], usable from any thread.
+pub struct MainThreadSender<T> {
+    items: mpsc::UnboundedSender<T>,
+}
+
+impl<T> Clone for MainThreadSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl<T> MainThreadSender<T> {
+    /// Queues `item` for the stream. Fails once the stream was dropped or disconnected.
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        self.items
+            .unbounded_send(item)
+            .map_err(|err| SendError(err.into_inner()))
+    }
+}
+
+/// A sender for other threads (e.g. workers reporting to the UI), and the [EvStream] of
+/// the items it sends, consumed on the calling thread.
+///
+/// No signal is involved: the items are moved into the stream by a task spawned on the
+/// thread-default `MainContext`, which disconnecting or dropping the stream aborts. The
+/// stream ends once all the senders are dropped. [EvStream::object] returns `None`.
+///
+/// # Examples
+/// ```ignore
+/// let (progress, updates) = main_thread_channel::<f64>();
+/// std::thread::spawn(move || {
+///     for step in 0..10 {
+///         let _ = progress.send(step as f64 / 10.0);
+///     }
+/// });
+/// ```
+pub fn main_thread_channel<T: Send + 'static>() -> (MainThreadSender<T>, EvStream<T>) {
+    let (items, mut received) = mpsc::unbounded();
+    let (s, r) = crate::channel::channel();
+    let task = glib::MainContext::ref_thread_default().spawn_local(async move {
+        while let Some(item) = received.next().await {
+            if s.send(item).is_err() {
+                break;
+            }
+        }
+    });
+    let stream = EvStream::with_handle(
+        glib::WeakRef::new(),
+        Handle::Unbound(Box::new(move || task.abort())),
+        r,
+    );
+    (MainThreadSender { items }, stream)
+}
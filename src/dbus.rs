@@ -0,0 +1,52 @@
+//! Streams of D-Bus signals, with the `gio` feature.
+use crate::{channel, EvStream, Object};
+use gio::prelude::*;
+
+/// Stream of the parameters of the D-Bus signals received on `connection` and matching
+/// the given rules, `None` matching anything, as for `DBusConnection::signal_subscribe`.
+///
+/// Dropping or disconnecting the stream unsubscribes, like it disconnects a glib signal
+/// handler. The signals are delivered on the thread-default `MainContext` of the calling
+/// thread.
+///
+/// # Examples
+/// ```ignore
+/// let name_changes = dbus_signal_stream(
+///     &connection,
+///     Some("org.freedesktop.DBus"),
+///     Some("org.freedesktop.DBus"),
+///     Some("NameOwnerChanged"),
+///     Some("/org/freedesktop/DBus"),
+/// );
+/// ```
+pub fn dbus_signal_stream(
+    connection: &gio::DBusConnection,
+    sender: Option<&str>,
+    interface: Option<&str>,
+    member: Option<&str>,
+    object_path: Option<&str>,
+) -> EvStream<glib::Variant> {
+    let (s, r) = channel::channel();
+    // `signal_subscribe` and `signal_unsubscribe` are deprecated for this guard, which
+    // unsubscribes on drop. Weak so that, like a signal handler, it doesn't keep the
+    // connection alive.
+    let subscription = connection
+        .subscribe_to_signal(
+            sender,
+            interface,
+            member,
+            object_path,
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |signal| {
+                crate::__no_unwind(|| {
+                    let _ = s.send(signal.parameters.clone());
+                })
+            },
+        )
+        .downgrade();
+    let object = connection.upcast_ref::<Object>().downgrade();
+    let teardown = move |_: &Object| drop(subscription);
+    EvStream::with_teardown(object, teardown, r)
+        .with_debug_name(&format!("dbus::{}", member.unwrap_or("*")))
+}
@@ -0,0 +1,63 @@
+//! Registry of the live subscriptions, with the `debug-subscriptions` feature.
+use crate::connection::Connection;
+use std::cell::RefCell;
+use std::rc::Weak;
+
+/// State of a live subscription, returned by [debug_snapshot].
+#[derive(Clone, Debug)]
+pub struct SubscriptionInfo {
+    /// See [EvStream::id](crate::EvStream::id).
+    pub id: u64,
+    /// Type name of the object, as it was when the stream was created.
+    pub object_type: &'static str,
+    /// Name of the signal, as written when creating the stream.
+    pub signal: String,
+    pub is_connected: bool,
+    /// Items buffered and not consumed yet.
+    pub pending: usize,
+}
+
+struct Entry {
+    object_type: &'static str,
+    signal: String,
+    connection: Weak<Connection>,
+    pending: Box<dyn Fn() -> usize>,
+}
+
+thread_local! {
+    static SUBSCRIPTIONS: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn register(
+    object_type: &'static str,
+    signal: &str,
+    connection: Weak<Connection>,
+    pending: Box<dyn Fn() -> usize>,
+) {
+    let entry = Entry {
+        object_type,
+        signal: signal.replace('"', ""),
+        connection,
+        pending,
+    };
+    SUBSCRIPTIONS.with(|subscriptions| subscriptions.borrow_mut().push(entry));
+}
+
+/// Lists the streams created on this thread which weren't dropped yet.
+pub fn debug_snapshot() -> Vec<SubscriptionInfo> {
+    SUBSCRIPTIONS.with(|subscriptions| {
+        let mut subscriptions = subscriptions.borrow_mut();
+        subscriptions.retain(|entry| entry.connection.strong_count() > 0);
+        subscriptions
+            .iter()
+            .filter_map(|entry| Some((entry, entry.connection.upgrade()?)))
+            .map(|(entry, connection)| SubscriptionInfo {
+                id: connection.id(),
+                object_type: entry.object_type,
+                signal: entry.signal.clone(),
+                is_connected: connection.is_connected(),
+                pending: (entry.pending)(),
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,1518 @@
+use crate::broadcast::{SharedEvStream, SplitFirst, SplitRest, Unzip};
+use crate::channel::{self, SendError};
+use crate::timer::{
+    BatchPerIteration, ChunksTimeout, Debounce, DelayEach, EndAfter, SettleMarker, Throttle,
+    WithDeadline,
+};
+use crate::{DynEvStream, EvStreamControl, Object};
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll, Waker};
+use pin_project_lite::pin_project;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Combinators useful for streams of gtk events.
+pub trait EvStreamExt: Stream {
+    /// Yields an item only once `duration` passed without any newer item.
+    /// The timer runs on the thread-default `MainContext`.
+    fn debounce(self, duration: Duration) -> Debounce<Self>
+    where
+        Self: Sized,
+    {
+        self.debounce_on(duration, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [debounce](EvStreamExt::debounce), running the timer on `ctx`.
+    fn debounce_on(self, duration: Duration, ctx: &glib::MainContext) -> Debounce<Self>
+    where
+        Self: Sized,
+    {
+        Debounce::new(self, duration, ctx.clone())
+    }
+
+    /// Yields every item right away, and [Settled::Quiescent](crate::Settled::Quiescent) once `duration` passed
+    /// without any newer item, e.g. to run an expensive update only when a burst of
+    /// events is over. Nothing is emitted before the first item, nor when the stream
+    /// ends. The timer runs on the thread-default `MainContext`.
+    fn settle_marker(self, duration: Duration) -> SettleMarker<Self>
+    where
+        Self: Sized,
+    {
+        self.settle_marker_on(duration, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [settle_marker](EvStreamExt::settle_marker), running the timer on `ctx`.
+    fn settle_marker_on(self, duration: Duration, ctx: &glib::MainContext) -> SettleMarker<Self>
+    where
+        Self: Sized,
+    {
+        SettleMarker::new(self, duration, ctx.clone())
+    }
+
+    /// Waits `duration` before yielding each item, one after the other, e.g. to stagger
+    /// animations. Unlike [debounce](EvStreamExt::debounce), no item is skipped.
+    /// The timer runs on the thread-default `MainContext`.
+    fn delay_each(self, duration: Duration) -> DelayEach<Self>
+    where
+        Self: Sized,
+    {
+        self.delay_each_on(duration, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [delay_each](EvStreamExt::delay_each), running the timer on `ctx`.
+    fn delay_each_on(self, duration: Duration, ctx: &glib::MainContext) -> DelayEach<Self>
+    where
+        Self: Sized,
+    {
+        DelayEach::new(self, duration, ctx.clone())
+    }
+
+    /// Pairs each item with a [Deadline] resolving `duration` after the item was yielded,
+    /// to race its processing against a timeout. The timers run on the thread-default
+    /// `MainContext`.
+    fn with_deadline(self, duration: Duration) -> WithDeadline<Self>
+    where
+        Self: Sized,
+    {
+        self.with_deadline_on(duration, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [with_deadline](EvStreamExt::with_deadline), running the timers on `ctx`.
+    fn with_deadline_on(self, duration: Duration, ctx: &glib::MainContext) -> WithDeadline<Self>
+    where
+        Self: Sized,
+    {
+        WithDeadline::new(self, duration, ctx.clone())
+    }
+
+    /// Ends the stream once `duration` passed since this call, however many items are
+    /// still emitted or buffered, e.g. so that a test waiting on a stream which never
+    /// ends can't hang. The items yielded before are untouched. The timer runs on the
+    /// thread-default `MainContext`, and is removed if the stream ends first.
+    fn end_after(self, duration: Duration) -> EndAfter<Self>
+    where
+        Self: Sized,
+    {
+        self.end_after_on(duration, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [end_after](EvStreamExt::end_after), running the timer on `ctx`.
+    fn end_after_on(self, duration: Duration, ctx: &glib::MainContext) -> EndAfter<Self>
+    where
+        Self: Sized,
+    {
+        EndAfter::new(self, duration, ctx)
+    }
+
+    /// Yields an item, then ignores the following ones until `duration` passed.
+    /// Same as `throttle_opts(duration, true, false)`.
+    fn throttle(self, duration: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        self.throttle_opts(duration, true, false)
+    }
+
+    /// Like [throttle](EvStreamExt::throttle), running the timer on `ctx`.
+    fn throttle_on(self, duration: Duration, ctx: &glib::MainContext) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        self.throttle_opts_on(duration, true, false, ctx)
+    }
+
+    /// Yields an item, then at most the latest one received during each `duration`, so
+    /// the last item of a burst is always delivered, once the window closes.
+    /// Same as `throttle_opts(duration, true, true)`.
+    fn throttle_latest(self, duration: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        self.throttle_opts(duration, true, true)
+    }
+
+    /// Like [throttle_latest](EvStreamExt::throttle_latest), running the timer on `ctx`.
+    fn throttle_latest_on(self, duration: Duration, ctx: &glib::MainContext) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        self.throttle_opts_on(duration, true, true, ctx)
+    }
+
+    /// Lets at most an item through every `duration`, like lodash's `throttle`.
+    ///
+    /// An item arriving when no window is open opens one, and is yielded right away if
+    /// `leading` is set. If `trailing` is set, the latest item received during the window
+    /// is yielded when it closes, opening a new window. With neither flag, nothing is
+    /// yielded. The timer runs on the thread-default `MainContext`.
+    fn throttle_opts(self, duration: Duration, leading: bool, trailing: bool) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        self.throttle_opts_on(
+            duration,
+            leading,
+            trailing,
+            &glib::MainContext::ref_thread_default(),
+        )
+    }
+
+    /// Like [throttle_opts](EvStreamExt::throttle_opts), running the timer on `ctx`.
+    fn throttle_opts_on(
+        self,
+        duration: Duration,
+        leading: bool,
+        trailing: bool,
+        ctx: &glib::MainContext,
+    ) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle::new(self, duration, ctx.clone(), leading, trailing)
+    }
+
+    /// Collects the items in chunks of `max`, yielding a smaller chunk if `max_delay`
+    /// passed since its first item. The last chunk is yielded when the stream ends.
+    /// The timer runs on the thread-default `MainContext`.
+    ///
+    /// # Panics
+    /// If `max` is 0.
+    fn chunks_timeout(self, max: usize, max_delay: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        self.chunks_timeout_on(max, max_delay, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [chunks_timeout](EvStreamExt::chunks_timeout), running the timer on `ctx`.
+    ///
+    /// # Panics
+    /// If `max` is 0.
+    fn chunks_timeout_on(
+        self,
+        max: usize,
+        max_delay: Duration,
+        ctx: &glib::MainContext,
+    ) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        ChunksTimeout::new(self, max, max_delay, ctx.clone())
+    }
+
+    /// Collects the items emitted during a main loop iteration, yielding them together
+    /// once the thread-default `MainContext` becomes idle.
+    fn batch_per_iteration(self) -> BatchPerIteration<Self>
+    where
+        Self: Sized,
+    {
+        self.batch_per_iteration_on(&glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [batch_per_iteration](EvStreamExt::batch_per_iteration), yielding the batches
+    /// once `ctx` becomes idle.
+    fn batch_per_iteration_on(self, ctx: &glib::MainContext) -> BatchPerIteration<Self>
+    where
+        Self: Sized,
+    {
+        BatchPerIteration::new(self, ctx.clone())
+    }
+
+    /// Collapses a backlog to its latest item: when at least `threshold` more items are
+    /// already buffered behind the polled one, only the newest is yielded.
+    ///
+    /// Useful after the main loop was blocked (e.g. by a modal native dialog),
+    /// without coalescing during normal operation like
+    /// [latest_only](crate::EvStream::latest_only) does.
+    /// The backlog is read from `size_hint`, which is exact for an [EvStream](crate::EvStream).
+    fn coalesce_on_resume(self, threshold: usize) -> CoalesceOnResume<Self>
+    where
+        Self: Sized,
+    {
+        CoalesceOnResume {
+            stream: self,
+            threshold,
+            done: false,
+        }
+    }
+
+    /// Skips items equal to the previously yielded one.
+    fn distinct_until_changed(self) -> DistinctUntilChanged<Self>
+    where
+        Self: Sized,
+        Self::Item: PartialEq + Clone,
+    {
+        DistinctUntilChanged {
+            stream: self,
+            last: None,
+        }
+    }
+
+    /// Skips items equal to the previously yielded one if they arrive less than `window`
+    /// after it, like the duplicate emissions some widgets fire.
+    fn dedupe_within(self, window: Duration) -> DedupeWithin<Self>
+    where
+        Self: Sized,
+        Self::Item: PartialEq + Clone,
+    {
+        DedupeWithin {
+            stream: self,
+            window,
+            last: None,
+        }
+    }
+
+    /// Skips items whose key, computed by `f`, equals the key of the previously
+    /// yielded item.
+    fn distinct_by_key<K, F>(self, f: F) -> DistinctByKey<Self, K, F>
+    where
+        Self: Sized,
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        DistinctByKey {
+            stream: self,
+            f,
+            last: None,
+        }
+    }
+
+    /// Lets items through only while the latest value of `gate` is `true`.
+    ///
+    /// The gate starts closed. Items arriving while it's closed are queued and yielded
+    /// once it opens if `buffer` is set, and discarded otherwise. When `gate` ends, its
+    /// latest value stays in effect: if it ends closed, the queued items are discarded too.
+    fn gate<G>(self, gate: G, buffer: bool) -> Gate<Self, G>
+    where
+        Self: Sized,
+        G: Stream<Item = bool>,
+    {
+        Gate {
+            stream: self,
+            gate,
+            buffer,
+            buffered: VecDeque::new(),
+            open: false,
+            gate_done: false,
+            done: false,
+        }
+    }
+
+    /// Splits the stream by the key computed by `f`: yields a `(key, group)` pair the
+    /// first time a key appears, and routes the items with that key to its group.
+    ///
+    /// The groups are only fed while this stream is polled, and end with it. Dropping a
+    /// group discards its items, and the next one with its key starts a new group.
+    fn group_by<K, F>(self, f: F) -> GroupBy<Self, K, F>
+    where
+        Self: Sized,
+        K: Hash + Eq + Clone,
+        F: FnMut(&Self::Item) -> K,
+    {
+        GroupBy {
+            stream: self,
+            f,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Runs `f` on each item with a mutable state, starting from `init`, then yields the
+    /// output of the future it returns.
+    ///
+    /// The next item is only taken once the future completed, serializing the async work
+    /// triggered by the events.
+    fn then_scan<St, Fut, F>(self, init: St, f: F) -> ThenScan<Self, St, Fut, F>
+    where
+        Self: Sized,
+        F: FnMut(&mut St, Self::Item) -> Fut,
+        Fut: Future,
+    {
+        ThenScan {
+            stream: self,
+            state: init,
+            f,
+            future: None,
+        }
+    }
+
+    /// Threads a state through the items like `StreamExt::scan`, yielding what `f` returns
+    /// until it returns `None`, e.g. for a state machine with a final state. The stream
+    /// is dropped then, disconnecting it.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// // Ends after the third click.
+    /// let counts = ev_stream!(button, clicked, |_| ()).scan_until(0, |count, ()| {
+    ///     *count += 1;
+    ///     (*count <= 3).then_some(*count)
+    /// });
+    /// ```
+    fn scan_until<St, U, F>(self, init: St, f: F) -> ScanUntil<Self, St, F>
+    where
+        Self: Sized,
+        F: FnMut(&mut St, Self::Item) -> Option<U>,
+    {
+        ScanUntil {
+            stream: Some(self),
+            state: init,
+            f,
+        }
+    }
+
+    /// Spawns `f(item)` on the thread-default `MainContext` for every item, keeping at
+    /// most `limit` of those tasks running at once (no limit if `None` or 0, like
+    /// `StreamExt::for_each_concurrent`).
+    ///
+    /// The returned future completes once the stream ended and all the tasks completed.
+    /// Dropping it aborts the tasks still running, so cancelling the consumer releases
+    /// everything it started.
+    fn for_each_concurrent_local<Fut, F>(
+        self,
+        limit: impl Into<Option<usize>>,
+        f: F,
+    ) -> ForEachConcurrentLocal<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.for_each_concurrent_local_on(limit, f, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [for_each_concurrent_local](EvStreamExt::for_each_concurrent_local), spawning
+    /// the tasks on `ctx`.
+    fn for_each_concurrent_local_on<Fut, F>(
+        self,
+        limit: impl Into<Option<usize>>,
+        f: F,
+        ctx: &glib::MainContext,
+    ) -> ForEachConcurrentLocal<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        ForEachConcurrentLocal {
+            stream: self,
+            f,
+            limit: limit.into().filter(|&limit| limit > 0),
+            ctx: ctx.clone(),
+            running: Rc::default(),
+            done: false,
+        }
+    }
+
+    /// Spawns `f(item)` on the thread-default `MainContext` for every item, like
+    /// [for_each_concurrent_local](EvStreamExt::for_each_concurrent_local), and yields the
+    /// outputs of those tasks in the order they complete.
+    ///
+    /// At most `limit` tasks run at once (no limit if `None` or 0): the next item is only
+    /// taken once one of them completed. Dropping the stream aborts the tasks still running.
+    fn buffer_unordered_local<Fut, F>(
+        self,
+        limit: impl Into<Option<usize>>,
+        f: F,
+    ) -> BufferUnorderedLocal<Self, Fut::Output, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future + 'static,
+    {
+        self.buffer_unordered_local_on(limit, f, &glib::MainContext::ref_thread_default())
+    }
+
+    /// Like [buffer_unordered_local](EvStreamExt::buffer_unordered_local), spawning the
+    /// tasks on `ctx`.
+    fn buffer_unordered_local_on<Fut, F>(
+        self,
+        limit: impl Into<Option<usize>>,
+        f: F,
+        ctx: &glib::MainContext,
+    ) -> BufferUnorderedLocal<Self, Fut::Output, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future + 'static,
+    {
+        BufferUnorderedLocal {
+            stream: self,
+            f,
+            limit: limit.into().filter(|&limit| limit > 0),
+            ctx: ctx.clone(),
+            running: Rc::default(),
+            outputs: Rc::default(),
+            done: false,
+        }
+    }
+
+    /// Runs `f` on every item, waiting for the future it returns before taking the next
+    /// one, until the stream ends or one of those futures fails.
+    ///
+    /// On the first `Err`, the stream is dropped right away, disconnecting it, and the
+    /// returned future resolves to that error. Being polled by the task awaiting it, it
+    /// needs no spawning, like `StreamExt::for_each`.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// ev_stream!(entry, activate, |e| e.text())
+    ///     .try_for_each_local(|text| save(text))
+    ///     .await?;
+    /// ```
+    fn try_for_each_local<E, Fut, F>(self, f: F) -> TryForEachLocal<Self, Fut, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        TryForEachLocal {
+            stream: Some(self),
+            f,
+            future: None,
+        }
+    }
+
+    /// Splits a stream of pairs into a stream of each component.
+    ///
+    /// The stream is consumed by a task spawned on the thread-default `MainContext`,
+    /// which feeds both halves: a component is discarded once its half is dropped, and
+    /// the stream is dropped with both halves.
+    fn unzip_ev<A, B>(self) -> (Unzip<A>, Unzip<B>)
+    where
+        Self: Stream<Item = (A, B)> + Sized + 'static,
+        A: 'static,
+        B: 'static,
+    {
+        crate::broadcast::unzip(self)
+    }
+
+    /// Splits the stream into a future resolving to its first item, `None` if it ended
+    /// first, and a stream of the items after it, e.g. for a handshake followed by the
+    /// regular events.
+    ///
+    /// The remainder waits until the first item was taken, and skips it if the future was
+    /// dropped before. The stream is dropped, disconnecting it, with both halves.
+    fn split_first(self) -> (SplitFirst<Self>, SplitRest<Self>)
+    where
+        Self: Sized,
+    {
+        crate::broadcast::split_first(self)
+    }
+
+    /// Logs every item with its `Debug` representation, at `level` and for `target`,
+    /// passing it through unchanged. Only with the `log` feature.
+    #[cfg(feature = "log")]
+    fn log_each(self, target: &'static str, level: log::Level) -> crate::LogEach<Self>
+    where
+        Self: Sized,
+        Self::Item: std::fmt::Debug,
+    {
+        crate::LogEach::new(self, target, level)
+    }
+
+    /// Yields the `Ok` values of a stream of results, logging each `Err` at `level` and
+    /// skipping it, e.g. after extracting the arguments of an untyped signal with
+    /// `Value::get`, when a few mismatching emissions are tolerable. Only with the `log`
+    /// feature.
+    #[cfg(feature = "log")]
+    fn unwrap_or_log<T, E>(self, level: log::Level) -> crate::UnwrapOrLog<Self>
+    where
+        Self: Stream<Item = Result<T, E>> + Sized,
+        E: std::fmt::Display,
+    {
+        crate::UnwrapOrLog::new(self, level)
+    }
+
+    /// Splits the stream into two streams yielding each item, e.g. for two processing
+    /// branches of the same task. Unlike [broadcast](crate::EvStream::broadcast), nothing
+    /// is spawned: whichever share is polled first takes the next item from the stream,
+    /// and buffers a clone for the other one, until it's dropped.
+    ///
+    /// The stream is dropped, disconnecting it, with both shares.
+    fn share_rc(self) -> (SharedEvStream<Self>, SharedEvStream<Self>)
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        crate::broadcast::share_rc(self)
+    }
+
+    /// Erases the type of the stream, see [DynEvStream].
+    fn into_dyn(self) -> DynEvStream<Self::Item>
+    where
+        Self: Sized + 'static,
+    {
+        Box::pin(self)
+    }
+
+    /// Maps the `Ok` variant of the items with `f`, leaving errors untouched.
+    fn map_ok<T, E, U, F>(self, f: F) -> MapOk<Self, F>
+    where
+        Self: Stream<Item = Result<T, E>> + Sized,
+        F: FnMut(T) -> U,
+    {
+        MapOk { stream: self, f }
+    }
+
+    /// Maps the `Err` variant of the items with `f`, leaving successes untouched.
+    fn map_err<T, E, U, F>(self, f: F) -> MapErr<Self, F>
+    where
+        Self: Stream<Item = Result<T, E>> + Sized,
+        F: FnMut(E) -> U,
+    {
+        MapErr { stream: self, f }
+    }
+
+    /// Maps the items with `f`, also passing the object the stream is connected to, or
+    /// `None` if it has been finalized by the time the item is received.
+    fn map_with_object<U, F>(self, f: F) -> MapWithObject<Self, F>
+    where
+        Self: EvStreamControl + Sized,
+        F: FnMut(Option<Object>, Self::Item) -> U,
+    {
+        MapWithObject { stream: self, f }
+    }
+
+    /// Converts the items with [Into], e.g. into the message type of the application.
+    fn map_into<U>(self) -> MapInto<Self, U>
+    where
+        Self: Sized,
+        Self::Item: Into<U>,
+    {
+        MapInto {
+            stream: self,
+            _item: PhantomData,
+        }
+    }
+
+    /// Pairs each item with the time elapsed since the previous one, `None` for the first.
+    ///
+    /// The time is taken when the item is yielded: items buffered while the consumer
+    /// was busy come out closer than they were emitted.
+    fn with_interval(self) -> WithInterval<Self>
+    where
+        Self: Sized,
+    {
+        WithInterval {
+            stream: self,
+            last: None,
+        }
+    }
+
+    /// Yields all the items of `before`, then the ones of `self`, e.g. to replay recorded
+    /// events before the live ones. The items emitted meanwhile are buffered by `self`.
+    fn prepend<B>(self, before: B) -> Prepend<Self, B>
+    where
+        Self: Sized,
+        B: Stream<Item = Self::Item>,
+    {
+        Prepend {
+            stream: self,
+            before,
+            before_done: false,
+        }
+    }
+
+    /// Calls `f` with the number of buffered items whenever it's above `threshold` when
+    /// the stream is polled, e.g. to shed load once the consumer falls behind.
+    ///
+    /// The count is the lower bound of `size_hint`, which an [EvStream](crate::EvStream)
+    /// sets to its buffered items.
+    fn on_backlog<F>(self, threshold: usize, f: F) -> OnBacklog<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(usize),
+    {
+        OnBacklog {
+            stream: self,
+            threshold,
+            f,
+        }
+    }
+
+    /// Yields the content of the `Some` items, skipping the `None`s.
+    fn flatten_options<T>(self) -> FlattenOptions<Self>
+    where
+        Self: Stream<Item = Option<T>> + Sized,
+    {
+        FlattenOptions { stream: self }
+    }
+
+    /// Ends as soon as the object is gone, discarding the items still buffered, where
+    /// the stream would otherwise yield them before ending.
+    fn take_while_connected(self) -> TakeWhileConnected<Self>
+    where
+        Self: EvStreamControl + Sized,
+    {
+        TakeWhileConnected {
+            stream: self,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream + ?Sized> EvStreamExt for S {}
+
+pin_project! {
+    /// `Stream` returned by [gate](EvStreamExt::gate).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Gate<S: Stream, G> {
+        #[pin]
+        stream: S,
+        #[pin]
+        gate: G,
+        buffer: bool,
+        buffered: VecDeque<S::Item>,
+        open: bool,
+        gate_done: bool,
+        done: bool,
+    }
+}
+
+impl<S, G> Stream for Gate<S, G>
+where
+    S: Stream,
+    G: Stream<Item = bool>,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if !*this.gate_done {
+            while let Poll::Ready(open) = this.gate.as_mut().poll_next(cx) {
+                match open {
+                    Some(open) => *this.open = open,
+                    None => {
+                        *this.gate_done = true;
+                        break;
+                    }
+                }
+            }
+            if *this.gate_done && !*this.open {
+                // Closed for good: nothing queued could ever be yielded.
+                this.buffered.clear();
+            }
+        }
+        if *this.open {
+            if let Some(item) = this.buffered.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+        }
+        if !*this.done {
+            while let Poll::Ready(next) = this.stream.as_mut().poll_next(cx) {
+                match next {
+                    Some(item) if *this.open => return Poll::Ready(Some(item)),
+                    Some(item) if *this.buffer && !*this.gate_done => {
+                        this.buffered.push_back(item)
+                    }
+                    Some(_) => {}
+                    None => {
+                        *this.done = true;
+                        break;
+                    }
+                }
+            }
+        }
+        // Once the stream ended, wait only for the buffered items which can still be
+        // flushed.
+        if *this.done && (this.buffered.is_empty() || *this.gate_done) {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl, G] Gate<S, G>, stream);
+
+pin_project! {
+    /// `Stream` returned by [group_by](EvStreamExt::group_by).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct GroupBy<S: Stream, K, F> {
+        #[pin]
+        stream: S,
+        f: F,
+        groups: HashMap<K, channel::Sender<S::Item>>,
+    }
+}
+
+impl<S, K, F> Stream for GroupBy<S, K, F>
+where
+    S: Stream,
+    K: Hash + Eq + Clone,
+    F: FnMut(&S::Item) -> K,
+{
+    type Item = (K, channel::Receiver<S::Item>);
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        while let Some(mut item) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+            let key = (this.f)(&item);
+            if let Some(group) = this.groups.get(&key) {
+                match group.send(item) {
+                    Ok(()) => continue,
+                    Err(SendError(rejected)) => {
+                        this.groups.remove(&key);
+                        item = rejected;
+                    }
+                }
+            }
+            let (s, r) = channel::channel();
+            let _ = s.send(item);
+            this.groups.insert(key.clone(), s);
+            return Poll::Ready(Some((key, r)));
+        }
+        // Dropping the senders ends the groups.
+        this.groups.clear();
+        Poll::Ready(None)
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl, K, F] GroupBy<S, K, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [then_scan](EvStreamExt::then_scan).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct ThenScan<S, St, Fut, F> {
+        #[pin]
+        stream: S,
+        state: St,
+        f: F,
+        #[pin]
+        future: Option<Fut>,
+    }
+}
+
+impl<S, St, Fut, F> Stream for ThenScan<S, St, Fut, F>
+where
+    S: Stream,
+    F: FnMut(&mut St, S::Item) -> Fut,
+    Fut: Future,
+{
+    type Item = Fut::Output;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(future) = this.future.as_mut().as_pin_mut() {
+                let output = futures_core::ready!(future.poll(cx));
+                this.future.set(None);
+                return Poll::Ready(Some(output));
+            }
+            let Some(item) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            this.future.set(Some((this.f)(this.state, item)));
+        }
+    }
+}
+
+delegate_ev_stream_control!(ThenScan<S, St, Fut, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [scan_until](EvStreamExt::scan_until).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct ScanUntil<S, St, F> {
+        // Dropped, disconnecting it, once `f` returned `None`.
+        #[pin]
+        stream: Option<S>,
+        state: St,
+        f: F,
+    }
+}
+
+impl<S, St, U, F> Stream for ScanUntil<S, St, F>
+where
+    S: Stream,
+    F: FnMut(&mut St, S::Item) -> Option<U>,
+{
+    type Item = U;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let mut this = self.project();
+        let Some(stream) = this.stream.as_mut().as_pin_mut() else {
+            return Poll::Ready(None);
+        };
+        let output =
+            futures_core::ready!(stream.poll_next(cx)).and_then(|item| (this.f)(this.state, item));
+        if output.is_none() {
+            this.stream.set(None);
+        }
+        Poll::Ready(output)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.stream {
+            Some(stream) => (0, stream.size_hint().1),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+// Disconnected once the stream was dropped.
+impl<S: EvStreamControl, St, F> EvStreamControl for ScanUntil<S, St, F> {
+    fn object(&self) -> Option<Object> {
+        self.stream.as_ref()?.object()
+    }
+    fn disconnect(&self) {
+        if let Some(stream) = &self.stream {
+            stream.disconnect();
+        }
+    }
+    fn is_connected(&self) -> bool {
+        self.stream
+            .as_ref()
+            .is_some_and(|stream| stream.is_connected())
+    }
+}
+
+/// Tasks spawned by [ForEachConcurrentLocal] or [BufferUnorderedLocal] still running, and
+/// the waker to call when one of them completes.
+#[derive(Default)]
+struct Running {
+    tasks: Cell<HashMap<usize, glib::JoinHandle<()>>>,
+    next_id: Cell<usize>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl Running {
+    fn count(&self) -> usize {
+        let tasks = self.tasks.take();
+        let count = tasks.len();
+        self.tasks.set(tasks);
+        count
+    }
+
+    fn insert(&self, id: usize, task: glib::JoinHandle<()>) {
+        let mut tasks = self.tasks.take();
+        tasks.insert(id, task);
+        self.tasks.set(tasks);
+    }
+
+    fn remove(&self, id: usize) {
+        let mut tasks = self.tasks.take();
+        tasks.remove(&id);
+        self.tasks.set(tasks);
+    }
+
+    /// Spawns `task` on `ctx`, counted as running until it completes.
+    fn spawn(self: &Rc<Self>, ctx: &glib::MainContext, task: impl Future<Output = ()> + 'static) {
+        let running = self.clone();
+        let id = running.next_id.get();
+        running.next_id.set(id.wrapping_add(1));
+        // A spawned task only starts from the next main loop iteration, after its handle
+        // has been stored.
+        let handle = ctx.spawn_local(async move {
+            task.await;
+            running.remove(id);
+            if let Some(waker) = running.waker.take() {
+                waker.wake();
+            }
+        });
+        self.insert(id, handle);
+    }
+
+    fn abort_all(&self) {
+        for task in self.tasks.take().into_values() {
+            task.abort();
+        }
+    }
+}
+
+pin_project! {
+    /// `Future` returned by [for_each_concurrent_local](EvStreamExt::for_each_concurrent_local).
+    #[must_use = "futures do nothing unless polled"]
+    pub struct ForEachConcurrentLocal<S, F> {
+        #[pin]
+        stream: S,
+        f: F,
+        limit: Option<usize>,
+        ctx: glib::MainContext,
+        running: Rc<Running>,
+        done: bool,
+    }
+
+    impl<S, F> PinnedDrop for ForEachConcurrentLocal<S, F> {
+        fn drop(this: Pin<&mut Self>) {
+            this.running.abort_all();
+        }
+    }
+}
+
+impl<S, Fut, F> Future for ForEachConcurrentLocal<S, F>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = ()> + 'static,
+{
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut this = self.project();
+        this.running.waker.set(Some(cx.waker().clone()));
+        while !*this.done && this.limit.is_none_or(|limit| this.running.count() < limit) {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => this.running.spawn(this.ctx, (this.f)(item)),
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => break,
+            }
+        }
+        if *this.done && this.running.count() == 0 {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+pin_project! {
+    /// `Stream` returned by [buffer_unordered_local](EvStreamExt::buffer_unordered_local).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct BufferUnorderedLocal<S, U, F> {
+        #[pin]
+        stream: S,
+        f: F,
+        limit: Option<usize>,
+        ctx: glib::MainContext,
+        running: Rc<Running>,
+        // Outputs of the completed tasks, not yielded yet.
+        outputs: Rc<Cell<VecDeque<U>>>,
+        done: bool,
+    }
+
+    impl<S, U, F> PinnedDrop for BufferUnorderedLocal<S, U, F> {
+        fn drop(this: Pin<&mut Self>) {
+            this.running.abort_all();
+        }
+    }
+}
+
+impl<S, Fut, F> Stream for BufferUnorderedLocal<S, Fut::Output, F>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future + 'static,
+{
+    type Item = Fut::Output;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Fut::Output>> {
+        let mut this = self.project();
+        this.running.waker.set(Some(cx.waker().clone()));
+        while !*this.done && this.limit.is_none_or(|limit| this.running.count() < limit) {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let task = (this.f)(item);
+                    let outputs = this.outputs.clone();
+                    this.running.spawn(this.ctx, async move {
+                        let output = task.await;
+                        let mut queue = outputs.take();
+                        queue.push_back(output);
+                        outputs.set(queue);
+                    });
+                }
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => break,
+            }
+        }
+        let mut queue = this.outputs.take();
+        let output = queue.pop_front();
+        this.outputs.set(queue);
+        if output.is_some() {
+            return Poll::Ready(output);
+        }
+        if *this.done && this.running.count() == 0 {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let queue = self.outputs.take();
+        let pending = queue.len() + self.running.count();
+        self.outputs.set(queue);
+        if self.done {
+            return (pending, Some(pending));
+        }
+        let (lower, upper) = self.stream.size_hint();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+delegate_ev_stream_control!(BufferUnorderedLocal<S, U, F>, stream);
+
+pin_project! {
+    /// `Future` returned by [try_for_each_local](EvStreamExt::try_for_each_local).
+    #[must_use = "futures do nothing unless polled"]
+    pub struct TryForEachLocal<S, Fut, F> {
+        // Dropped, disconnecting it, as soon as a future fails.
+        #[pin]
+        stream: Option<S>,
+        f: F,
+        #[pin]
+        future: Option<Fut>,
+    }
+}
+
+impl<S, E, Fut, F> Future for TryForEachLocal<S, Fut, F>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    type Output = Result<(), E>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            if let Some(future) = this.future.as_mut().as_pin_mut() {
+                let output = futures_core::ready!(future.poll(cx));
+                this.future.set(None);
+                if let Err(err) = output {
+                    this.stream.set(None);
+                    return Poll::Ready(Err(err));
+                }
+            }
+            let Some(stream) = this.stream.as_mut().as_pin_mut() else {
+                return Poll::Ready(Ok(()));
+            };
+            let Some(item) = futures_core::ready!(stream.poll_next(cx)) else {
+                this.stream.set(None);
+                return Poll::Ready(Ok(()));
+            };
+            this.future.set(Some((this.f)(item)));
+        }
+    }
+}
+
+pin_project! {
+    /// `Stream` returned by [map_ok](EvStreamExt::map_ok).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct MapOk<S, F> {
+        #[pin]
+        stream: S,
+        f: F,
+    }
+}
+
+impl<S, T, E, U, F> Stream for MapOk<S, F>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(T) -> U,
+{
+    type Item = Result<U, E>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        Poll::Ready(item.map(|res| res.map(this.f)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(MapOk<S, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [map_with_object](EvStreamExt::map_with_object).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct MapWithObject<S, F> {
+        #[pin]
+        stream: S,
+        f: F,
+    }
+}
+
+impl<S, U, F> Stream for MapWithObject<S, F>
+where
+    S: Stream + EvStreamControl,
+    F: FnMut(Option<Object>, S::Item) -> U,
+{
+    type Item = U;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let mut this = self.project();
+        let item = futures_core::ready!(this.stream.as_mut().poll_next(cx));
+        Poll::Ready(item.map(|item| (this.f)(this.stream.object(), item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(MapWithObject<S, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [map_into](EvStreamExt::map_into).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct MapInto<S, U> {
+        #[pin]
+        stream: S,
+        _item: PhantomData<fn() -> U>,
+    }
+}
+
+impl<S, U> Stream for MapInto<S, U>
+where
+    S: Stream,
+    S::Item: Into<U>,
+{
+    type Item = U;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let item = futures_core::ready!(self.project().stream.poll_next(cx));
+        Poll::Ready(item.map(Into::into))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(MapInto<S, U>, stream);
+
+pin_project! {
+    /// `Stream` returned by [with_interval](EvStreamExt::with_interval).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct WithInterval<S> {
+        #[pin]
+        stream: S,
+        last: Option<Instant>,
+    }
+}
+
+impl<S: Stream> Stream for WithInterval<S> {
+    type Item = (S::Item, Option<Duration>);
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        Poll::Ready(item.map(|item| {
+            let now = Instant::now();
+            let interval = this.last.replace(now).map(|last| now - last);
+            (item, interval)
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(WithInterval<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [prepend](EvStreamExt::prepend).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Prepend<S, B> {
+        #[pin]
+        stream: S,
+        #[pin]
+        before: B,
+        before_done: bool,
+    }
+}
+
+impl<S, B> Stream for Prepend<S, B>
+where
+    S: Stream,
+    B: Stream<Item = S::Item>,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if !*this.before_done {
+            match futures_core::ready!(this.before.poll_next(cx)) {
+                Some(item) => return Poll::Ready(Some(item)),
+                None => *this.before_done = true,
+            }
+        }
+        this.stream.poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.stream.size_hint();
+        if self.before_done {
+            return (low, high);
+        }
+        let (before_low, before_high) = self.before.size_hint();
+        let high = match (before_high, high) {
+            (Some(before_high), Some(high)) => before_high.checked_add(high),
+            _ => None,
+        };
+        (before_low.saturating_add(low), high)
+    }
+}
+
+delegate_ev_stream_control!(Prepend<S, B>, stream);
+
+pin_project! {
+    /// `Stream` returned by [on_backlog](EvStreamExt::on_backlog).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct OnBacklog<S, F> {
+        #[pin]
+        stream: S,
+        threshold: usize,
+        f: F,
+    }
+}
+
+impl<S, F> Stream for OnBacklog<S, F>
+where
+    S: Stream,
+    F: FnMut(usize),
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.project();
+        let pending = this.stream.size_hint().0;
+        if pending > *this.threshold {
+            (this.f)(pending);
+        }
+        this.stream.poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(OnBacklog<S, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [flatten_options](EvStreamExt::flatten_options).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct FlattenOptions<S> {
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S, T> Stream for FlattenOptions<S>
+where
+    S: Stream<Item = Option<T>>,
+{
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut this = self.project();
+        loop {
+            match futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(Some(item)) => return Poll::Ready(Some(item)),
+                Some(None) => continue,
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.stream.size_hint().1)
+    }
+}
+
+delegate_ev_stream_control!(FlattenOptions<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [take_while_connected](EvStreamExt::take_while_connected).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct TakeWhileConnected<S> {
+        #[pin]
+        stream: S,
+        done: bool,
+    }
+}
+
+impl<S: Stream + EvStreamControl> Stream for TakeWhileConnected<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.done || this.stream.object().is_none() {
+            *this.done = true;
+            return Poll::Ready(None);
+        }
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        *this.done = item.is_none();
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, self.stream.size_hint().1)
+        }
+    }
+}
+
+delegate_ev_stream_control!(TakeWhileConnected<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [map_err](EvStreamExt::map_err).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct MapErr<S, F> {
+        #[pin]
+        stream: S,
+        f: F,
+    }
+}
+
+impl<S, T, E, U, F> Stream for MapErr<S, F>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(E) -> U,
+{
+    type Item = Result<T, U>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        Poll::Ready(item.map(|res| res.map_err(this.f)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(MapErr<S, F>, stream);
+
+pin_project! {
+    /// `Stream` returned by [distinct_until_changed](EvStreamExt::distinct_until_changed).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct DistinctUntilChanged<S: Stream> {
+        #[pin]
+        stream: S,
+        last: Option<S::Item>,
+    }
+}
+
+impl<S> Stream for DistinctUntilChanged<S>
+where
+    S: Stream,
+    S::Item: PartialEq + Clone,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        while let Some(item) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+            if this.last.as_ref() != Some(&item) {
+                *this.last = Some(item.clone());
+                return Poll::Ready(Some(item));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] DistinctUntilChanged<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [dedupe_within](EvStreamExt::dedupe_within).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct DedupeWithin<S: Stream> {
+        #[pin]
+        stream: S,
+        window: Duration,
+        last: Option<(S::Item, Instant)>,
+    }
+}
+
+impl<S> Stream for DedupeWithin<S>
+where
+    S: Stream,
+    S::Item: PartialEq + Clone,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        while let Some(item) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+            let now = Instant::now();
+            let duplicate = this
+                .last
+                .as_ref()
+                .is_some_and(|(last, at)| *last == item && now.duration_since(*at) < *this.window);
+            if !duplicate {
+                *this.last = Some((item.clone(), now));
+                return Poll::Ready(Some(item));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] DedupeWithin<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [coalesce_on_resume](EvStreamExt::coalesce_on_resume).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct CoalesceOnResume<S> {
+        #[pin]
+        stream: S,
+        threshold: usize,
+        // The stream ended while draining a backlog: it mustn't be polled again.
+        done: bool,
+    }
+}
+
+impl<S: Stream> Stream for CoalesceOnResume<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        let Some(mut latest) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) else {
+            *this.done = true;
+            return Poll::Ready(None);
+        };
+        if this.stream.size_hint().0 >= *this.threshold {
+            while let Poll::Ready(item) = this.stream.as_mut().poll_next(cx) {
+                match item {
+                    Some(item) => latest = item,
+                    None => {
+                        *this.done = true;
+                        break;
+                    }
+                }
+            }
+        }
+        Poll::Ready(Some(latest))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        (0, self.stream.size_hint().1)
+    }
+}
+
+delegate_ev_stream_control!(CoalesceOnResume<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [distinct_by_key](EvStreamExt::distinct_by_key).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct DistinctByKey<S, K, F> {
+        #[pin]
+        stream: S,
+        f: F,
+        last: Option<K>,
+    }
+}
+
+impl<S, K, F> Stream for DistinctByKey<S, K, F>
+where
+    S: Stream,
+    K: PartialEq,
+    F: FnMut(&S::Item) -> K,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        while let Some(item) = futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+            let key = (this.f)(&item);
+            if this.last.as_ref() != Some(&key) {
+                *this.last = Some(key);
+                return Poll::Ready(Some(item));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+delegate_ev_stream_control!(DistinctByKey<S, K, F>, stream);
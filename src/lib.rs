@@ -1,39 +1,926 @@
 use futures_core::stream::Stream;
 use futures_core::task::{Context, Poll};
-use glib::prelude::*;
-use std::cell::Cell;
+use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
 
+use connection::{disconnect_raw_handlers, handler_is_connected, Connection, Handle};
+
+/// Implements [EvStreamControl] for a wrapper by delegating to its upstream stream, the
+/// `self.<field>` expression. The first form bounds the `S` parameter by
+/// [EvStreamControl], the second takes the generics of the impl in brackets.
+macro_rules! delegate_ev_stream_control {
+    ($ty:ident<S $(, $param:ident)*>, $($field:tt)+) => {
+        delegate_ev_stream_control!(
+            [S: $crate::EvStreamControl $(, $param)*] $ty<S $(, $param)*>, $($field)+
+        );
+    };
+    ([$($generics:tt)*] $ty:ty, $($field:tt)+) => {
+        impl<$($generics)*> $crate::EvStreamControl for $ty {
+            fn object(&self) -> Option<$crate::Object> {
+                self.$($field)+.object()
+            }
+            fn disconnect(&self) {
+                self.$($field)+.disconnect()
+            }
+            fn is_connected(&self) -> bool {
+                self.$($field)+.is_connected()
+            }
+        }
+    };
+}
+
+mod broadcast;
+pub mod channel;
+#[cfg(feature = "manual-clock")]
+mod clock;
+mod combine;
+mod connection;
+mod cross_thread;
+#[cfg(feature = "gio")]
+mod dbus;
+#[cfg(feature = "debug-subscriptions")]
+mod debug;
+mod ext;
+#[cfg(feature = "log")]
+mod logging;
+mod pause;
+mod signal;
+mod sink;
+mod source;
+mod timer;
+#[cfg(feature = "gtk4")]
+pub mod widgets;
+
+pub use broadcast::{Broadcast, SharedEvStream, SplitFirst, SplitRest, Unzip};
+pub use channel::{Overflow, TryRecvError};
+#[cfg(feature = "manual-clock")]
+pub use clock::ManualClock;
+pub use combine::{
+    combine_latest_vec, follow_object, merge, merge_either, merge_with_priority, once,
+    select_first, CombineLatestVec, DropMarkers, Event, FollowObject, Merge, MergeEither,
+    MergeWithPriority,
+};
+pub use cross_thread::{
+    cross_thread_stream, main_thread_channel, CrossThreadStream, MainThreadSender,
+};
+#[cfg(feature = "gio")]
+pub use dbus::dbus_signal_stream;
+#[cfg(feature = "debug-subscriptions")]
+pub use debug::{debug_snapshot, SubscriptionInfo};
+#[cfg(feature = "derive")]
+pub use ev_stream_gtk_rs_derive::FromSignalArgs;
+pub use ext::{
+    BufferUnorderedLocal, CoalesceOnResume, DedupeWithin, DistinctByKey, DistinctUntilChanged,
+    EvStreamExt, FlattenOptions, ForEachConcurrentLocal, Gate, GroupBy, MapErr, MapInto, MapOk,
+    MapWithObject, OnBacklog, Prepend, ScanUntil, TakeWhileConnected, ThenScan, TryForEachLocal,
+    WithInterval,
+};
 pub use futures_channel::mpsc;
+pub use glib;
 pub use glib::{object::Object, SignalHandlerId, WeakRef};
+#[cfg(feature = "log")]
+pub use logging::{LogEach, UnwrapOrLog};
 pub use paste;
+pub use pause::{GlobalPause, PauseMode};
+pub use signal::{
+    check_signal, destroyed, properties_stream, property_stream, property_stream_emit_on_rebind,
+    property_stream_no_echo, raw_signal_stream, signal_args_stream, subclass_signal_stream,
+    typed_signal_stream, DowncastError, FromSignalArgs, UnknownSignalError,
+};
+pub use sink::{emit_stream, property_sink, EmitSink, PropertySink};
+pub use source::{source_stream, RawCallback, SignalSource};
+pub use timer::{
+    BatchPerIteration, ChunksTimeout, Deadline, Debounce, DelayEach, EndAfter, SettleMarker,
+    Settled, Throttle, WithDeadline,
+};
+
+/// Used by the typed form of [ev_stream], whose callback returns what the generated
+/// `connect_<event>` method expects, when it's one of these types.
+#[doc(hidden)]
+pub mod __handler_return {
+    /// The value letting the emission go on as if the handler wasn't connected.
+    pub trait Proceed {
+        fn proceed() -> Self;
+    }
+
+    impl Proceed for () {
+        fn proceed() {}
+    }
+
+    impl Proceed for glib::Propagation {
+        fn proceed() -> Self {
+            glib::Propagation::Proceed
+        }
+    }
+}
+
+/// Used by the default body of [ev_stream], which clones the callback arguments.
+#[doc(hidden)]
+pub mod __clone_arg {
+    /// Nullable object arguments (`Option<&T>`) become `Option<T>`, and the arguments of
+    /// the untyped form (`&[Value]`) a `Vec`: a plain `clone()` would keep the borrowed
+    /// reference. Picked by method resolution before [CloneArg], which needs an autoref.
+    pub trait CloneRefArg {
+        type Owned;
+        fn clone_arg(self) -> Self::Owned;
+    }
+
+    impl<T: Clone> CloneRefArg for Option<&T> {
+        type Owned = Option<T>;
+        fn clone_arg(self) -> Option<T> {
+            self.cloned()
+        }
+    }
+
+    impl<T: Clone> CloneRefArg for &[T] {
+        type Owned = Vec<T>;
+        fn clone_arg(self) -> Vec<T> {
+            self.to_vec()
+        }
+    }
+
+    pub trait CloneArg {
+        type Owned;
+        fn clone_arg(self) -> Self::Owned;
+    }
+
+    impl<T: Clone> CloneArg for &T {
+        type Owned = T;
+        fn clone_arg(self) -> T {
+            self.clone()
+        }
+    }
+}
 
 /// `Stream` of `T` created with the [ev_stream]
 /// Provides automatic callback disconnection on drop.
+///
+/// The callback only pushes into an unbounded channel and never polls the consumer,
+/// so the signal can safely be re-emitted while an item is being processed (e.g.
+/// clicking a button programmatically inside a `clicked` handler). Items are always
+/// delivered in emission order.
+///
+/// An `EvStream` is `Unpin`, so it can be given directly to the combinators requiring it,
+/// like `futures::stream::select_all` over a `Vec<EvStream<T>>`: dropping the combined
+/// stream drops and disconnects each of them. Some combinators yield a stream which isn't
+/// `Unpin` (e.g. holding a future, like [then_scan](EvStreamExt::then_scan)): pin it with
+/// `Box::pin`, or erase its type with [into_dyn](EvStreamExt::into_dyn).
+///
+/// An `EvStream` is bound to the thread of the object it's connected to, and so isn't
+/// `Send`, whatever `T` is: spawn its consumer on the same `MainContext`, e.g. with
+/// `MainContext::spawn_local`, and send the items to other threads instead.
+///
+/// Dropping the consumer releases the stream and everything its combinators hold, their
+/// timers included. For a consumer spawned with `MainContext::spawn_local`, aborting its
+/// `JoinHandle` isn't enough: the task is only freed, and the stream with it, once the
+/// `JoinHandle` is dropped too.
+///
+/// ```compile_fail
+/// fn assert_send<S: Send>(_: S) {}
+/// fn check(stream: ev_stream_gtk_rs::EvStream<u32>) {
+///     assert_send(stream);
+/// }
+/// ```
+#[must_use = "EvStream does nothing unless polled; spawn or await it"]
 pub struct EvStream<T> {
-    object: glib::WeakRef<glib::object::Object>,
-    signal_id: Cell<Option<glib::SignalHandlerId>>,
-    receiver: mpsc::UnboundedReceiver<T>,
+    connection: Rc<Connection>,
+    receiver: channel::Receiver<T>,
+    // Connects the callback to another object, for streams created with `rebindable`.
+    rebinder: Option<Box<Rebinder<T>>>,
+    // `poll_next` only touches the receiver and these flags: the connection state is
+    // reserved to the lifecycle methods.
+    terminated: bool,
+    #[cfg(feature = "debug-subscriptions")]
+    polled: bool,
 }
 
 impl<T> EvStream<T> {
+    /// Wraps the signal handler `signal_id`, connected on `object` and sending into
+    /// `receiver`: usually a [channel::Receiver], or an `mpsc::UnboundedReceiver`.
     pub fn new(
         object: WeakRef<Object>,
         signal_id: SignalHandlerId,
-        receiver: mpsc::UnboundedReceiver<T>
+        receiver: impl Into<channel::Receiver<T>>,
+    ) -> Self {
+        Self::with_signals(object, vec![signal_id], receiver.into())
+    }
+
+    /// Like [EvStream::new], for several signal handlers connected on the same object
+    /// and sending into the same channel.
+    pub fn with_signals(
+        object: WeakRef<Object>,
+        signal_ids: Vec<SignalHandlerId>,
+        receiver: channel::Receiver<T>,
     ) -> Self {
+        debug_assert!(
+            object.upgrade().is_none_or(|obj| signal_ids
+                .iter()
+                .all(|signal_id| handler_is_connected(&obj, signal_id))),
+            "EvStream::new: the signal handler isn't connected to the object"
+        );
+        Self::with_handle(object, Handle::Signals(signal_ids), receiver)
+    }
+
+    /// Adds signal handlers connected on another object, sending into the same channel.
+    /// They are blocked and disconnected along with the stream, and it's connected as long
+    /// as any of its objects is.
+    ///
+    /// The other lifecycle methods, like [object](EvStream::object) and
+    /// [on_disconnect](EvStream::on_disconnect), only concern the first object.
+    pub fn with_linked_signals(
+        self,
+        object: WeakRef<Object>,
+        signal_ids: Vec<SignalHandlerId>,
+    ) -> Self {
+        debug_assert!(
+            object.upgrade().is_none_or(|obj| signal_ids
+                .iter()
+                .all(|signal_id| handler_is_connected(&obj, signal_id))),
+            "EvStream::with_linked_signals: the signal handler isn't connected to the object"
+        );
+        self.connection
+            .link(Connection::new(object, Handle::Signals(signal_ids)));
+        self
+    }
+
+    /// Like [EvStream::new], for sources which aren't a signal handler (e.g. a tick callback).
+    /// `teardown` is called with the object when disconnecting, if it's still alive.
+    pub fn with_teardown(
+        object: WeakRef<Object>,
+        teardown: impl FnOnce(&Object) + 'static,
+        receiver: channel::Receiver<T>,
+    ) -> Self {
+        Self::with_handle(object, Handle::Teardown(Box::new(teardown)), receiver)
+    }
+
+    fn with_handle(
+        object: WeakRef<Object>,
+        handle: Handle,
+        receiver: channel::Receiver<T>,
+    ) -> Self {
+        debug_assert!(
+            !receiver.is_closed(),
+            "EvStream::new: the receiver is already closed"
+        );
         Self {
-            object,
-            signal_id: Cell::new(Some(signal_id)),
+            connection: Rc::new(Connection::new(object, handle)),
             receiver,
+            rebinder: None,
+            terminated: false,
+            #[cfg(feature = "debug-subscriptions")]
+            polled: false,
         }
     }
+
+    /// Like [EvStream::new], connecting the callback with `connect`, given the object and
+    /// a sender into the channel of the stream. `connect` is kept to connect the same
+    /// callback to another object with [rebind](EvStream::rebind).
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let stream = EvStream::rebindable(&button, |obj, s| {
+    ///     obj.connect_local("clicked", false, move |_| {
+    ///         let _ = s.send(Msg::Clicked);
+    ///         None
+    ///     })
+    /// });
+    /// ```
+    pub fn rebindable(
+        object: &impl glib::prelude::IsA<Object>,
+        connect: impl Fn(&Object, channel::Sender<T>) -> SignalHandlerId + 'static,
+    ) -> Self {
+        let (s, r) = channel::channel();
+        let object = glib::prelude::Cast::upcast_ref::<Object>(object);
+        let signal_id = connect(object, s);
+        let mut stream = Self::new(glib::prelude::ObjectExt::downgrade(object), signal_id, r);
+        stream.rebinder = Some(Box::new(connect));
+        stream
+    }
+
+    /// Records `signal` as the name of the subscription, listed by `debug_snapshot` with
+    /// the `debug-subscriptions` feature. Does nothing without it.
+    #[doc(hidden)]
+    pub fn with_debug_name(self, signal: &str) -> Self
+    where
+        T: 'static,
+    {
+        #[cfg(feature = "debug-subscriptions")]
+        debug::register(
+            self.object().map_or("<finalized>", |obj| {
+                glib::prelude::ObjectExt::type_(&obj).name()
+            }),
+            signal,
+            Rc::downgrade(&self.connection),
+            Box::new(self.receiver.pending_probe()),
+        );
+        #[cfg(not(feature = "debug-subscriptions"))]
+        let _ = signal;
+        self
+    }
+
+    /// Identifier of the stream, unique in the process, to correlate log messages.
+    /// Unrelated to the [SignalHandlerId]s.
+    pub fn id(&self) -> u64 {
+        self.connection.id()
+    }
+
+    /// Panics when the callback sends an item after the stream was dropped, instead of
+    /// silently dropping the item. See [channel::Receiver::set_strict].
+    pub fn strict(self) -> Self {
+        self.receiver.set_strict(true);
+        self
+    }
+
+    /// Coalesces buffered items, so that each poll only yields the freshest one.
+    ///
+    /// Useful for signals firing faster than they are consumed, like `value-changed`
+    /// during a drag. The last emitted item is always delivered.
+    pub fn latest_only(self) -> Self {
+        self.bounded(1, Overflow::DropOldest)
+    }
+
+    /// Buffers at most `capacity` items, handling the excess with `overflow`.
+    pub fn bounded(self, capacity: usize, overflow: Overflow) -> Self {
+        self.receiver.set_bound(capacity, overflow);
+        self
+    }
+
+    /// Pre-allocates the buffer for `additional` items, avoiding reallocations
+    /// when a bursty signal fires many times before the stream is polled.
+    pub fn reserve(self, additional: usize) -> Self {
+        self.receiver.reserve(additional);
+        self
+    }
+
+    /// The maximum number of buffered items of a [bounded](EvStream::bounded) stream,
+    /// `None` if unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.receiver.capacity()
+    }
+
+    /// Whether the most recent emission was dropped (or made room by dropping the oldest
+    /// item) because the buffer of a [bounded](EvStream::bounded) stream was full.
+    pub fn last_send_dropped(&self) -> bool {
+        self.receiver.last_send_dropped()
+    }
+
+    /// How many emissions were dropped (or made room by dropping the oldest item) because
+    /// the buffer of a [bounded](EvStream::bounded) stream was full.
+    pub fn dropped_count(&self) -> usize {
+        self.receiver.dropped_count()
+    }
+
+    /// Yields [Event::Dropped] markers along with the items, telling how many items were
+    /// lost to the bound of the stream. See [DropMarkers].
+    pub fn with_drop_markers(self) -> DropMarkers<T> {
+        DropMarkers::new(self)
+    }
+
+    /// The object the stream is connected to, if it's still alive.
+    pub fn object(&self) -> Option<Object> {
+        self.connection.object()
+    }
+
+    /// A clone of the weak reference the stream keeps to its object, to follow the lifetime
+    /// of the object without keeping it alive. It still points to the same object after a
+    /// [rebind](EvStream::rebind).
+    pub fn weak_source(&self) -> WeakRef<Object> {
+        self.connection.weak_object()
+    }
+
+    /// Splits the stream into the object, its signal handlers and the channel they send
+    /// into, for code managing the handlers itself (e.g. from C).
+    ///
+    /// The caller now owns the handlers: they aren't disconnected when the receiver is
+    /// dropped, only by the first emission after that, which would build an item nobody
+    /// receives. The caller can still disconnect them first. The
+    /// [on_disconnect](EvStream::on_disconnect) callbacks only run when the object is
+    /// finalized. Gives the stream back if the object is gone, if it
+    /// wasn't created from signal handlers, or if it has
+    /// [linked ones](EvStream::with_linked_signals).
+    pub fn into_raw_parts(
+        self,
+    ) -> Result<(Object, Vec<SignalHandlerId>, channel::Receiver<T>), Self> {
+        let Some((object, signal_ids)) = self.connection.take_signals() else {
+            return Err(self);
+        };
+        let this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used nor dropped again, each field is read once.
+        let (connection, receiver, rebinder) = unsafe {
+            (
+                std::ptr::read(&this.connection),
+                std::ptr::read(&this.receiver),
+                std::ptr::read(&this.rebinder),
+            )
+        };
+        drop((connection, rebinder));
+        // SAFETY: only used to disconnect the handlers still connected.
+        let raw_ids: Vec<_> = signal_ids.iter().map(|id| unsafe { id.as_raw() }).collect();
+        let weak = glib::prelude::ObjectExt::downgrade(&object);
+        receiver.on_send_failure(move || {
+            if let Some(object) = weak.upgrade() {
+                disconnect_raw_handlers(&object, &raw_ids);
+            }
+        });
+        Ok((object, signal_ids, receiver))
+    }
+
+    /// The type of the object, or `None` if it has been finalized. Doesn't keep the object
+    /// alive.
+    pub fn connected_object_type(&self) -> Option<glib::Type> {
+        self.object()
+            .map(|obj| glib::prelude::ObjectExt::type_(&obj))
+    }
+
+    /// Disconnects the callback from the object. Already buffered items can still
+    /// be received, then the stream ends.
+    ///
+    /// When called while a signal of the object is being emitted (e.g. the stream is
+    /// dropped from inside a handler), the callback is blocked right away and actually
+    /// disconnected from an idle callback on the thread-default `MainContext`, once the
+    /// emission is over.
+    pub fn disconnect(&self) {
+        self.connection.disconnect();
+    }
+
+    /// Calls `f` once the stream gets disconnected, be it through [EvStream::disconnect],
+    /// by dropping the stream or because the object was finalized.
+    /// If the stream is already disconnected, `f` is called right away.
+    pub fn on_disconnect(self, f: impl FnOnce() + 'static) -> Self {
+        self.connection.on_disconnect(f);
+        self
+    }
+
+    /// Resolves once the stream gets disconnected, in any of the ways listed in
+    /// [on_disconnect](EvStream::on_disconnect). Resolves right away if it already is.
+    pub fn disconnected(&self) -> impl Future<Output = ()> {
+        let (s, r) = futures_channel::oneshot::channel();
+        self.connection.on_disconnect(move || {
+            let _ = s.send(());
+        });
+        futures_util::FutureExt::map(r, |_| ())
+    }
+
+    /// Whether the callback is still connected to a live object.
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_connected()
+    }
+
+    /// A [HandlerToken] to query and block the callback without access to the stream.
+    pub fn token(&self) -> HandlerToken {
+        HandlerToken {
+            connection: Rc::downgrade(&self.connection),
+        }
+    }
+
+    /// A [Reconnector] to swap the callback for a new one, sending into the same channel.
+    pub fn reconnector(&self) -> Reconnector<T> {
+        Reconnector {
+            connection: Rc::downgrade(&self.connection),
+            senders: self.receiver.sender_source(),
+        }
+    }
+
+    /// Disconnects the callback from the current object and connects it to `new_obj`, with
+    /// the closure given to [rebindable](EvStream::rebindable). The channel is kept, so
+    /// the buffered items and the state of the combinators chained on the stream are too.
+    ///
+    /// Fails if the stream wasn't created with `rebindable`, or if it's no longer
+    /// connected: disconnected, or its object finalized.
+    pub fn rebind(&self, new_obj: &impl glib::prelude::IsA<Object>) -> Result<(), RebindError> {
+        let connect = self.rebinder.as_ref().ok_or(RebindError::NotRebindable)?;
+        if !self.is_connected() {
+            return Err(RebindError::Disconnected);
+        }
+        let sender = self
+            .receiver
+            .sender_source()
+            .sender()
+            .ok_or(RebindError::Disconnected)?;
+        let obj = glib::prelude::Cast::upcast_ref::<Object>(new_obj);
+        let signal_id = connect(obj, sender);
+        match self
+            .connection
+            .rebind(obj, Handle::Signals(vec![signal_id]))
+        {
+            Ok(()) => Ok(()),
+            // `connect` disconnected the stream itself.
+            Err(Handle::Signals(signal_ids)) => {
+                for signal_id in signal_ids {
+                    glib::prelude::ObjectExt::disconnect(obj, signal_id);
+                }
+                Err(RebindError::Disconnected)
+            }
+            Err(_) => Err(RebindError::Disconnected),
+        }
+    }
+
+    /// Another consumer of the stream, getting a clone of each item emitted from now on,
+    /// while this stream keeps receiving them too. Like this stream, it's withheld while a
+    /// [GlobalPause] is held.
+    ///
+    /// Unlike [broadcast](EvStream::broadcast), nothing is spawned: the callback clones
+    /// the items into the subscribers, once the first one subscribed. The subscribers
+    /// don't keep the callback connected, and end once this stream is disconnected or
+    /// dropped.
+    pub fn subscribe(&self) -> channel::Receiver<T>
+    where
+        T: Clone,
+    {
+        self.receiver.subscribe()
+    }
+
+    /// Runs the thread-default main context until an item arrives or `duration` elapses.
+    ///
+    /// Returns `None` on timeout or if the stream ended. Handy in tests, where a signal
+    /// which unexpectedly doesn't fire shouldn't hang forever.
+    pub fn next_timeout(&mut self, duration: Duration) -> Option<T> {
+        let ctx = glib::MainContext::ref_thread_default();
+        let mut timer = timer::Timer::timeout(duration, &ctx);
+        ctx.block_on(std::future::poll_fn(|cx| {
+            if let Poll::Ready(item) = Pin::new(&mut *self).poll_next(cx) {
+                return Poll::Ready(item);
+            }
+            Pin::new(&mut timer).poll(cx).map(|_| None)
+        }))
+    }
+
+    /// Takes the next buffered item without waiting nor iterating the main loop, for
+    /// consumers which aren't async. `Ok(None)` means that no item is buffered yet, or
+    /// that a [GlobalPause] is held: the items stay buffered until it's dropped.
+    pub fn try_recv(&mut self) -> Result<Option<T>, TryRecvError> {
+        #[cfg(feature = "debug-subscriptions")]
+        {
+            self.polled = true;
+        }
+        if self.terminated {
+            return Err(TryRecvError::Disconnected);
+        }
+        let item = self.receiver.try_recv();
+        self.terminated = item.is_err();
+        item
+    }
+
+    /// Takes all the buffered items at once, like [try_recv](EvStream::try_recv), e.g. to
+    /// flush the pending events before tearing a component down. The stream stays
+    /// connected and receives the items emitted later. Nothing is taken while a
+    /// [GlobalPause] is held.
+    pub fn drain_now(&mut self) -> Vec<T> {
+        let mut items = Vec::with_capacity(self.receiver.size_hint().0);
+        while let Ok(Some(item)) = self.try_recv() {
+            items.push(item);
+        }
+        items
+    }
+
+    /// Moves up to `max` buffered items to the end of `buf`, like
+    /// [drain_now](EvStream::drain_now) but bounded, and reusing the allocation of `buf`.
+    /// Returns how many items were moved.
+    pub fn recv_many(&mut self, max: usize, buf: &mut Vec<T>) -> usize {
+        let mut received = 0;
+        while received < max {
+            let Ok(Some(item)) = self.try_recv() else {
+                break;
+            };
+            buf.push(item);
+            received += 1;
+        }
+        received
+    }
+
+    /// Pairs each item with the object the stream is connected to, or `None` if it
+    /// has been finalized by the time the item is received.
+    pub fn with_object(self) -> impl Stream<Item = (Option<Object>, T)> {
+        let connection = self.connection.clone();
+        futures_util::StreamExt::map(self, move |item| (connection.object(), item))
+    }
+
+    /// Resolves with the first item, then drops the stream, disconnecting it, like [once].
+    /// Resolves to `None` if the stream ends first, e.g. because the object was finalized.
+    /// Dropping the future early disconnects the stream too.
+    pub fn first(self) -> impl Future<Output = Option<T>> {
+        let mut stream = self.bounded(1, Overflow::DropNewest);
+        async move {
+            let item = futures_util::StreamExt::next(&mut stream).await;
+            drop(stream);
+            item
+        }
+    }
+
+    /// Yields `f(item)` while it returns `Some`. The first `None` ends the stream and drops
+    /// it, disconnecting the callback, e.g. to process the events until a sentinel.
+    pub fn map_while<U>(self, mut f: impl FnMut(T) -> Option<U>) -> impl Stream<Item = U> {
+        let mut stream = Some(self);
+        futures_util::stream::poll_fn(move |cx| {
+            let Some(source) = stream.as_mut() else {
+                return Poll::Ready(None);
+            };
+            let mapped = futures_core::ready!(Pin::new(source).poll_next(cx)).and_then(&mut f);
+            if mapped.is_none() {
+                stream = None;
+            }
+            Poll::Ready(mapped)
+        })
+    }
+
+    /// Makes the stream abortable through the returned [AbortHandle]: aborting
+    /// disconnects the callback and ends the stream right away, without yielding the
+    /// buffered items.
+    pub fn abortable(self) -> (futures_util::stream::Abortable<Self>, AbortHandle) {
+        let connection = self.connection.clone();
+        let (stream, handle) = futures_util::stream::abortable(self);
+        (stream, AbortHandle { handle, connection })
+    }
+
+    /// Shares the items between several consumers, see [Broadcast].
+    pub fn broadcast(self) -> Broadcast<T>
+    where
+        T: Clone + 'static,
+    {
+        Broadcast::new(self)
+    }
+
+    /// Boxes the stream, keeping access to the [EvStreamControl] methods.
+    pub fn boxed(self) -> BoxedEvStream<T>
+    where
+        T: 'static,
+    {
+        Box::new(self)
+    }
+}
+
+/// Handle returned by [EvStream::abortable].
+#[derive(Clone)]
+pub struct AbortHandle {
+    handle: futures_util::stream::AbortHandle,
+    connection: Rc<Connection>,
+}
+
+impl AbortHandle {
+    /// Disconnects the callback and ends the stream.
+    pub fn abort(&self) {
+        self.handle.abort();
+        self.connection.disconnect();
+    }
+
+    /// Whether [abort](AbortHandle::abort) was called.
+    pub fn is_aborted(&self) -> bool {
+        self.handle.is_aborted()
+    }
+}
+
+/// Handle to the callback of an [EvStream], returned by [EvStream::token] and
+/// [ev_stream_with_id].
+///
+/// It doesn't keep the stream connected: once the stream is dropped, the token reports
+/// it as disconnected and blocking does nothing.
+#[derive(Clone)]
+pub struct HandlerToken {
+    connection: std::rc::Weak<Connection>,
+}
+
+impl HandlerToken {
+    /// See [EvStream::is_connected].
+    pub fn is_connected(&self) -> bool {
+        self.connection
+            .upgrade()
+            .is_some_and(|connection| connection.is_connected())
+    }
+
+    /// Stops the signal handlers from running, until [unblock](HandlerToken::unblock).
+    /// Emissions happening meanwhile are lost. Sources which aren't signal handlers
+    /// (like the tick callback of `widgets::size_allocate_stream`) can't be blocked.
+    pub fn block(&self) {
+        if let Some(connection) = self.connection.upgrade() {
+            connection.set_blocked(true);
+        }
+    }
+
+    /// Undoes [block](HandlerToken::block).
+    pub fn unblock(&self) {
+        if let Some(connection) = self.connection.upgrade() {
+            connection.set_blocked(false);
+        }
+    }
+}
+
+/// Handle returned by [EvStream::reconnector].
+///
+/// The channel of the stream is kept, so the combinators chained on it keep working
+/// across reconnections, with their state (e.g. a pending debounce timer). Like a
+/// [HandlerToken], it doesn't keep the stream alive.
+pub struct Reconnector<T> {
+    connection: std::rc::Weak<Connection>,
+    senders: channel::SenderSource<T>,
+}
+
+impl<T> Reconnector<T> {
+    /// Connects a new callback with `connect`, given the object and a sender into the
+    /// channel of the stream, then disconnects the current callback.
+    ///
+    /// Does nothing and returns `false` if the stream was dropped or disconnected, or if
+    /// the object is gone.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let reconnector = stream.reconnector();
+    /// reconnector.reconnect(|obj, s| {
+    ///     obj.connect_local("clicked", false, move |_| {
+    ///         let _ = s.send(Msg::Clicked);
+    ///         None
+    ///     })
+    /// });
+    /// ```
+    pub fn reconnect(
+        &self,
+        connect: impl FnOnce(&Object, channel::Sender<T>) -> SignalHandlerId,
+    ) -> bool {
+        let Some(connection) = self.connection.upgrade().filter(|c| c.is_connected()) else {
+            return false;
+        };
+        let (Some(obj), Some(sender)) = (connection.connected_object(), self.senders.sender())
+        else {
+            return false;
+        };
+        let signal_id = connect(&obj, sender);
+        match connection.replace_handle(Handle::Signals(vec![signal_id])) {
+            Ok(()) => true,
+            // `connect` disconnected the stream itself.
+            Err(Handle::Signals(signal_ids)) => {
+                for signal_id in signal_ids {
+                    glib::prelude::ObjectExt::disconnect(&obj, signal_id);
+                }
+                false
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Callback kept by [EvStream::rebindable].
+type Rebinder<T> = dyn Fn(&Object, channel::Sender<T>) -> SignalHandlerId;
+
+/// Error returned by [EvStream::rebind].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebindError {
+    /// The stream wasn't created with [EvStream::rebindable].
+    NotRebindable,
+    /// The stream was disconnected, or its object finalized.
+    Disconnected,
+}
+
+impl std::fmt::Display for RebindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RebindError::NotRebindable => "the stream wasn't created with `EvStream::rebindable`",
+            RebindError::Disconnected => "the stream isn't connected anymore",
+        })
+    }
+}
+
+impl std::error::Error for RebindError {}
+
+/// Runs `f`, a callback called by glib, aborting the process if it panics.
+///
+/// Unwinding into glib would leave it in an inconsistent state, or abort anyway with a
+/// message that doesn't tell which closure it was. The panic message is printed first,
+/// by the panic hook.
+#[doc(hidden)]
+pub fn __no_unwind<R>(f: impl FnOnce() -> R) -> R {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(_) => {
+            eprintln!(
+                "ev-stream-gtk-rs: a closure panicked inside a glib signal handler, \
+                 aborting since it can't unwind into glib"
+            );
+            std::process::abort()
+        }
+    }
+}
+
+/// Connects the callback of the untyped form of [ev_stream], see its emission semantics.
+#[doc(hidden)]
+pub fn __connect_untyped(
+    object: &Object,
+    signal: &str,
+    after: bool,
+    callback: impl Fn(&[glib::Value]) -> Option<glib::Value> + 'static,
+) -> SignalHandlerId {
+    glib::prelude::ObjectExt::connect_local(object, signal, after, move |args| {
+        __no_unwind(|| callback(args))
+    })
+}
+
+/// See [assert_disconnected_on_drop].
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_disconnected_on_drop<T>(object: &Object, stream: EvStream<T>) {
+    let signal_ids = stream.connection.raw_signal_ids();
+    assert!(
+        !signal_ids.is_empty(),
+        "assert_disconnected_on_drop!: the stream has no signal handler connected"
+    );
+    let is_connected = |signal_id| unsafe {
+        glib::gobject_ffi::g_signal_handler_is_connected(
+            glib::prelude::ObjectType::as_ptr(object),
+            signal_id,
+        ) != glib::ffi::GFALSE
+    };
+    assert!(
+        signal_ids.iter().all(|&signal_id| is_connected(signal_id)),
+        "assert_disconnected_on_drop!: the stream isn't connected to this object"
+    );
+    drop(stream);
+    let still_connected = signal_ids.iter().filter(|&&id| is_connected(id)).count();
+    assert!(
+        still_connected == 0,
+        "assert_disconnected_on_drop!: {still_connected} of the {} signal handlers of the \
+         stream are still connected after dropping it",
+        signal_ids.len()
+    );
+}
+
+// `EvStream` must stay `Unpin`, see its documentation.
+const _: fn() = || {
+    fn assert_unpin<S: Stream + Unpin>() {}
+    assert_unpin::<EvStream<()>>();
+    assert_unpin::<BoxedEvStream<()>>();
+};
+
+/// Lifecycle methods of an [EvStream], usable after its type has been erased.
+///
+/// The combinators of [EvStreamExt] built around a single stream, like
+/// [map_ok](EvStreamExt::map_ok), [log_each](EvStreamExt::log_each) or the shares of
+/// [share_rc](EvStreamExt::share_rc), implement it whenever that stream does, by
+/// delegating to it.
+pub trait EvStreamControl {
+    /// See [EvStream::object].
+    fn object(&self) -> Option<Object>;
+    /// See [EvStream::disconnect].
+    fn disconnect(&self);
+    /// See [EvStream::is_connected].
+    fn is_connected(&self) -> bool;
+}
+
+impl<T> EvStreamControl for EvStream<T> {
+    fn object(&self) -> Option<Object> {
+        EvStream::object(self)
+    }
+    fn disconnect(&self) {
+        EvStream::disconnect(self)
+    }
+    fn is_connected(&self) -> bool {
+        EvStream::is_connected(self)
+    }
 }
 
+/// A `Stream` which can also be controlled through [EvStreamControl].
+pub trait ControlledStream: EvStreamControl + Stream + Unpin {}
+
+impl<S: EvStreamControl + Stream + Unpin> ControlledStream for S {}
+
+/// Type-erased [EvStream], returned by [EvStream::boxed].
+pub type BoxedEvStream<T> = Box<dyn ControlledStream<Item = T>>;
+
+/// Any stream of `T` with its type erased, returned by [EvStreamExt::into_dyn].
+///
+/// Handy to store subscriptions built from different combinator chains in the same
+/// field, mapping each of them to a common message type. Dropping the stream drops the
+/// [EvStream]s inside it, disconnecting them.
+///
+/// # Examples
+/// ```ignore
+/// struct Window {
+///     subscriptions: Vec<DynEvStream<Msg>>,
+/// }
+///
+/// let subscriptions = vec![
+///     ev_stream!(button, clicked, |_| Msg::Clicked).into_dyn(),
+///     ev_stream!(entry, changed, |e| e.text())
+///         .debounce(Duration::from_millis(300))
+///         .map(Msg::Search)
+///         .into_dyn(),
+/// ];
+/// ```
+pub type DynEvStream<T> = Pin<Box<dyn Stream<Item = T>>>;
+
 impl<T> Stream for EvStream<T> {
     type Item = T;
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().receiver).poll_next(cx)
+        let this = self.get_mut();
+        #[cfg(feature = "debug-subscriptions")]
+        {
+            this.polled = true;
+        }
+        if this.terminated {
+            return Poll::Ready(None);
+        }
+        let next = Pin::new(&mut this.receiver).poll_next(cx);
+        this.terminated = matches!(next, Poll::Ready(None));
+        next
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -41,25 +928,88 @@ impl<T> Stream for EvStream<T> {
     }
 }
 
+/// Terminated once the stream yielded `None`: a disconnected stream still yields its
+/// buffered items first, so `select!` keeps polling it until they are drained.
+impl<T> futures_core::stream::FusedStream for EvStream<T> {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
 impl<T> std::ops::Drop for EvStream<T> {
     fn drop(&mut self) {
-        self.object
-            .upgrade()
-            .map(|obj| obj.disconnect(self.signal_id.take().unwrap()));
+        #[cfg(feature = "debug-subscriptions")]
+        if !self.polled {
+            glib::g_warning!(
+                "ev-stream-gtk-rs",
+                "stream of `{}` on `{}` dropped without being polled: was it spawned?",
+                std::any::type_name::<T>(),
+                self.object()
+                    .map_or("<finalized>", |obj| glib::prelude::ObjectExt::type_(&obj)
+                        .name()),
+            );
+        }
+        self.disconnect();
     }
 }
 
 /// This macro let's you listen to glib events as streams.
 ///
-/// The last argument, which resembles a closure, is needed to let 
+/// The last argument, which resembles a closure, is needed to let
 /// the type system see what arguments need to be taken by the connect
 /// callback and which need to be cloned.
 ///
 /// The arguments must be cloned because they must live longer than the
-/// callback inside the `connect` function.
+/// callback inside the `connect` function. Nullable object arguments (`Option<&T>`,
+/// e.g. the new focus widget) are cloned into an `Option<T>`.
 /// If you need to manipulate the arguments inside the callback before they get into
 /// the stream, you can do so by passing a closure body.
 ///
+/// # Emission semantics
+/// The untyped form connects with `connect_local`, whether or not the thread owns its
+/// default `MainContext` (e.g. library code running before the main loop): it wraps the
+/// callback in the same thread guard as a local closure given to `connect_closure`, so
+/// both would behave the same. The callback is bound to the thread which connected it:
+/// emitting the signal from another thread panics inside glib, and so does the emission
+/// of a signal returning a value, which the untyped callback never provides.
+/// Objects without a typed `connect_<event>` method can always
+/// be reached through the untyped form. It panics if the object has no such signal: use
+/// [try_ev_stream] for names only known at runtime.
+///
+/// # Return value of the handler
+/// With the typed form, handlers expected to return a `glib::Propagation` (e.g.
+/// `key-pressed` of an event controller) return `Propagation::Proceed`, as if the stream
+/// wasn't connected. Another value can be given after the body, as `=> value`: it's
+/// evaluated in the handler, once the item was sent, and can use the arguments. For the
+/// other return types, or to decide from the untyped form, see [ev_stream_decide].
+///
+/// # Lifetime of the object
+/// The object expression is evaluated once, and only borrowed: the stream keeps a weak
+/// reference, so the caller must keep the object alive. An expression returning a new
+/// reference, like `self.view.model()`, is fine as long as something else holds the
+/// object too. If that temporary was the last reference, the object is finalized at the
+/// end of the macro, and the stream ends right away instead of never yielding.
+///
+/// As it's borrowed, the object doesn't need to be `Clone`, but it must be a glib object
+/// (`IsA<glib::Object>`), with the glib prelude in scope:
+///
+/// ```compile_fail
+/// use glib::prelude::*;
+/// struct NotAnObject;
+/// let _ = ev_stream_gtk_rs::ev_stream!(NotAnObject, "clicked", |args|);
+/// ```
+///
+/// Without a body, the arguments are cloned, so they must be `Clone` (object and
+/// `glib::Value` arguments are): write a body to take only what can be cloned.
+///
+/// # Panics in the body
+/// The body (like the `decide` closure of [ev_stream_decide]) runs inside the glib
+/// handler, which a panic must not unwind into: it's caught there and the process aborts,
+/// after the panic message and a line telling it happened in a signal handler. The same
+/// goes for the panics of the handlers connected by the other streams of the crate, like
+/// a property of an unexpected type, or an item sent after the drop of a
+/// [strict](EvStream::strict) stream. The closures given to the combinators run when the
+/// stream is polled, and their panics unwind through the consumer as usual.
 ///
 /// # Examples
 /// ```ignore
@@ -74,42 +1024,445 @@ impl<T> std::ops::Drop for EvStream<T> {
 /// // cloned. To achieve this, I'm manually adding a closure body after `|...|`, telling the
 /// // macro to manually manage the data.
 /// let edges_reached = ev_stream!(scrolled_win, edge_reached, |_win, edge| edge);
+///
+/// // Recommended when only some of the arguments are needed: ignore the others with
+/// // `_` or an underscore-prefixed name, and clone just what you keep.
+/// let rows = ev_stream!(tree_view, row_activated, |_, path, _col| path.clone());
+///
+/// // With three or more arguments, prefer building a named struct over a tuple.
+/// let rows = ev_stream!(tree_view, row_activated, |_, path, col| RowActivated {
+///     path: path.clone(),
+///     column: col.cloned(),
+/// });
+///
+/// // Without a body, the arguments named `_` are skipped and the others are cloned.
+/// let paths = ev_stream!(tree_view, row_activated, |_, path, _|);
+///
+/// // The item type can be given explicitly, when the body doesn't determine it.
+/// let items = ev_stream!(obj, "my-signal"::<MyType>, |values| MyType::from(values));
+///
+/// // Stops the propagation of the key events, still streaming them.
+/// let keys = ev_stream!(controller, key_pressed, |_, key, _, _| key => glib::Propagation::Stop);
+///
+/// // The object is only borrowed, so fields can be used from `&self` methods.
+/// let clicks = ev_stream!(&self.button, clicked, |btn|);
 /// ```
 #[macro_export]
 macro_rules! ev_stream {
-    // Typed macro
-    ($this:expr, $event:ident, | $($x:ident),* | $cloning_body:expr) => {
+    // Builds the tuple of the cloned arguments, skipping `_`.
+    (@cloned [$($acc:ident)*] _ $(, $($rest:tt)*)?) => {
+        $crate::ev_stream!(@cloned [$($acc)*] $($($rest)*)?)
+    };
+    (@cloned [$($acc:ident)*] $x:ident $(, $($rest:tt)*)?) => {
+        $crate::ev_stream!(@cloned [$($acc)* $x] $($($rest)*)?)
+    };
+    (@cloned [$($acc:ident)*]) => {
         {
-            let (s, r) = $crate::mpsc::unbounded();
-            let object = $this.clone().upcast::<$crate::Object>().downgrade();
-            let signal_id = $crate::paste::expr!($this.[<connect_ $event>](move |$($x,)*| {
-                let args = $cloning_body;
-                s.unbounded_send(args).expect("sending value in ev_stream");
+            #[allow(unused_imports)]
+            use $crate::__clone_arg::{CloneArg as _, CloneRefArg as _};
+            ($($acc.clone_arg()),*) // tuple with cloned elements
+        }
+    };
+    // Explicit item type, for when it can't be inferred from the body.
+    ($this:expr, $event:tt :: <$item:ty>, $($callback:tt)*) => {
+        {
+            let stream: $crate::EvStream<$item> = $crate::ev_stream!($this, $event, $($callback)*);
+            stream
+        }
+    };
+    // Typed macro, returning `$ret` to the emitter
+    ($this:expr, $event:ident, | $($x:pat_param),* | $cloning_body:expr => $ret:expr $(,)?) => {
+        {
+            // Bound first: `paste` flattens `$this`, so `&obj` would take a reference
+            // to the result of the call instead. It's evaluated once in every form.
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = $crate::paste::expr!(this.[<connect_ $event>](move |$($x,)*| {
+                $crate::__no_unwind(|| {
+                    let args = $cloning_body;
+                    let _ = s.send(args);
+                    $ret
+                })
             }));
-            $crate::EvStream::new(object, signal_id, r)
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(stringify!($event))
         }
     };
+    // Typed macro
+    ($this:expr, $event:ident, | $($x:pat_param),* | $cloning_body:expr $(,)?) => {
+        $crate::ev_stream!(
+            $this,
+            $event,
+            | $($x),* | $cloning_body => $crate::__handler_return::Proceed::proceed()
+        )
+    };
     // Untyped macro (connects to the event by name, using a string)
-    ($this:expr, $event:expr, | $($x:ident),* | $cloning_body:expr) => {
+    ($this:expr, $event:expr, | $($x:pat_param),* | $cloning_body:expr $(,)?) => {
         {
-            let (s, r) = $crate::mpsc::unbounded();
-            let object = $this.clone().upcast::<Object>().downgrade();
-            let signal_id = $this.connect_local($event, false, move |$($x,)*| {
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = $crate::__connect_untyped(this.upcast_ref(), $event, false, move |$($x,)*| {
                 let args = $cloning_body;
-                s.unbounded_send(args).expect("sending value in ev_stream");
+                // Never unwind into glib: the receiver can only be gone while the
+                // callback is being disconnected, so the item is simply dropped.
+                let _ = s.send(args);
                 None
             });
-            $crate::EvStream::new(object, signal_id, r)
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(stringify!($event))
         }
     };
-    ($this:expr, $event:ident, | $($x:ident),* |) => {
-        $crate::ev_stream!($this, $event, | $($x),* | {
-            ($($x.clone()),*) // tuple with cloned elements
+    ($this:expr, $event:ident, | $($x:tt),* | $(,)?) => {
+        $crate::ev_stream!($this, $event, | $($x),* | $crate::ev_stream!(@cloned [] $($x),*))
+    };
+    ($this:expr, $event:expr, | $($x:tt),* | $(,)?) => {
+        $crate::ev_stream!($this, $event, | $($x),* | $crate::ev_stream!(@cloned [] $($x),*))
+    };
+    // Common mistakes, reported with a readable message instead of an error
+    // from deep inside the expansion.
+    ($this:expr, $event:tt, || $($rest:tt)*) => {
+        compile_error!("ev_stream!: the callback always receives the emitting object, write `|obj|` instead of `||`")
+    };
+    ($this:expr, $event:tt $(,)?) => {
+        compile_error!("ev_stream!: missing the callback arguments, expected `ev_stream!(object, signal, |args| [body])`")
+    };
+    ($this:expr, $event:tt, $($rest:tt)*) => {
+        compile_error!("ev_stream!: the third argument must be the callback arguments, like `|args| [body]`")
+    };
+    ($($rest:tt)*) => {
+        compile_error!("ev_stream! expects `ev_stream!(object, signal, |args| [body])`")
+    };
+}
+
+/// Like [ev_stream], pre-allocating the buffer for `expected_burst` items.
+/// See [EvStream::reserve].
+///
+/// # Examples
+/// ```ignore
+/// let changes = ev_stream_with_hint!(model, items_changed, 64, |_, pos, removed, added| (pos, removed, added));
+/// ```
+#[macro_export]
+macro_rules! ev_stream_with_hint {
+    ($this:expr, $event:tt, $expected_burst:expr, $($callback:tt)*) => {
+        $crate::ev_stream!($this, $event, $($callback)*).reserve($expected_burst)
+    };
+}
+
+/// Like [ev_stream], connecting the callback after the default handler of the signal.
+///
+/// The generated `connect_<event>` methods have no such variant, so both forms connect by
+/// name. In the typed form the event is written as an identifier, with `_` in place of
+/// `-`, and every argument is annotated with its type: emissions whose arguments don't
+/// convert to those types are skipped.
+///
+/// # Examples
+/// ```ignore
+/// // typed
+/// let clicks = ev_stream_after!(button, clicked, |btn: gtk::Button|);
+/// let edges = ev_stream_after!(win, edge_reached, |_win: gtk::ScrolledWindow, edge: gtk::PositionType| edge);
+/// // untyped
+/// let clicks = ev_stream_after!(button, "clicked", |args| args.len());
+/// ```
+#[macro_export]
+macro_rules! ev_stream_after {
+    ($this:expr, $event:ident, | $($x:ident : $t:ty),* | $(,)?) => {
+        $crate::ev_stream_after!($this, $event, | $($x: $t),* | ($($x),*))
+    };
+    ($this:expr, $event:ident, | $($x:ident : $t:ty),* | $body:expr $(,)?) => {
+        {
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal = stringify!($event).replace('_', "-");
+            let signal_id = this.connect_local(&signal, true, move |args| {
+                $crate::__no_unwind(|| {
+                    let [$($x),*] = args else {
+                        return None;
+                    };
+                    $(
+                        let Ok($x) = $x.get::<$t>() else {
+                            return None;
+                        };
+                    )*
+                    let _ = s.send($body);
+                    None
+                })
+            });
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(&signal)
+        }
+    };
+    ($this:expr, $event:expr, | $($x:pat_param),* | $body:expr $(,)?) => {
+        {
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = $crate::__connect_untyped(this.upcast_ref(), $event, true, move |$($x,)*| {
+                let _ = s.send($body);
+                None
+            });
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(stringify!($event))
+        }
+    };
+    ($this:expr, $event:expr, | $($x:tt),* | $(,)?) => {
+        $crate::ev_stream_after!($this, $event, | $($x),* | $crate::ev_stream!(@cloned [] $($x),*))
+    };
+}
+
+/// Like [ev_stream], for signals whose handler must return a value right away.
+///
+/// `decide` runs inside the handler and its result is returned to the emitter, while the
+/// stream yields the items built by the body, for observation. The typed form calls
+/// `decide` with the signal arguments, the untyped one with the `&[glib::Value]` and
+/// expects an `Option<glib::Value>` back. Like the body, `decide` is evaluated inside the
+/// handler, so a closure written in place gets its argument types inferred from the
+/// signal. The item is built before `decide` runs.
+///
+/// # Examples
+/// ```ignore
+/// let tooltips = ev_stream_decide!(
+///     widget,
+///     query_tooltip,
+///     |_, _, _, _, tooltip| {
+///         tooltip.set_text(Some("Hello"));
+///         true
+///     },
+///     |widget, x, y, keyboard, tooltip| (x, y, keyboard)
+/// );
+/// ```
+#[macro_export]
+macro_rules! ev_stream_decide {
+    ($this:expr, $event:ident, $decide:expr, | $($x:ident),* | $body:expr $(,)?) => {
+        {
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = $crate::paste::expr!(this.[<connect_ $event>](move |$($x,)*| {
+                $crate::__no_unwind(|| {
+                    let _ = s.send($body);
+                    ($decide)($($x),*)
+                })
+            }));
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(stringify!($event))
+        }
+    };
+    ($this:expr, $event:expr, $decide:expr, | $x:ident | $body:expr $(,)?) => {
+        {
+            let this = &$this;
+            let (s, r) = $crate::channel::channel();
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = this.connect_local($event, false, move |$x| {
+                $crate::__no_unwind(|| {
+                    let _ = s.send($body);
+                    ($decide)($x)
+                })
+            });
+            $crate::EvStream::new(object, signal_id, r).with_debug_name(stringify!($event))
+        }
+    };
+}
+
+/// Like the untyped form of [ev_stream], but first checks that the object has the
+/// signal (see [check_signal]), returning an [UnknownSignalError] instead of panicking
+/// when it doesn't.
+///
+/// # Examples
+/// ```ignore
+/// let activations = try_ev_stream!(widget, "activate", |w|)?;
+/// ```
+#[macro_export]
+macro_rules! try_ev_stream {
+    ($this:expr, $event:expr, $($callback:tt)*) => {
+        {
+            let this = &$this;
+            match $crate::check_signal(this.upcast_ref::<$crate::Object>(), $event) {
+                Ok(()) => Ok($crate::ev_stream!(this, $event, $($callback)*)),
+                Err(err) => Err(err),
+            }
+        }
+    };
+}
+
+/// Like [ev_stream], for an object living on the thread owning a `MainContext`, from
+/// any other thread: connects on that thread and yields a `Send` [CrossThreadStream].
+/// See [cross_thread_stream].
+///
+/// The object is passed as a `glib::SendWeakRef`, created on its thread. The items must
+/// be `Send`.
+///
+/// # Examples
+/// ```ignore
+/// // on the main thread
+/// let weak = glib::SendWeakRef::from(button.downgrade());
+/// std::thread::spawn(move || {
+///     let clicks = ev_stream_cross_thread!(glib::MainContext::default(), weak, clicked, |_| ());
+///     futures::executor::block_on(clicks.for_each(|()| async { println!("clicked") }));
+/// });
+/// ```
+#[macro_export]
+macro_rules! ev_stream_cross_thread {
+    ($ctx:expr, $weak:expr, $event:tt, $($callback:tt)*) => {
+        {
+            let weak = $weak;
+            $crate::cross_thread_stream(&$ctx, move || {
+                let this = weak.upgrade()?;
+                Some($crate::ev_stream!(this, $event, $($callback)*))
+            })
+        }
+    };
+}
+
+/// Like [ev_stream], also returning a [HandlerToken] for the callback.
+///
+/// # Examples
+/// ```ignore
+/// let (clicks, token) = ev_stream_with_id!(button, clicked, |btn|);
+/// token.block();
+/// ```
+#[macro_export]
+macro_rules! ev_stream_with_id {
+    ($this:expr, $event:tt, $($callback:tt)*) => {
+        {
+            let stream = $crate::ev_stream!($this, $event, $($callback)*);
+            let token = stream.token();
+            (stream, token)
+        }
+    };
+}
+
+/// Like [ev_stream], but resolves with the first emission only, disconnecting the
+/// callback right after, or with `None` if the object is finalized first. See [once].
+///
+/// # Examples
+/// ```ignore
+/// let widget = ev_once!(widget, realize, |w|).await;
+/// ```
+#[macro_export]
+macro_rules! ev_once {
+    ($this:expr, $event:tt, $($callback:tt)*) => {
+        $crate::once($crate::ev_stream!($this, $event, $($callback)*))
+    };
+}
+
+/// Asserts that dropping `stream` disconnects all its signal handlers from `object`, the
+/// guarantee of every [EvStream], for the test suites of code managing subscriptions.
+///
+/// The handlers must be connected to `object` before the drop. Use it outside of signal
+/// handlers: a stream dropped during an emission on its object is only disconnected once
+/// the emission is over.
+///
+/// # Examples
+/// ```ignore
+/// let clicks = ev_stream!(button, clicked, |_| ());
+/// assert_disconnected_on_drop!(button, clicks);
+/// ```
+#[macro_export]
+macro_rules! assert_disconnected_on_drop {
+    ($object:expr, $stream:expr $(,)?) => {{
+        let object = &$object;
+        $crate::__assert_disconnected_on_drop(object.upcast_ref::<$crate::Object>(), $stream)
+    }};
+}
+
+/// Like [ev_stream], but yields a [WeakRef] to the emitting object instead of a strong
+/// reference, so buffered items don't keep the object alive.
+/// Other callback arguments are ignored.
+///
+/// # Examples
+/// ```ignore
+/// // typed, yields `WeakRef<gtk::Button>`
+/// let clicks = ev_stream_weak_item!(button, clicked, |btn|);
+/// // untyped, yields `WeakRef<Object>`
+/// let clicks = ev_stream_weak_item!(button, "clicked", |args|);
+/// ```
+#[macro_export]
+macro_rules! ev_stream_weak_item {
+    ($this:expr, $event:ident, | $x:ident $(, $rest:pat_param)* |) => {
+        $crate::ev_stream!($this, $event, | $x $(, $rest)* | $x.downgrade())
+    };
+    ($this:expr, $event:expr, | $x:ident |) => {
+        $crate::ev_stream!($this, $event, | $x | {
+            $x[0]
+                .get::<$crate::Object>()
+                .expect("signal emitted without an object")
+                .downgrade()
         })
     };
-    ($this:expr, $event:expr, | $($x:ident),* |) => {
-        $crate::ev_stream!($this, $event, | $($x),* | {
-            ($($x.clone()),*) // tuple with cloned elements
+}
+
+/// Merges several signals of the same object into a single stream.
+///
+/// Each signal is written like in [ev_stream], as `signal: |args| body`, and all the
+/// bodies must produce the same type (e.g. a message enum). Every callback sends into the
+/// same channel, so items are delivered in the exact order the signals were emitted.
+/// Dropping the stream disconnects all the callbacks.
+///
+/// Signals of different objects are written `object.signal => |args| body`, without the
+/// leading object. Each object keeps its own handlers, disconnected from it when the
+/// stream is dropped; the first object is the one of the stream, see
+/// [EvStream::with_linked_signals].
+///
+/// # Examples
+/// ```ignore
+/// let events = ev_stream_any!(entry, {
+///     changed: |entry| Msg::Changed(entry.text().to_string()),
+///     activate: |_| Msg::Activated,
+///     "icon-press": |_| Msg::IconPressed,
+/// });
+///
+/// let events = ev_stream_any!({
+///     button.clicked => |_| Msg::Clicked,
+///     entry.changed => |entry| Msg::Changed(entry.text().to_string()),
+/// });
+/// ```
+#[macro_export]
+macro_rules! ev_stream_any {
+    (@connect $this:ident, $s:ident, $event:ident, | $($x:pat_param),* | $body:expr) => {{
+        let s = $s.clone();
+        $crate::paste::expr!($this.[<connect_ $event>](move |$($x,)*| {
+            $crate::__no_unwind(|| {
+                let _ = s.send($body);
+                $crate::__handler_return::Proceed::proceed()
+            })
+        }))
+    }};
+    (@connect $this:ident, $s:ident, $event:expr, | $($x:pat_param),* | $body:expr) => {{
+        let s = $s.clone();
+        $this.connect_local($event, false, move |$($x,)*| {
+            $crate::__no_unwind(|| {
+                let _ = s.send($body);
+                None
+            })
         })
-    }
+    }};
+    ({
+        $first:ident . $first_event:tt => | $($first_x:pat_param),* | $first_body:expr
+        $(, $this:ident . $event:tt => | $($x:pat_param),* | $body:expr)* $(,)?
+    }) => {{
+        let (s, r) = $crate::channel::channel();
+        let stream = {
+            let this = &$first;
+            let object = this.upcast_ref::<$crate::Object>().downgrade();
+            let signal_id = $crate::ev_stream_any!(
+                @connect this, s, $first_event, | $($first_x),* | $first_body
+            );
+            $crate::EvStream::new(object, signal_id, r)
+        };
+        $(
+            let stream = {
+                let this = &$this;
+                let object = this.upcast_ref::<$crate::Object>().downgrade();
+                let signal_id = $crate::ev_stream_any!(@connect this, s, $event, | $($x),* | $body);
+                stream.with_linked_signals(object, vec![signal_id])
+            };
+        )*
+        stream.with_debug_name(stringify!($first_event $(| $event)*))
+    }};
+    ($this:expr, { $($event:tt : | $($x:pat_param),* | $body:expr),+ $(,)? }) => {{
+        let this = &$this;
+        let (s, r) = $crate::channel::channel();
+        let object = this.upcast_ref::<$crate::Object>().downgrade();
+        let signal_ids = vec![$($crate::ev_stream_any!(@connect this, s, $event, | $($x),* | $body)),+];
+        $crate::EvStream::with_signals(object, signal_ids, r)
+            .with_debug_name(stringify!($($event)|+))
+    }};
 }
@@ -0,0 +1,89 @@
+//! Logging the items or the errors of a stream through the `log` crate, with the `log` feature.
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use pin_project_lite::pin_project;
+use std::fmt::{Debug, Display};
+use std::pin::Pin;
+
+pin_project! {
+    /// `Stream` returned by [log_each](crate::EvStreamExt::log_each).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct LogEach<S> {
+        #[pin]
+        stream: S,
+        target: &'static str,
+        level: log::Level,
+    }
+}
+
+impl<S> LogEach<S> {
+    pub(crate) fn new(stream: S, target: &'static str, level: log::Level) -> Self {
+        Self {
+            stream,
+            target,
+            level,
+        }
+    }
+}
+
+impl<S> Stream for LogEach<S>
+where
+    S: Stream,
+    S::Item: Debug,
+{
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        if let Some(item) = &item {
+            log::log!(target: *this.target, *this.level, "{item:?}");
+        }
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(LogEach<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [unwrap_or_log](crate::EvStreamExt::unwrap_or_log).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct UnwrapOrLog<S> {
+        #[pin]
+        stream: S,
+        level: log::Level,
+    }
+}
+
+impl<S> UnwrapOrLog<S> {
+    pub(crate) fn new(stream: S, level: log::Level) -> Self {
+        Self { stream, level }
+    }
+}
+
+impl<S, T, E> Stream for UnwrapOrLog<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    E: Display,
+{
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut this = self.project();
+        loop {
+            match futures_core::ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(Ok(item)) => return Poll::Ready(Some(item)),
+                Some(Err(err)) => log::log!(*this.level, "skipped an emission: {err}"),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.stream.size_hint().1)
+    }
+}
+
+delegate_ev_stream_control!(UnwrapOrLog<S>, stream);
@@ -0,0 +1,118 @@
+//! Pausing the delivery of the items of every stream of the thread, see [GlobalPause].
+use futures_core::task::{Context, Waker};
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+/// What happens to the items emitted while a [GlobalPause] is held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseMode {
+    /// Keep them buffered, delivered once the pause is over.
+    Buffer,
+    /// Discard them.
+    Drop,
+}
+
+#[derive(Default)]
+struct Paused {
+    buffering: Cell<usize>,
+    dropping: Cell<usize>,
+    // The streams polled during the pause, woken once it's over.
+    wakers: Cell<Vec<Waker>>,
+}
+
+thread_local! {
+    static PAUSED: Paused = Paused::default();
+}
+
+/// Guard pausing every [EvStream](crate::EvStream) of the current thread while it's held,
+/// e.g. during a heavy import: they don't yield anything, so the combinators chained on
+/// them get no new items, and the items emitted meanwhile are buffered or dropped,
+/// depending on the [PauseMode]. Dropping the guard wakes the paused streams.
+///
+/// Guards can be nested; items are dropped as long as any `PauseMode::Drop` guard is
+/// held. Items the combinators already hold, like a pending debounce, may still be
+/// delivered during the pause.
+///
+/// # Examples
+/// ```ignore
+/// let pause = GlobalPause::new(PauseMode::Buffer);
+/// import_everything(&store);
+/// drop(pause); // the buffered events are delivered from now on
+/// ```
+#[must_use = "the pause ends as soon as the guard is dropped"]
+pub struct GlobalPause {
+    mode: PauseMode,
+    // Bound to the thread whose streams it pauses.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl GlobalPause {
+    pub fn new(mode: PauseMode) -> Self {
+        PAUSED.with(|paused| {
+            let count = counter(paused, mode);
+            count.set(count.get() + 1);
+        });
+        Self {
+            mode,
+            _not_send: PhantomData,
+        }
+    }
+
+    pub fn mode(&self) -> PauseMode {
+        self.mode
+    }
+}
+
+impl Drop for GlobalPause {
+    fn drop(&mut self) {
+        let wakers = PAUSED.with(|paused| {
+            let count = counter(paused, self.mode);
+            count.set(count.get() - 1);
+            if is_paused_now(paused) {
+                Vec::new()
+            } else {
+                paused.wakers.take()
+            }
+        });
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+fn counter(paused: &Paused, mode: PauseMode) -> &Cell<usize> {
+    match mode {
+        PauseMode::Buffer => &paused.buffering,
+        PauseMode::Drop => &paused.dropping,
+    }
+}
+
+fn is_paused_now(paused: &Paused) -> bool {
+    paused.buffering.get() + paused.dropping.get() > 0
+}
+
+/// Whether a pause is held, in which case the task of `cx` is woken once it's over.
+pub(crate) fn poll_paused(cx: &mut Context<'_>) -> bool {
+    PAUSED.with(|paused| {
+        if !is_paused_now(paused) {
+            return false;
+        }
+        let mut wakers = paused.wakers.take();
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        paused.wakers.set(wakers);
+        true
+    })
+}
+
+/// Whether a pause is held, without registering for a wakeup, for the non-async receiving
+/// paths.
+pub(crate) fn is_paused() -> bool {
+    PAUSED.with(is_paused_now)
+}
+
+/// Whether the items sent now must be discarded.
+pub(crate) fn drops_items() -> bool {
+    PAUSED.with(|paused| paused.dropping.get() > 0)
+}
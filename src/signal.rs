@@ -0,0 +1,341 @@
+//! Helpers for objects and signals only known at runtime.
+use crate::{EvStream, Object};
+use glib::prelude::*;
+use std::cell::Cell;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+
+/// Resolves once `object` is finalized, whatever holds the last reference to it.
+///
+/// Unlike [EvStream::disconnected], this isn't tied to a subscription. The returned
+/// future doesn't keep `object` alive.
+pub fn destroyed(object: &impl IsA<Object>) -> impl Future<Output = ()> {
+    let (s, r) = futures_channel::oneshot::channel();
+    let notify = object
+        .upcast_ref::<Object>()
+        .add_weak_ref_notify_local(move || {
+            let _ = s.send(());
+        });
+    let notify = NotifyGuard(Some(notify));
+    async move {
+        let _notify = notify;
+        let _ = r.await;
+    }
+}
+
+/// Removes the finalization callback of [destroyed] if its future is dropped first.
+struct NotifyGuard(Option<glib::object::WeakRefNotify<Object>>);
+
+impl Drop for NotifyGuard {
+    fn drop(&mut self) {
+        if let Some(notify) = self.0.take() {
+            notify.disconnect();
+        }
+    }
+}
+
+/// Error returned when an object isn't of the expected type.
+#[derive(Clone, Debug)]
+pub struct DowncastError {
+    pub object: Object,
+    pub expected: glib::Type,
+}
+
+impl fmt::Display for DowncastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected an object of type `{}`, got `{}`",
+            self.expected,
+            self.object.type_()
+        )
+    }
+}
+
+impl std::error::Error for DowncastError {}
+
+/// Error returned by [check_signal] when the object has no such signal.
+#[derive(Clone, Debug)]
+pub struct UnknownSignalError {
+    pub type_: glib::Type,
+    pub signal: String,
+}
+
+impl fmt::Display for UnknownSignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "type `{}` has no signal `{}`", self.type_, self.signal)
+    }
+}
+
+impl std::error::Error for UnknownSignalError {}
+
+/// Downcasts `object` to `O`, then builds the stream with `connect`, usually through
+/// [ev_stream](crate::ev_stream).
+///
+/// Allows typed signals on objects whose type is only known at runtime (e.g. plugins).
+///
+/// # Examples
+/// ```ignore
+/// let clicks = typed_signal_stream(object, |btn: &gtk::Button| ev_stream!(btn, clicked, |btn|))?;
+/// ```
+pub fn typed_signal_stream<O, T>(
+    object: Object,
+    connect: impl FnOnce(&O) -> EvStream<T>,
+) -> Result<EvStream<T>, DowncastError>
+where
+    O: IsA<Object>,
+{
+    match object.downcast_ref::<O>() {
+        Some(typed) => Ok(connect(typed)),
+        None => Err(DowncastError {
+            object,
+            expected: O::static_type(),
+        }),
+    }
+}
+
+/// Stream of the values of the property `name` of `object`, emitted on every `notify`.
+/// It can be moved to another object with [EvStream::rebind].
+///
+/// Connects with `connect_notify_local`, so the handler is attached to the
+/// `notify::<name>` detail once: other properties don't run it, and no detailed signal
+/// name is parsed on emission like with `connect_local("notify::<name>", ...)`.
+///
+/// # Panics
+/// When reading the property, if it doesn't hold a `V`.
+///
+/// # Examples
+/// ```ignore
+/// let titles = property_stream::<Option<String>>(&window, "title");
+/// ```
+pub fn property_stream<V>(object: &impl IsA<Object>, name: &str) -> EvStream<V>
+where
+    V: for<'b> glib::value::FromValue<'b> + 'static,
+{
+    notify_stream(object.upcast_ref(), name, false)
+}
+
+/// Like [property_stream], also emitting the current value of the property each time the
+/// stream is moved to another object with [EvStream::rebind], as the new object's value
+/// usually differs and no `notify` would report it.
+///
+/// Nothing is emitted when first connecting: chain `start_with` for that.
+///
+/// # Examples
+/// ```ignore
+/// let labels = property_stream_emit_on_rebind::<String>(&row_item, "label");
+/// // The widget got recycled for another item: `labels` yields its label right away.
+/// labels.rebind(&other_item)?;
+/// ```
+pub fn property_stream_emit_on_rebind<V>(object: &impl IsA<Object>, name: &str) -> EvStream<V>
+where
+    V: for<'b> glib::value::FromValue<'b> + 'static,
+{
+    notify_stream(object.upcast_ref(), name, true)
+}
+
+fn notify_stream<V>(object: &Object, name: &str, emit_on_rebind: bool) -> EvStream<V>
+where
+    V: for<'b> glib::value::FromValue<'b> + 'static,
+{
+    let debug_name = format!("notify::{name}");
+    let name = name.to_owned();
+    let rebound = Cell::new(false);
+    EvStream::rebindable(object, move |object, s| {
+        if emit_on_rebind && rebound.replace(true) {
+            let _ = s.send(object.property::<V>(&name));
+        }
+        object.connect_notify_local(Some(&name), move |object, pspec| {
+            crate::__no_unwind(|| {
+                let _ = s.send(object.property::<V>(pspec.name()));
+            })
+        })
+    })
+    .with_debug_name(&debug_name)
+}
+
+/// Like [property_stream], also returning a setter for the property whose changes are
+/// left out of the stream, to avoid feedback loops in two-way bindings.
+///
+/// The `notify` emitted while the setter runs is skipped. If notifications are frozen
+/// (see `ObjectExt::freeze_notify`), it comes later and isn't recognized as an echo.
+///
+/// # Examples
+/// ```ignore
+/// let (values, set_value) = property_stream_no_echo::<f64>(&adjustment, "value");
+/// set_value(0.5); // `values` doesn't yield 0.5
+/// ```
+pub fn property_stream_no_echo<V>(
+    object: &impl IsA<Object>,
+    name: &str,
+) -> (EvStream<V>, impl Fn(V))
+where
+    V: for<'b> glib::value::FromValue<'b> + ToValue + 'static,
+{
+    let (s, r) = crate::channel::channel();
+    let object = object.upcast_ref::<Object>();
+    let echo = Rc::new(Cell::new(false));
+    let signal_id = object.connect_notify_local(Some(name), {
+        let echo = echo.clone();
+        move |object, pspec| {
+            if !echo.get() {
+                crate::__no_unwind(|| {
+                    let _ = s.send(object.property::<V>(pspec.name()));
+                })
+            }
+        }
+    });
+    let stream =
+        EvStream::new(object.downgrade(), signal_id, r).with_debug_name(&format!("notify::{name}"));
+    let weak = object.downgrade();
+    let name = name.to_owned();
+    let set = move |value: V| {
+        if let Some(object) = weak.upgrade() {
+            echo.set(true);
+            object.set_property_from_value(&name, &value.to_value());
+            echo.set(false);
+        }
+    };
+    (stream, set)
+}
+
+/// Stream of the changes of the properties `names` of `object`, as the name of the changed
+/// property and its new value.
+///
+/// A single `notify` handler, without detail, is connected for all of them and skips the
+/// other properties, instead of a detailed handler per property. Names may use
+/// underscores instead of dashes. It can be moved to another object with
+/// [EvStream::rebind].
+///
+/// # Examples
+/// ```ignore
+/// let changes = properties_stream(&window, &["default-width", "default-height"]);
+/// while let Some((name, value)) = changes.next().await {
+///     // ...
+/// }
+/// ```
+pub fn properties_stream(
+    object: &impl IsA<Object>,
+    names: &[&str],
+) -> EvStream<(String, glib::Value)> {
+    let names: Rc<[String]> = names.iter().map(|name| name.replace('_', "-")).collect();
+    EvStream::rebindable(object, move |object, s| {
+        let names = names.clone();
+        object.connect_notify_local(None, move |object, pspec| {
+            let name = pspec.name();
+            if names.iter().any(|watched| watched == name) {
+                crate::__no_unwind(|| {
+                    let _ = s.send((name.to_owned(), object.property_value(name)));
+                })
+            }
+        })
+    })
+    .with_debug_name("notify")
+}
+
+/// Types which can be built from the raw arguments of a signal emission.
+///
+/// The first value is always the emitting object, followed by the signal parameters.
+/// With the `derive` feature, it can be derived for structs, whose fields get the
+/// arguments in order.
+///
+/// # Examples
+/// ```ignore
+/// struct RowActivated {
+///     view: gtk::TreeView,
+///     path: gtk::TreePath,
+///     column: Option<gtk::TreeViewColumn>,
+/// }
+///
+/// impl FromSignalArgs for RowActivated {
+///     fn from_signal_args(args: &[glib::Value]) -> Option<Self> {
+///         match args {
+///             [view, path, column] => Some(Self {
+///                 view: view.get().ok()?,
+///                 path: path.get().ok()?,
+///                 column: column.get().ok()?,
+///             }),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let rows = signal_args_stream::<RowActivated>(&view, "row-activated");
+/// ```
+pub trait FromSignalArgs: Sized {
+    /// Extracts `Self` from `args`, or `None` if their number or types don't match.
+    fn from_signal_args(args: &[glib::Value]) -> Option<Self>;
+}
+
+/// Stream of the emissions of `signal` on `object`, converted with [FromSignalArgs].
+///
+/// Emissions whose arguments can't be converted are skipped.
+pub fn signal_args_stream<T>(object: &impl IsA<Object>, signal: &str) -> EvStream<T>
+where
+    T: FromSignalArgs + 'static,
+{
+    let (s, r) = crate::channel::channel();
+    let object = object.upcast_ref::<Object>();
+    let signal_id = object.connect_local(signal, false, move |args| {
+        crate::__no_unwind(|| {
+            if let Some(item) = T::from_signal_args(args) {
+                let _ = s.send(item);
+            }
+        });
+        None
+    });
+    EvStream::new(object.downgrade(), signal_id, r).with_debug_name(signal)
+}
+
+/// Stream of the emissions of `signal` on `object`, yielding all their arguments,
+/// starting with the object itself. Connects after the default handler if `after`.
+///
+/// The untyped form of [ev_stream](crate::ev_stream) yields the same without a body.
+pub fn raw_signal_stream(
+    object: &impl IsA<Object>,
+    signal: &str,
+    after: bool,
+) -> EvStream<Vec<glib::Value>> {
+    let (s, r) = crate::channel::channel();
+    let object = object.upcast_ref::<Object>();
+    let signal_id = object.connect_local(signal, after, move |args| {
+        crate::__no_unwind(|| {
+            let _ = s.send(args.to_vec());
+        });
+        None
+    });
+    EvStream::new(object.downgrade(), signal_id, r).with_debug_name(signal)
+}
+
+/// Like [signal_args_stream], first checking that the type of `object` registered
+/// `signal`, instead of connecting to a signal which never fires.
+///
+/// Meant for the signals of subclasses, defined at runtime with
+/// `glib::subclass::Signal`, whose names the compiler can't check.
+pub fn subclass_signal_stream<T>(
+    object: &impl IsA<Object>,
+    signal: &str,
+) -> Result<EvStream<T>, UnknownSignalError>
+where
+    T: FromSignalArgs + 'static,
+{
+    check_signal(object, signal)?;
+    Ok(signal_args_stream(object, signal))
+}
+
+/// Checks that the type of `object` registered `signal`, which may carry a detail
+/// (e.g. `"notify::label"`).
+///
+/// Connecting by name to a signal which doesn't exist panics: this lets the callers
+/// of [try_ev_stream](crate::try_ev_stream) and [subclass_signal_stream] handle it.
+pub fn check_signal(object: &impl IsA<Object>, signal: &str) -> Result<(), UnknownSignalError> {
+    let type_ = object.as_ref().type_();
+    match glib::subclass::SignalId::parse_name(signal, type_, false) {
+        Some(_) => Ok(()),
+        None => Err(UnknownSignalError {
+            type_,
+            signal: signal.to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,94 @@
+//! Sinks turning items back into signal emissions or property changes.
+use crate::Object;
+use futures_core::task::{Context, Poll};
+use futures_sink::Sink;
+use glib::prelude::*;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// Emits `signal` on `object` for every sent argument list (without the object itself).
+///
+/// Only a weak reference is kept: once the object is finalized, sent items are ignored.
+pub fn emit_stream(object: &impl IsA<Object>, signal: &str) -> EmitSink {
+    EmitSink {
+        object: object.upcast_ref().downgrade(),
+        signal: signal.to_owned(),
+    }
+}
+
+/// `Sink` returned by [emit_stream].
+#[must_use = "sinks do nothing unless polled"]
+pub struct EmitSink {
+    object: glib::WeakRef<Object>,
+    signal: String,
+}
+
+impl Sink<Vec<glib::Value>> for EmitSink {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, args: Vec<glib::Value>) -> Result<(), Self::Error> {
+        if let Some(object) = self.object.upgrade() {
+            object.emit_by_name_with_values(&self.signal, &args);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Sets the property `name` of `object` to every sent value.
+///
+/// Together with [property_stream](crate::property_stream), allows two-way bindings.
+/// Only a weak reference is kept: once the object is finalized, sent items are ignored.
+///
+/// # Panics
+/// When sending, if the property doesn't exist or doesn't accept a `V`.
+pub fn property_sink<V: ToValue>(object: &impl IsA<Object>, name: &str) -> PropertySink<V> {
+    PropertySink {
+        object: object.upcast_ref().downgrade(),
+        name: name.to_owned(),
+        value: PhantomData,
+    }
+}
+
+/// `Sink` returned by [property_sink].
+#[must_use = "sinks do nothing unless polled"]
+pub struct PropertySink<V> {
+    object: glib::WeakRef<Object>,
+    name: String,
+    value: PhantomData<fn(V)>,
+}
+
+impl<V: ToValue> Sink<V> for PropertySink<V> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: V) -> Result<(), Self::Error> {
+        if let Some(object) = self.object.upgrade() {
+            object.set_property_from_value(&self.name, &value.to_value());
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,80 @@
+//! Streams fed by signal-like sources which aren't a `glib::Object`.
+use crate::connection::Handle;
+use crate::{EvStream, Object};
+use glib::prelude::*;
+use std::rc::Rc;
+
+/// Callback given to [SignalSource::connect_raw], with the arguments of an emission.
+pub type RawCallback = Box<dyn Fn(&[glib::Value])>;
+
+/// Something emitting named signals, like a `glib::Object` does, which [source_stream] can
+/// connect to, e.g. a model implemented in plain Rust, or an in-memory mock in tests.
+pub trait SignalSource {
+    /// Identifies a connected callback, to disconnect it.
+    type HandlerId: 'static;
+
+    /// Registers `callback`, to be called on every emission of the signal `name`.
+    fn connect_raw(&self, name: &str, callback: RawCallback) -> Self::HandlerId;
+
+    /// Removes a callback registered with [connect_raw](SignalSource::connect_raw). It
+    /// mustn't be called anymore afterwards.
+    fn disconnect_raw(&self, id: Self::HandlerId);
+}
+
+/// Connects with `connect_local`: the arguments start with the emitting object, and no
+/// value is returned to the emitter.
+impl<O: IsA<Object>> SignalSource for O {
+    type HandlerId = glib::SignalHandlerId;
+
+    fn connect_raw(&self, name: &str, callback: RawCallback) -> glib::SignalHandlerId {
+        self.connect_local(name, false, move |args| {
+            callback(args);
+            None
+        })
+    }
+
+    fn disconnect_raw(&self, id: glib::SignalHandlerId) {
+        ObjectExt::disconnect(self, id);
+    }
+}
+
+/// Stream of `map(args)` for each emission of the signal `name` of `source`.
+///
+/// The callback is disconnected like the one of any [EvStream], through
+/// [disconnect_raw](SignalSource::disconnect_raw) if `source` is still alive; the stream
+/// doesn't keep it alive. The source is no object, so [EvStream::object] returns `None`,
+/// and the stream is connected until it's disconnected or dropped. Blocking it only
+/// works with signal handlers: prefer [ev_stream](crate::ev_stream) for a `glib::Object`.
+///
+/// # Examples
+/// ```ignore
+/// let source = Rc::new(MockSource::default());
+/// let values = source_stream(&source, "changed", |args| args[0].get::<u32>().unwrap());
+/// source.emit("changed", &[1u32.to_value()]);
+/// ```
+pub fn source_stream<S, T>(
+    source: &Rc<S>,
+    name: &str,
+    map: impl Fn(&[glib::Value]) -> T + 'static,
+) -> EvStream<T>
+where
+    S: SignalSource + 'static,
+    T: 'static,
+{
+    let (s, r) = crate::channel::channel();
+    let id = source.connect_raw(
+        name,
+        Box::new(move |args| {
+            crate::__no_unwind(|| {
+                let _ = s.send(map(args));
+            })
+        }),
+    );
+    let source = Rc::downgrade(source);
+    let release = move || {
+        if let Some(source) = source.upgrade() {
+            source.disconnect_raw(id);
+        }
+    };
+    EvStream::with_handle(glib::WeakRef::new(), Handle::Unbound(Box::new(release)), r)
+}
@@ -0,0 +1,607 @@
+//! Timer-based combinators.
+//!
+//! Every timeout or idle source is owned by a [Timer], which destroys it on drop: a
+//! combinator dropped while waiting removes its pending source from the main context,
+//! like [EvStream](crate::EvStream) disconnects its callbacks. Combinators must never
+//! attach a source without going through [Timer], which is also where the timeouts are
+//! replaced by a [ManualClock](crate::ManualClock), with the `manual-clock` feature.
+use crate::EvStreamControl;
+use futures_channel::oneshot;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// One-shot timer attached to a given `MainContext`.
+/// The underlying source is destroyed when the timer is dropped.
+pub(crate) struct Timer {
+    source: TimerSource,
+    fired: oneshot::Receiver<()>,
+}
+
+/// What makes a [Timer] fire.
+enum TimerSource {
+    Glib(glib::Source),
+    #[cfg(feature = "manual-clock")]
+    Manual(crate::clock::Scheduled),
+}
+
+impl Timer {
+    /// Fires once `duration` passed, or once the [ManualClock](crate::ManualClock)
+    /// installed on the thread reaches it.
+    pub(crate) fn new(duration: Duration, ctx: &glib::MainContext) -> Self {
+        #[cfg(feature = "manual-clock")]
+        if let Some((scheduled, fired)) = crate::clock::schedule(duration) {
+            return Self {
+                source: TimerSource::Manual(scheduled),
+                fired,
+            };
+        }
+        Self::timeout(duration, ctx)
+    }
+
+    /// Like [new](Timer::new), always using the real time.
+    pub(crate) fn timeout(duration: Duration, ctx: &glib::MainContext) -> Self {
+        let (callback, fired) = fire_once();
+        let source = glib::timeout_source_new(duration, None, glib::Priority::DEFAULT, callback);
+        source.attach(Some(ctx));
+        Self {
+            source: TimerSource::Glib(source),
+            fired,
+        }
+    }
+
+    /// Fires once `ctx` has no more pending events of higher priority.
+    pub(crate) fn idle(ctx: &glib::MainContext) -> Self {
+        let (callback, fired) = fire_once();
+        let source = glib::idle_source_new(None, glib::Priority::DEFAULT_IDLE, callback);
+        source.attach(Some(ctx));
+        Self {
+            source: TimerSource::Glib(source),
+            fired,
+        }
+    }
+}
+
+fn fire_once() -> (
+    impl FnMut() -> glib::ControlFlow + Send + 'static,
+    oneshot::Receiver<()>,
+) {
+    let (s, fired) = oneshot::channel();
+    let mut s = Some(s);
+    let callback = move || {
+        if let Some(s) = s.take() {
+            let _ = s.send(());
+        }
+        glib::ControlFlow::Break
+    };
+    (callback, fired)
+}
+
+impl Future for Timer {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.fired).poll(cx).map(|_| ())
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        match &self.source {
+            TimerSource::Glib(source) => source.destroy(),
+            #[cfg(feature = "manual-clock")]
+            TimerSource::Manual(scheduled) => scheduled.cancel(),
+        }
+    }
+}
+
+pin_project! {
+    /// `Stream` returned by [debounce](crate::EvStreamExt::debounce).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Debounce<S: Stream> {
+        #[pin]
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+        pending: Option<S::Item>,
+        timer: Option<Timer>,
+        done: bool,
+    }
+}
+
+impl<S: Stream> Debounce<S> {
+    pub(crate) fn new(stream: S, duration: Duration, ctx: glib::MainContext) -> Self {
+        Self {
+            stream,
+            duration,
+            ctx,
+            pending: None,
+            timer: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for Debounce<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        while let Poll::Ready(next) = this.stream.as_mut().poll_next(cx) {
+            match next {
+                Some(item) => {
+                    *this.pending = Some(item);
+                    *this.timer = Some(Timer::new(*this.duration, this.ctx));
+                }
+                None => {
+                    // Flush the last item instead of losing it.
+                    *this.done = true;
+                    *this.timer = None;
+                    return Poll::Ready(this.pending.take());
+                }
+            }
+        }
+        if let Some(timer) = this.timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                *this.timer = None;
+                return Poll::Ready(this.pending.take());
+            }
+        }
+        Poll::Pending
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] Debounce<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [throttle](crate::EvStreamExt::throttle),
+    /// [throttle_latest](crate::EvStreamExt::throttle_latest) and
+    /// [throttle_opts](crate::EvStreamExt::throttle_opts).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Throttle<S: Stream> {
+        #[pin]
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+        leading: bool,
+        trailing: bool,
+        pending: Option<S::Item>,
+        timer: Option<Timer>,
+        done: bool,
+    }
+}
+
+impl<S: Stream> Throttle<S> {
+    pub(crate) fn new(
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+        leading: bool,
+        trailing: bool,
+    ) -> Self {
+        Self {
+            stream,
+            duration,
+            ctx,
+            leading,
+            trailing,
+            pending: None,
+            timer: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for Throttle<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        while let Poll::Ready(next) = this.stream.as_mut().poll_next(cx) {
+            match next {
+                Some(item) => {
+                    let window_open = this.timer.is_some();
+                    if !window_open {
+                        *this.timer = Some(Timer::new(*this.duration, this.ctx));
+                        if *this.leading {
+                            return Poll::Ready(Some(item));
+                        }
+                    }
+                    if *this.trailing {
+                        *this.pending = Some(item);
+                    }
+                }
+                None => {
+                    *this.done = true;
+                    *this.timer = None;
+                    return Poll::Ready(this.pending.take());
+                }
+            }
+        }
+        if let Some(timer) = this.timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                *this.timer = None;
+                if let Some(item) = this.pending.take() {
+                    // The trailing item opens a new window, so that items are never
+                    // closer than `duration`.
+                    *this.timer = Some(Timer::new(*this.duration, this.ctx));
+                    return Poll::Ready(Some(item));
+                }
+            }
+        }
+        Poll::Pending
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] Throttle<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [chunks_timeout](crate::EvStreamExt::chunks_timeout).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct ChunksTimeout<S: Stream> {
+        #[pin]
+        stream: S,
+        max: usize,
+        duration: Duration,
+        ctx: glib::MainContext,
+        chunk: Vec<S::Item>,
+        timer: Option<Timer>,
+        done: bool,
+    }
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    pub(crate) fn new(stream: S, max: usize, duration: Duration, ctx: glib::MainContext) -> Self {
+        assert!(max > 0, "chunks_timeout: max must be at least 1");
+        Self {
+            stream,
+            max,
+            duration,
+            ctx,
+            chunk: Vec::new(),
+            timer: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        while let Poll::Ready(next) = this.stream.as_mut().poll_next(cx) {
+            match next {
+                Some(item) => {
+                    // The delay counts from the first item of the chunk.
+                    if this.chunk.is_empty() {
+                        *this.timer = Some(Timer::new(*this.duration, this.ctx));
+                    }
+                    this.chunk.push(item);
+                    if this.chunk.len() >= *this.max {
+                        *this.timer = None;
+                        return Poll::Ready(Some(std::mem::take(this.chunk)));
+                    }
+                }
+                None => {
+                    *this.done = true;
+                    *this.timer = None;
+                    if this.chunk.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(std::mem::take(this.chunk)));
+                }
+            }
+        }
+        if let Some(timer) = this.timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                *this.timer = None;
+                return Poll::Ready(Some(std::mem::take(this.chunk)));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] ChunksTimeout<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [batch_per_iteration](crate::EvStreamExt::batch_per_iteration).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct BatchPerIteration<S: Stream> {
+        #[pin]
+        stream: S,
+        batch: Vec<S::Item>,
+        ctx: glib::MainContext,
+        idle: Option<Timer>,
+        done: bool,
+    }
+}
+
+impl<S: Stream> BatchPerIteration<S> {
+    pub(crate) fn new(stream: S, ctx: glib::MainContext) -> Self {
+        Self {
+            stream,
+            batch: Vec::new(),
+            ctx,
+            idle: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for BatchPerIteration<S> {
+    type Item = Vec<S::Item>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        // One allocation per batch, however long: the hint is exact for an `EvStream`.
+        this.batch.reserve(this.stream.size_hint().0);
+        while let Poll::Ready(next) = this.stream.as_mut().poll_next(cx) {
+            match next {
+                Some(item) => this.batch.push(item),
+                None => {
+                    *this.done = true;
+                    *this.idle = None;
+                    if this.batch.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(std::mem::take(this.batch)));
+                }
+            }
+        }
+        if this.batch.is_empty() {
+            return Poll::Pending;
+        }
+        let ctx = this.ctx;
+        let idle = this.idle.get_or_insert_with(|| Timer::idle(ctx));
+        if Pin::new(idle).poll(cx).is_ready() {
+            *this.idle = None;
+            return Poll::Ready(Some(std::mem::take(this.batch)));
+        }
+        Poll::Pending
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] BatchPerIteration<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [delay_each](crate::EvStreamExt::delay_each).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct DelayEach<S: Stream> {
+        #[pin]
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+        pending: Option<(S::Item, Timer)>,
+    }
+}
+
+impl<S: Stream> DelayEach<S> {
+    pub(crate) fn new(stream: S, duration: Duration, ctx: glib::MainContext) -> Self {
+        Self {
+            stream,
+            duration,
+            ctx,
+            pending: None,
+        }
+    }
+}
+
+impl<S: Stream> Stream for DelayEach<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if this.pending.is_none() {
+            match futures_core::ready!(this.stream.poll_next(cx)) {
+                Some(item) => *this.pending = Some((item, Timer::new(*this.duration, this.ctx))),
+                None => return Poll::Ready(None),
+            }
+        }
+        if let Some((_, timer)) = this.pending {
+            futures_core::ready!(Pin::new(timer).poll(cx));
+        }
+        Poll::Ready(this.pending.take().map(|(item, _)| item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = usize::from(self.pending.is_some());
+        let (low, high) = self.stream.size_hint();
+        (
+            low.saturating_add(pending),
+            high.and_then(|high| high.checked_add(pending)),
+        )
+    }
+}
+
+delegate_ev_stream_control!([S: Stream + EvStreamControl] DelayEach<S>, stream);
+
+/// `Future` paired with each item by [with_deadline](crate::EvStreamExt::with_deadline),
+/// resolving once the deadline of the item passed. Dropping it removes its timer.
+#[must_use = "futures do nothing unless polled"]
+pub struct Deadline {
+    timer: Timer,
+}
+
+impl Future for Deadline {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.timer).poll(cx)
+    }
+}
+
+pin_project! {
+    /// `Stream` returned by [with_deadline](crate::EvStreamExt::with_deadline).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct WithDeadline<S> {
+        #[pin]
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+    }
+}
+
+impl<S> WithDeadline<S> {
+    pub(crate) fn new(stream: S, duration: Duration, ctx: glib::MainContext) -> Self {
+        Self {
+            stream,
+            duration,
+            ctx,
+        }
+    }
+}
+
+impl<S: Stream> Stream for WithDeadline<S> {
+    type Item = (S::Item, Deadline);
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        Poll::Ready(item.map(|item| {
+            let timer = Timer::new(*this.duration, this.ctx);
+            (item, Deadline { timer })
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+delegate_ev_stream_control!(WithDeadline<S>, stream);
+
+pin_project! {
+    /// `Stream` returned by [end_after](crate::EvStreamExt::end_after).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct EndAfter<S> {
+        #[pin]
+        stream: S,
+        // `None` once the stream ended, either way.
+        timer: Option<Timer>,
+    }
+}
+
+impl<S> EndAfter<S> {
+    pub(crate) fn new(stream: S, duration: Duration, ctx: &glib::MainContext) -> Self {
+        Self {
+            stream,
+            timer: Some(Timer::new(duration, ctx)),
+        }
+    }
+}
+
+impl<S: Stream> Stream for EndAfter<S> {
+    type Item = S::Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.project();
+        // Checked first: a stream with items always ready would never let it fire.
+        let Some(timer) = this.timer else {
+            return Poll::Ready(None);
+        };
+        if Pin::new(timer).poll(cx).is_ready() {
+            *this.timer = None;
+            return Poll::Ready(None);
+        }
+        let item = futures_core::ready!(this.stream.poll_next(cx));
+        if item.is_none() {
+            *this.timer = None;
+        }
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.timer {
+            Some(_) => (0, self.stream.size_hint().1),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+delegate_ev_stream_control!(EndAfter<S>, stream);
+
+/// Item of [settle_marker](crate::EvStreamExt::settle_marker).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Settled<T> {
+    /// An item of the stream, yielded as soon as it comes.
+    Item(T),
+    /// The quiet period passed since the last item.
+    Quiescent,
+}
+
+pin_project! {
+    /// `Stream` returned by [settle_marker](crate::EvStreamExt::settle_marker).
+    #[must_use = "streams do nothing unless polled"]
+    pub struct SettleMarker<S> {
+        #[pin]
+        stream: S,
+        duration: Duration,
+        ctx: glib::MainContext,
+        // Restarted by every item, `None` while settled.
+        timer: Option<Timer>,
+        done: bool,
+    }
+}
+
+impl<S> SettleMarker<S> {
+    pub(crate) fn new(stream: S, duration: Duration, ctx: glib::MainContext) -> Self {
+        Self {
+            stream,
+            duration,
+            ctx,
+            timer: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for SettleMarker<S> {
+    type Item = Settled<S::Item>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                *this.timer = Some(Timer::new(*this.duration, this.ctx));
+                return Poll::Ready(Some(Settled::Item(item)));
+            }
+            Poll::Ready(None) => {
+                *this.done = true;
+                *this.timer = None;
+                return Poll::Ready(None);
+            }
+            Poll::Pending => {}
+        }
+        if let Some(timer) = this.timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                *this.timer = None;
+                return Poll::Ready(Some(Settled::Quiescent));
+            }
+        }
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let (lower, upper) = self.stream.size_hint();
+        // Each item can be followed by a marker.
+        (
+            lower,
+            upper.and_then(|upper| upper.checked_mul(2)?.checked_add(1)),
+        )
+    }
+}
+
+delegate_ev_stream_control!(SettleMarker<S>, stream);
@@ -0,0 +1,330 @@
+//! Ready-made streams for common gtk widgets.
+use crate::{channel, ev_stream, EvStream, Object, Overflow};
+use gtk::{gdk, gio, glib, prelude::*};
+use std::cell::{Cell, RefCell};
+
+/// Stream of the values of `adj`, emitted on `value-changed`.
+///
+/// The stream is [`latest_only`](EvStream::latest_only): during a drag only the
+/// freshest value is delivered.
+pub fn adjustment_value_stream(adj: &gtk::Adjustment) -> EvStream<f64> {
+    ev_stream!(adj, value_changed, |adj| adj.value()).latest_only()
+}
+
+/// Stream of the value of `scale`, emitted when the user releases it after a drag or a
+/// click, rather than on every `value-changed` in between.
+///
+/// The releases are watched by an event controller added to `scale` in the capture
+/// phase, so the gestures of the scale don't hide them. The controller is removed when
+/// the stream is disconnected.
+pub fn adjustment_commit_stream(scale: &gtk::Scale) -> EvStream<f64> {
+    let (s, r) = channel::channel();
+    let controller = gtk::EventControllerLegacy::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_event(move |controller, event| {
+        let released = matches!(
+            event.event_type(),
+            gdk::EventType::ButtonRelease | gdk::EventType::TouchEnd
+        );
+        if let (true, Some(scale)) = (released, controller.widget()) {
+            if let Some(scale) = scale.downcast_ref::<gtk::Scale>() {
+                crate::__no_unwind(|| {
+                    let _ = s.send(scale.value());
+                });
+            }
+        }
+        glib::Propagation::Proceed
+    });
+    // Only the scale owns the controller, so that the stream ends with the scale.
+    let weak_controller = controller.downgrade();
+    scale.add_controller(controller);
+    let object = scale.upcast_ref::<Object>().downgrade();
+    EvStream::with_teardown(
+        object,
+        move |scale| {
+            if let (Some(scale), Some(controller)) = (
+                scale.downcast_ref::<gtk::Widget>(),
+                weak_controller.upgrade(),
+            ) {
+                scale.remove_controller(&controller);
+            }
+        },
+        r,
+    )
+    .with_debug_name("commit")
+}
+
+/// Stream of the `(width, height, baseline)` of `widget`, emitted when it changes.
+///
+/// GTK4 has no `size-allocate` signal, so the size is checked on every frame with a
+/// tick callback, which keeps the frame clock running while the stream is alive.
+/// The stream is [`latest_only`](EvStream::latest_only).
+pub fn size_allocate_stream(widget: &impl IsA<gtk::Widget>) -> EvStream<(i32, i32, i32)> {
+    let (s, r) = channel::channel();
+    let last = Cell::new(None);
+    let tick_id = widget.add_tick_callback(move |widget, _| {
+        let size = (widget.width(), widget.height(), widget.allocated_baseline());
+        if last.replace(Some(size)) != Some(size) {
+            crate::__no_unwind(|| {
+                let _ = s.send(size);
+            });
+        }
+        glib::ControlFlow::Continue
+    });
+    let object = widget
+        .upcast_ref::<gtk::Widget>()
+        .upcast_ref::<Object>()
+        .downgrade();
+    EvStream::with_teardown(object, move |_| tick_id.remove(), r)
+        .with_debug_name("tick")
+        .latest_only()
+}
+
+/// Stream of the text of `editable` (e.g. a `gtk::Entry` or `gtk::SearchEntry`),
+/// emitted on every `changed`.
+pub fn entry_text_stream(editable: &impl IsA<gtk::Editable>) -> EvStream<String> {
+    ev_stream!(
+        editable.upcast_ref::<gtk::Editable>(),
+        changed,
+        |editable| { editable.text().to_string() }
+    )
+}
+
+/// Stream of the text of `entry`, emitted on `activate`, when the user presses Enter.
+pub fn entry_activate_stream(entry: &impl IsA<gtk::Entry>) -> EvStream<String> {
+    ev_stream!(entry.upcast_ref::<gtk::Entry>(), activate, |entry| {
+        entry.text().to_string()
+    })
+}
+
+/// Stream of the `search-started` emissions of `entry`, when the user starts typing in
+/// it, or in the widget it captures the key events of.
+pub fn search_started_stream(entry: &gtk::SearchEntry) -> EvStream<()> {
+    ev_stream!(entry, search_started, |_| ())
+}
+
+/// Stream of the `stop-search` emissions of `entry`, when the user stops the search,
+/// e.g. by pressing Escape.
+pub fn search_stopped_stream(entry: &gtk::SearchEntry) -> EvStream<()> {
+    ev_stream!(entry, stop_search, |_| ())
+}
+
+/// A child added to or removed from a widget, see [children_stream].
+#[derive(Clone, Debug)]
+pub enum ChildEvent {
+    Added(gtk::Widget),
+    Removed(gtk::Widget),
+}
+
+/// Stream of the children added to and removed from `widget`.
+///
+/// Built on `gtk::Widget::observe_children`, which GTK documents as slow:
+/// prefer it for containers with few children.
+pub fn children_stream(widget: &impl IsA<gtk::Widget>) -> EvStream<ChildEvent> {
+    let (s, r) = channel::channel();
+    let model = widget.observe_children();
+    let child = |model: &gio::ListModel, i| model.item(i).and_downcast::<gtk::Widget>();
+    let children = RefCell::new(
+        (0..model.n_items())
+            .filter_map(|i| child(&model, i))
+            .collect::<Vec<_>>(),
+    );
+    let signal_id = model.connect_items_changed(move |model, position, removed, added| {
+        let (position, removed, added) = (position as usize, removed as usize, added as usize);
+        let mut children = children.borrow_mut();
+        let new_children = (position..position + added).filter_map(|i| child(model, i as u32));
+        let old_children: Vec<_> = children
+            .splice(position..position + removed, new_children)
+            .collect();
+        crate::__no_unwind(|| {
+            for widget in old_children {
+                let _ = s.send(ChildEvent::Removed(widget));
+            }
+            for widget in &children[position..position + added] {
+                let _ = s.send(ChildEvent::Added(widget.clone()));
+            }
+        });
+    });
+    let object = widget
+        .upcast_ref::<gtk::Widget>()
+        .upcast_ref::<Object>()
+        .downgrade();
+    EvStream::with_teardown(object, move |_| model.disconnect(signal_id), r)
+        .with_debug_name("observe-children::items-changed")
+}
+
+/// Stream of the `(width, height)` of `area`, emitted on `resize`.
+///
+/// The stream is [`latest_only`](EvStream::latest_only). GTK queues a redraw after a
+/// resize, so there is no need to call `queue_draw` when handling an item, unless the
+/// drawing depends on state updated asynchronously.
+pub fn resize_stream(area: &gtk::DrawingArea) -> EvStream<(i32, i32)> {
+    ev_stream!(area, resize, |_, width, height| (width, height)).latest_only()
+}
+
+/// Stream of the state of `button`, emitted on every `toggled`.
+pub fn toggled_stream(button: &impl IsA<gtk::ToggleButton>) -> EvStream<bool> {
+    ev_stream!(
+        button.upcast_ref::<gtk::ToggleButton>(),
+        toggled,
+        |button| { button.is_active() }
+    )
+}
+
+/// Like [toggled_stream], for check buttons, which aren't toggle buttons in gtk4.
+pub fn check_button_toggled_stream(button: &impl IsA<gtk::CheckButton>) -> EvStream<bool> {
+    ev_stream!(button.upcast_ref::<gtk::CheckButton>(), toggled, |button| {
+        button.is_active()
+    })
+}
+
+/// Stream of the full text of `buffer`, emitted on every `changed`.
+///
+/// `changed` fires for each edit (every keystroke while typing) and the whole text is
+/// copied each time: for big buffers, consider chaining
+/// [debounce](crate::EvStreamExt::debounce).
+pub fn text_buffer_stream(buffer: &gtk::TextBuffer) -> EvStream<String> {
+    ev_stream!(buffer, changed, |buffer| {
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, false).to_string()
+    })
+}
+
+/// Stream of the name of the visible child of `stack`, emitted when it changes.
+pub fn visible_child_stream(stack: &gtk::Stack) -> EvStream<Option<String>> {
+    ev_stream!(stack, visible_child_name_notify, |stack| {
+        stack.visible_child_name().map(|name| name.to_string())
+    })
+}
+
+/// Stream of the index of the page `notebook` switches to, emitted on `switch-page`.
+pub fn page_switched_stream(notebook: &gtk::Notebook) -> EvStream<u32> {
+    ev_stream!(notebook, switch_page, |_, _page, page_num| page_num)
+}
+
+/// Stream of the state of `revealer` once its transition finished, emitted when
+/// `child-revealed` changes: `true` when the child got fully shown, `false` when hidden.
+pub fn reveal_finished_stream(revealer: &gtk::Revealer) -> EvStream<bool> {
+    ev_stream!(revealer, child_revealed_notify, |revealer| revealer
+        .is_child_revealed())
+}
+
+/// Stream of the position of the item selected in `dropdown`, emitted when `selected`
+/// changes. `gtk::INVALID_LIST_POSITION` means that nothing is selected.
+pub fn selected_stream(dropdown: &gtk::DropDown) -> EvStream<u32> {
+    ev_stream!(dropdown, selected_notify, |dropdown| dropdown.selected())
+}
+
+/// Stream yielding `()` once, when `widget` is destroyed, then ending: the usual "the
+/// widget is going away" notification.
+///
+/// The stream also ends, without yielding, if `widget` is finalized without being
+/// destroyed.
+pub fn destroy_stream(widget: &impl IsA<gtk::Widget>) -> EvStream<()> {
+    let (s, r) = channel::channel();
+    // Dropped after the first emission, which closes the channel.
+    let s = Cell::new(Some(s));
+    let widget = widget.upcast_ref::<gtk::Widget>();
+    let signal_id = widget.connect_destroy(move |_| {
+        if let Some(s) = s.take() {
+            crate::__no_unwind(|| {
+                let _ = s.send(());
+            });
+        }
+    });
+    let object = widget.upcast_ref::<Object>().downgrade();
+    EvStream::new(object, signal_id, r).with_debug_name("destroy")
+}
+
+/// Stream of the focus state of `window`, emitted when `is-active` changes.
+pub fn window_active_stream(window: &impl IsA<gtk::Window>) -> EvStream<bool> {
+    ev_stream!(
+        window.upcast_ref::<gtk::Window>(),
+        is_active_notify,
+        |window| { window.is_active() }
+    )
+}
+
+/// Stream of the fullscreen state of `window`, emitted when `fullscreened` changes.
+pub fn fullscreened_stream(window: &impl IsA<gtk::Window>) -> EvStream<bool> {
+    ev_stream!(
+        window.upcast_ref::<gtk::Window>(),
+        fullscreened_notify,
+        |window| { window.is_fullscreen() }
+    )
+}
+
+/// Stream of the maximized state of `window`, emitted when `maximized` changes.
+pub fn maximized_stream(window: &impl IsA<gtk::Window>) -> EvStream<bool> {
+    ev_stream!(
+        window.upcast_ref::<gtk::Window>(),
+        maximized_notify,
+        |window| { window.is_maximized() }
+    )
+}
+
+/// Stream of the `(position, removed, added)` arguments of `items-changed` on `model`.
+pub fn items_changed_stream(model: &impl IsA<gio::ListModel>) -> EvStream<(u32, u32, u32)> {
+    ev_stream!(
+        model.upcast_ref::<gio::ListModel>(),
+        items_changed,
+        |_, position, removed, added| (position, removed, added)
+    )
+}
+
+/// Stream of the `(position, removed, added)` arguments of `items-changed` on `model`,
+/// a menu rather than a list model.
+pub fn menu_items_changed_stream(model: &impl IsA<gio::MenuModel>) -> EvStream<(i32, i32, i32)> {
+    ev_stream!(
+        model.upcast_ref::<gio::MenuModel>(),
+        items_changed,
+        |_, position, removed, added| (position, removed, added)
+    )
+}
+
+/// Stream of the `(position, n_items)` arguments of `selection-changed` on `model`:
+/// the selection state of the items in that range may have changed.
+pub fn selection_changed_stream(model: &impl IsA<gtk::SelectionModel>) -> EvStream<(u32, u32)> {
+    ev_stream!(
+        model.upcast_ref::<gtk::SelectionModel>(),
+        selection_changed,
+        |_, position, n_items| (position, n_items)
+    )
+}
+
+/// Stream emitting every time a monitor is attached to or detached from `display`.
+pub fn display_monitors_stream(display: &gdk::Display) -> EvStream<()> {
+    let monitors = display.monitors();
+    ev_stream!(monitors, items_changed, |_, _, _, _| ())
+}
+
+/// Stream of the `(offset_x, offset_y)` of `drag-update` on `gesture`, relative to the
+/// start point of the drag.
+///
+/// The stream is [`latest_only`](EvStream::latest_only): the offsets aren't deltas, so
+/// the freshest one is all that matters.
+pub fn drag_update_stream(gesture: &gtk::GestureDrag) -> EvStream<(f64, f64)> {
+    ev_stream!(gesture, drag_update, |_, x, y| (x, y)).latest_only()
+}
+
+/// Stream of the `(dx, dy)` deltas of `scroll` on `controller`.
+///
+/// `scroll` expects the handler to tell whether the event was handled: every emission
+/// returns `propagation`. Scroll events come at a high rate, so the stream keeps only
+/// the latest 64 deltas if the consumer falls behind.
+pub fn scroll_stream(
+    controller: &gtk::EventControllerScroll,
+    propagation: glib::Propagation,
+) -> EvStream<(f64, f64)> {
+    let (s, r) = channel::channel();
+    let signal_id = controller.connect_scroll(move |_, dx, dy| {
+        crate::__no_unwind(|| {
+            let _ = s.send((dx, dy));
+        });
+        propagation
+    });
+    let object = controller.upcast_ref::<Object>().downgrade();
+    EvStream::new(object, signal_id, r)
+        .with_debug_name("scroll")
+        .bounded(64, Overflow::DropOldest)
+}
@@ -0,0 +1,122 @@
+//! Allocations counted by a global allocator, on the thread of each test only.
+mod common;
+
+use common::{flush, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, ev_stream_with_hint, EvStreamExt};
+use glib::prelude::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::task::Poll;
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The number of allocations and reallocations made by `f` on this thread.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn a_burst_within_the_hint_doesnt_grow_the_buffer() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let hinted = ev_stream_with_hint!(emitter, ping, 64, |_, n| n);
+        // Whatever the emission itself allocates, it's the same every time: only the
+        // growth of the buffer could make the second half of the burst differ.
+        let first = allocations(|| (0..32).for_each(|n| emitter.ping(n)));
+        let second = allocations(|| (32..64).for_each(|n| emitter.ping(n)));
+        assert_eq!(first, second);
+        drop(hinted);
+
+        let unhinted = ev_stream!(emitter, ping, |_, n| n);
+        let grown = allocations(|| (0..64).for_each(|n| emitter.ping(n)));
+        assert!(grown > first + second, "{grown} <= {first} + {second}");
+        drop(unhinted);
+    });
+}
+
+#[test]
+fn polling_buffered_items_doesnt_allocate() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        (0..32).for_each(|n| emitter.ping(n));
+        let polled = allocations(|| {
+            for n in 0..32 {
+                assert_eq!(poll_once(&mut pings), Poll::Ready(Some(n)));
+            }
+            assert_eq!(poll_once(&mut pings), Poll::Pending);
+        });
+        assert_eq!(polled, 0);
+    });
+}
+
+#[test]
+fn latest_only_doesnt_allocate_in_steady_state() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut latest = ev_stream!(emitter, ping, |_, n| n).latest_only();
+        emitter.ping(0);
+        assert_eq!(poll_once(&mut latest), Poll::Ready(Some(0)));
+
+        // Replacing the buffered item reuses the slot of the previous one.
+        let few = allocations(|| (0..4).for_each(|n| emitter.ping(n)));
+        let many = allocations(|| (0..32).for_each(|n| emitter.ping(n)));
+        assert_eq!(many, few * 8);
+        let polled = allocations(|| {
+            assert_eq!(poll_once(&mut latest), Poll::Ready(Some(31)));
+            assert_eq!(poll_once(&mut latest), Poll::Pending);
+        });
+        assert_eq!(polled, 0);
+    });
+}
+
+#[test]
+fn batch_per_iteration_allocates_once_per_batch_whatever_its_length() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut batches = ev_stream!(emitter, ping, |_, n| n).batch_per_iteration();
+        let mut batch_of = |len: u32| {
+            (0..len).for_each(|n| emitter.ping(n));
+            allocations(|| {
+                assert_eq!(poll_once(&mut batches), Poll::Pending);
+                flush(ctx);
+                let Poll::Ready(Some(batch)) = poll_once(&mut batches) else {
+                    panic!("expected a batch");
+                };
+                assert_eq!(batch.len(), len as usize);
+            })
+        };
+        // The first batch also allocates whatever the idle source needs the first time.
+        batch_of(1);
+        assert_eq!(batch_of(4), batch_of(64));
+        assert_eq!(
+            allocations(|| assert!(poll_once(&mut batches).is_pending())),
+            0
+        );
+    });
+}
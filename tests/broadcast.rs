@@ -0,0 +1,215 @@
+mod common;
+
+use common::{flush, has_handlers, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::channel::Receiver;
+use ev_stream_gtk_rs::{ev_stream, EvStreamExt, TryRecvError};
+use futures::StreamExt;
+use glib::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::Poll;
+
+/// The items buffered by `receiver`.
+fn received<T>(receiver: &Receiver<T>) -> Vec<T> {
+    std::iter::from_fn(|| receiver.try_recv().ok().flatten()).collect()
+}
+
+#[test]
+fn a_late_subscriber_receives_the_last_n_items_then_the_new_ones() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let broadcast = ev_stream!(emitter, ping, |_, n| n).broadcast().replay(2);
+        let early = broadcast.subscribe();
+        for n in 1..=4 {
+            emitter.ping(n);
+        }
+        flush(ctx);
+        assert_eq!(received(&early), [1, 2, 3, 4]);
+
+        let late = broadcast.subscribe();
+        assert_eq!(received(&late), [3, 4]);
+        emitter.ping(5);
+        flush(ctx);
+        assert_eq!(received(&early), [5]);
+        assert_eq!(received(&late), [5]);
+        assert_eq!(received(&broadcast.subscribe()), [4, 5]);
+
+        // After the source ended, only the replay.
+        broadcast.close();
+        let closed = broadcast.subscribe();
+        assert_eq!(received(&closed), [4, 5]);
+        assert_eq!(closed.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
+
+#[test]
+fn without_replay_a_late_subscriber_only_receives_the_new_items() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let broadcast = ev_stream!(emitter, ping, |_, n| n).broadcast();
+        emitter.ping(1);
+        flush(ctx);
+        let late = broadcast.subscribe();
+        assert!(received(&late).is_empty());
+        emitter.ping(2);
+        flush(ctx);
+        assert_eq!(received(&late), [2]);
+    });
+}
+
+#[test]
+fn close_ends_every_subscriber_and_disconnects_the_source() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let broadcast = ev_stream!(emitter, ping, |_, n| n).broadcast();
+        let mut subscribers = [broadcast.subscribe(), broadcast.subscribe()];
+        emitter.ping(1);
+        flush(ctx);
+
+        broadcast.close();
+        assert!(!has_handlers(&emitter, "ping"));
+        for subscriber in &mut subscribers {
+            assert_eq!(poll_once(subscriber), Poll::Ready(Some(1)));
+            assert_eq!(poll_once(subscriber), Poll::Ready(None));
+        }
+        // Emitting again neither reaches them nor fails.
+        emitter.ping(2);
+        flush(ctx);
+        let mut late = broadcast.subscribe();
+        assert_eq!(poll_once(&mut late), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn unzip_ev_sends_each_component_to_its_half() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let (mut numbers, mut labels) =
+            ev_stream!(emitter, moved, |_, x, label| (x, label.to_owned())).unzip_ev();
+        emitter.moved(1, "a");
+        emitter.moved(2, "b");
+        flush(ctx);
+        assert_eq!(poll_once(&mut numbers), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut numbers), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut labels), Poll::Ready(Some("a".to_owned())));
+        assert_eq!(poll_once(&mut labels), Poll::Ready(Some("b".to_owned())));
+
+        // One half is enough to keep the source.
+        drop(numbers);
+        emitter.moved(3, "c");
+        flush(ctx);
+        assert_eq!(poll_once(&mut labels), Poll::Ready(Some("c".to_owned())));
+        assert!(has_handlers(&emitter, "moved"));
+        drop(labels);
+        flush(ctx);
+        assert!(!has_handlers(&emitter, "moved"));
+    });
+}
+
+#[test]
+fn split_first_yields_the_first_item_then_the_rest() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (mut first, mut rest) = ev_stream!(emitter, ping, |_, n| n).split_first();
+        emitter.ping(1);
+        emitter.ping(2);
+        // The rest waits for the first item to be taken.
+        assert_eq!(poll_once(&mut rest), Poll::Pending);
+        assert_eq!(poll_future(&mut first), Poll::Ready(Some(1)));
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut rest), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut rest), Poll::Ready(Some(3)));
+
+        // Disconnected with both halves only.
+        drop(first);
+        assert!(has_handlers(&emitter, "ping"));
+        drop(rest);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn split_first_rest_skips_the_first_item_once_the_future_is_dropped() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (first, mut rest) = ev_stream!(emitter, ping, |_, n| n).split_first();
+        emitter.ping(1);
+        emitter.ping(2);
+        drop(first);
+        assert_eq!(poll_once(&mut rest), Poll::Ready(Some(2)));
+        drop(emitter);
+        assert_eq!(poll_once(&mut rest), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn subscribe_gives_both_consumers_the_items_emitted_from_then_on() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        emitter.ping(1);
+        let subscriber = pings.subscribe();
+        let late = pings.subscribe();
+        emitter.ping(2);
+        emitter.ping(3);
+        assert_eq!(pings.drain_now(), [1, 2, 3]);
+        assert_eq!(received(&subscriber), [2, 3]);
+        assert_eq!(received(&late), [2, 3]);
+
+        // The subscribers end with the stream, after their buffered items.
+        emitter.ping(4);
+        drop(pings);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(subscriber.try_recv(), Ok(Some(4)));
+        assert_eq!(subscriber.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
+
+#[test]
+fn share_rc_gives_each_item_to_both_shares() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (mut left, mut right) = ev_stream!(emitter, ping, |_, n| n).share_rc();
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut left), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut right), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut right), Poll::Ready(Some(2)));
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut left), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut left), Poll::Ready(Some(3)));
+        assert_eq!(poll_once(&mut right), Poll::Ready(Some(3)));
+
+        // Disconnected with both shares only.
+        drop(left);
+        emitter.ping(4);
+        assert_eq!(poll_once(&mut right), Poll::Ready(Some(4)));
+        drop(right);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn dropping_a_share_waiting_for_the_source_doesnt_stall_the_other() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let (left, right) = ev_stream!(emitter, ping, |_, n| n).share_rc();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        ctx.spawn_local({
+            let received = received.clone();
+            left.for_each(move |n| {
+                received.borrow_mut().push(n);
+                std::future::ready(())
+            })
+        });
+        // Both tasks wait for the source, which only knows the waker of the last one.
+        let right = ctx.spawn_local(right.count());
+        flush(ctx);
+        right.abort();
+        flush(ctx);
+
+        emitter.ping(1);
+        flush(ctx);
+        assert_eq!(*received.borrow(), [1]);
+    });
+}
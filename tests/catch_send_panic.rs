@@ -0,0 +1,30 @@
+//! Strict streams with the `catch-send-panic` feature.
+mod common;
+
+use common::{handler_connected, with_context, Emitter};
+use ev_stream_gtk_rs::{channel, ev_stream};
+use glib::prelude::*;
+
+#[test]
+fn a_strict_send_after_the_drop_fails_instead_of_panicking() {
+    let (s, r) = channel::channel::<u32>();
+    r.set_strict(true);
+    assert!(s.send(1).is_ok());
+    drop(r);
+    assert!(s.send(2).is_err());
+}
+
+#[test]
+fn a_strict_handler_given_away_disconnects_instead_of_panicking() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n).strict();
+        let Ok((_object, signal_ids, receiver)) = pings.into_raw_parts() else {
+            panic!("the stream was made of a signal handler");
+        };
+        let raw_id = unsafe { signal_ids[0].as_raw() };
+        drop(receiver);
+        emitter.ping(1);
+        assert!(!handler_connected(&emitter, raw_id));
+    });
+}
@@ -0,0 +1,411 @@
+mod common;
+
+use common::{block_on, flush, has_handlers, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{
+    channel, combine_latest_vec, ev_once, ev_stream, follow_object, merge, merge_either,
+    merge_with_priority, once, select_first, Event, MergeEither, Overflow,
+};
+use futures::future::Either;
+use glib::prelude::*;
+use std::task::Poll;
+
+#[test]
+fn select_first_resolves_with_the_first_emission_and_disconnects_every_stream() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let mut first = Box::pin(select_first(vec![
+            ev_stream!(a, ping, |_, n| n),
+            ev_stream!(b, ping, |_, n| n + 100),
+        ]));
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+
+        b.ping(1);
+        a.ping(2);
+        assert_eq!(poll_future(&mut first), Poll::Ready(Some(101)));
+        assert!(!has_handlers(&a, "ping"));
+        assert!(!has_handlers(&b, "ping"));
+    });
+}
+
+#[test]
+fn select_first_resolves_to_none_once_every_stream_ended() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let mut first = Box::pin(select_first(vec![
+            ev_stream!(a, ping, |_, n| n),
+            ev_stream!(b, ping, |_, n| n),
+        ]));
+        drop(a);
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+        drop(b);
+        assert_eq!(poll_future(&mut first), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn select_first_of_no_stream_is_none() {
+    assert_eq!(
+        block_on(select_first(Vec::<ev_stream_gtk_rs::EvStream<u32>>::new())),
+        None
+    );
+}
+
+#[test]
+fn once_yields_the_first_item_and_disconnects() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut first = Box::pin(ev_once!(emitter, ping, |_, n| n));
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_future(&mut first), Poll::Ready(Some(1)));
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn once_disconnects_on_the_first_emission_before_being_polled() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let first = ev_once!(emitter, ping, |_, n| n);
+        emitter.ping(1);
+        emitter.ping(2);
+        flush(ctx);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(block_on(first), Some(1));
+    });
+}
+
+#[test]
+fn once_dropped_early_disconnects() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut first = Box::pin(once(ev_stream!(emitter, ping, |_, n| n)));
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+        drop(first);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn once_is_none_when_the_object_is_finalized_first() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut first = Box::pin(once(ev_stream!(emitter, ping, |_, n| n)));
+        drop(emitter);
+        assert_eq!(poll_future(&mut first), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn merge_polls_the_streams_in_turn_and_ends_once_all_ended() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let mut merged = merge(vec![
+            ev_stream!(a, ping, |_, n| n),
+            ev_stream!(b, ping, |_, n| n),
+        ]);
+        // A busy stream doesn't starve the other one.
+        for n in 1..=3 {
+            a.ping(n);
+        }
+        b.ping(10);
+        b.ping(20);
+        let items: Vec<_> = (0..5).map(|_| poll_once(&mut merged)).collect();
+        assert_eq!(items, [1, 10, 2, 20, 3].map(|n| Poll::Ready(Some(n))));
+        assert_eq!(poll_once(&mut merged), Poll::Pending);
+
+        drop(a);
+        b.ping(30);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(Some(30)));
+        assert_eq!(poll_once(&mut merged), Poll::Pending);
+        drop(b);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn dropping_merge_disconnects_every_stream() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let merged = merge(vec![
+            ev_stream!(a, ping, |_, n| n),
+            ev_stream!(b, ping, |_, n| n),
+        ]);
+        assert!(has_handlers(&a, "ping") && has_handlers(&b, "ping"));
+        drop(merged);
+        assert!(!has_handlers(&a, "ping"));
+        assert!(!has_handlers(&b, "ping"));
+    });
+}
+
+/// Polls `merged` once, with `Ok` for the left items and `Err` for the right ones, `Either`
+/// not being `PartialEq`.
+fn tagged<A, B>(merged: &mut MergeEither<A, B>) -> Poll<Option<Result<A, B>>> {
+    poll_once(merged).map(|item| {
+        item.map(|item| match item {
+            Either::Left(a) => Ok(a),
+            Either::Right(b) => Err(b),
+        })
+    })
+}
+
+#[test]
+fn merge_either_tags_interleaved_emissions_with_their_source() {
+    with_context(|_| {
+        let numbers = Emitter::new();
+        let names = Emitter::new();
+        let mut merged = merge_either(
+            ev_stream!(numbers, ping, |_, n| n),
+            ev_stream!(names, named, |_, name| name.to_owned()),
+        );
+        numbers.ping(1);
+        names.named("a");
+        assert_eq!(tagged(&mut merged), Poll::Ready(Some(Ok(1))));
+        assert_eq!(tagged(&mut merged), Poll::Ready(Some(Err("a".to_owned()))));
+        assert_eq!(tagged(&mut merged), Poll::Pending);
+
+        drop(numbers);
+        names.named("b");
+        assert_eq!(tagged(&mut merged), Poll::Ready(Some(Err("b".to_owned()))));
+        drop(names);
+        assert_eq!(tagged(&mut merged), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn merge_either_alternates_between_two_busy_streams() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let mut merged = merge_either(ev_stream!(a, ping, |_, n| n), ev_stream!(b, ping, |_, n| n));
+        for n in 1..=3 {
+            a.ping(n);
+        }
+        b.ping(10);
+        b.ping(20);
+        let items: Vec<_> = (0..5).map(|_| tagged(&mut merged)).collect();
+        assert_eq!(
+            items,
+            [Ok(1), Err(10), Ok(2), Err(20), Ok(3)].map(|item| Poll::Ready(Some(item)))
+        );
+    });
+}
+
+#[test]
+fn merge_services_a_slow_stream_next_to_a_hot_one() {
+    with_context(|_| {
+        let hot = Emitter::new();
+        let slow = Emitter::new();
+        let mut merged = merge(vec![
+            ev_stream!(hot, ping, |_, n| n),
+            ev_stream!(slow, ping, |_, _| 0),
+        ]);
+        for n in 1..=100 {
+            hot.ping(n);
+        }
+        slow.ping(0);
+        let first: Vec<_> = (0..2).map(|_| poll_once(&mut merged)).collect();
+        assert!(first.contains(&Poll::Ready(Some(0))), "{first:?}");
+
+        // Still serviced when it emits again while the hot stream has a backlog.
+        slow.ping(0);
+        let next: Vec<_> = (0..2).map(|_| poll_once(&mut merged)).collect();
+        assert!(next.contains(&Poll::Ready(Some(0))), "{next:?}");
+    });
+}
+
+#[test]
+fn follow_object_moves_the_subscription_to_each_new_object() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let (objects, r) = channel::channel();
+        let mut pings = follow_object(r, |emitter: &Emitter| ev_stream!(emitter, ping, |_, n| n));
+        objects.send(a.clone()).unwrap();
+        assert_eq!(poll_once(&mut pings), Poll::Pending);
+        a.ping(1);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+
+        // Emitted before the swap is noticed: still delivered.
+        objects.send(b.clone()).unwrap();
+        a.ping(2);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(2)));
+        assert!(!has_handlers(&a, "ping"));
+        b.ping(3);
+        a.ping(4);
+        b.ping(5);
+        let items: Vec<_> = (0..3).map(|_| poll_once(&mut pings)).collect();
+        assert_eq!(
+            items,
+            [Poll::Ready(Some(3)), Poll::Ready(Some(5)), Poll::Pending]
+        );
+
+        drop(objects);
+        assert_eq!(poll_once(&mut pings), Poll::Pending);
+        drop(pings);
+        assert!(!has_handlers(&b, "ping"));
+    });
+}
+
+#[test]
+fn an_overflowing_burst_yields_a_dropped_marker_with_the_count() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut events = ev_stream!(emitter, ping, |_, n| n)
+            .bounded(2, Overflow::DropNewest)
+            .with_drop_markers();
+        for n in 1..=5 {
+            emitter.ping(n);
+        }
+        let items: Vec<_> = (0..4).map(|_| poll_once(&mut events)).collect();
+        assert_eq!(
+            items,
+            [
+                Poll::Ready(Some(Event::Dropped(3))),
+                Poll::Ready(Some(Event::Item(1))),
+                Poll::Ready(Some(Event::Item(2))),
+                Poll::Pending,
+            ]
+        );
+
+        // No marker without new drops.
+        emitter.ping(6);
+        assert_eq!(poll_once(&mut events), Poll::Ready(Some(Event::Item(6))));
+        assert_eq!(poll_once(&mut events), Poll::Pending);
+    });
+}
+
+#[test]
+fn merge_with_priority_yields_every_buffered_high_item_first() {
+    with_context(|_| {
+        let cancel = Emitter::new();
+        let input = Emitter::new();
+        let mut merged = merge_with_priority(
+            ev_stream!(cancel, ping, |_, n| n + 100),
+            ev_stream!(input, ping, |_, n| n),
+        );
+        input.ping(1);
+        cancel.ping(1);
+        input.ping(2);
+        cancel.ping(2);
+        let items: Vec<_> = (0..5).map(|_| poll_once(&mut merged)).collect();
+        assert_eq!(
+            items,
+            [101, 102, 1, 2]
+                .map(|n| Poll::Ready(Some(n)))
+                .into_iter()
+                .chain([Poll::Pending])
+                .collect::<Vec<_>>()
+        );
+
+        // Emitted between two polls, a high item still goes before the buffered low one.
+        input.ping(3);
+        input.ping(4);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(Some(3)));
+        cancel.ping(3);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(Some(103)));
+        assert_eq!(poll_once(&mut merged), Poll::Ready(Some(4)));
+
+        drop(cancel);
+        input.ping(5);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(Some(5)));
+        drop(input);
+        assert_eq!(poll_once(&mut merged), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn combine_latest_vec_waits_for_every_source_then_yields_on_each_change() {
+    with_context(|_| {
+        let fields = [Emitter::new(), Emitter::new(), Emitter::new()];
+        let mut form = combine_latest_vec(
+            fields
+                .iter()
+                .map(|field| ev_stream!(field, ping, |_, n| n))
+                .collect(),
+        );
+        assert_eq!(poll_once(&mut form), Poll::Pending);
+        fields[0].ping(1);
+        fields[1].ping(2);
+        assert_eq!(poll_once(&mut form), Poll::Pending);
+        // Overwrites its missing-value slot, still incomplete.
+        fields[0].ping(3);
+        assert_eq!(poll_once(&mut form), Poll::Pending);
+
+        fields[2].ping(10);
+        assert_eq!(poll_once(&mut form), Poll::Ready(Some(vec![3, 2, 10])));
+        fields[1].ping(4);
+        assert_eq!(poll_once(&mut form), Poll::Ready(Some(vec![3, 4, 10])));
+        assert_eq!(poll_once(&mut form), Poll::Pending);
+
+        // A source ending after it emitted keeps its last value.
+        let [first, second, third] = fields;
+        drop(first);
+        third.ping(11);
+        assert_eq!(poll_once(&mut form), Poll::Ready(Some(vec![3, 4, 11])));
+        drop((second, third));
+        assert_eq!(poll_once(&mut form), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn combine_latest_vec_ends_when_a_source_ends_without_emitting() {
+    with_context(|_| {
+        let a = Emitter::new();
+        let b = Emitter::new();
+        let mut both = combine_latest_vec(vec![
+            ev_stream!(a, ping, |_, n| n),
+            ev_stream!(b, ping, |_, n| n),
+        ]);
+        a.ping(1);
+        drop(b);
+        assert_eq!(poll_once(&mut both), Poll::Ready(None));
+        assert!(!has_handlers(&a, "ping"));
+    });
+}
+
+#[test]
+fn select_all_drains_ev_streams_to_completion() {
+    use futures::StreamExt;
+
+    with_context(|_| {
+        let emitters: Vec<_> = (0..3).map(|_| Emitter::new()).collect();
+        let all = futures::stream::select_all(
+            emitters
+                .iter()
+                .map(|emitter| ev_stream!(emitter, ping, |_, n| n)),
+        );
+        for (emitter, n) in emitters.iter().zip([1, 2, 3]) {
+            emitter.ping(n);
+        }
+        drop(emitters);
+        let mut items = block_on(all.collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, [1, 2, 3]);
+    });
+}
+
+#[test]
+fn dropping_select_all_disconnects_every_ev_stream() {
+    use futures::StreamExt;
+
+    with_context(|_| {
+        let emitters: Vec<_> = (0..3).map(|_| Emitter::new()).collect();
+        let mut all = futures::stream::select_all(
+            emitters
+                .iter()
+                .map(|emitter| ev_stream!(emitter, ping, |_, n| n)),
+        );
+        emitters[1].ping(2);
+        assert_eq!(poll_once(&mut all), Poll::Ready(Some(2)));
+        assert_eq!(poll_future(&mut all.next()), Poll::Pending);
+        drop(all);
+        assert!(emitters
+            .iter()
+            .all(|emitter| !has_handlers(emitter, "ping")));
+    });
+}
@@ -0,0 +1,236 @@
+//! Test object and helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use futures::task::noop_waker_ref;
+use futures::Stream;
+use glib::prelude::*;
+use glib::subclass::prelude::*;
+use glib::subclass::Signal;
+use glib::translate::IntoGlib;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct Emitter {
+        pub(super) value: Cell<i32>,
+        pub(super) label: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Emitter {
+        const NAME: &'static str = "EvStreamTestEmitter";
+        type Type = super::Emitter;
+    }
+
+    impl ObjectImpl for Emitter {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("ping")
+                        .param_types([u32::static_type()])
+                        .build(),
+                    Signal::builder("named")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("moved")
+                        .param_types([i32::static_type(), String::static_type()])
+                        .build(),
+                    Signal::builder("focused")
+                        .param_types([glib::Object::static_type()])
+                        .build(),
+                    Signal::builder("decide")
+                        .param_types([u32::static_type()])
+                        .return_type::<bool>()
+                        .build(),
+                ]
+            })
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecInt::builder("value").build(),
+                    glib::ParamSpecString::builder("label").build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "value" => self.value.set(value.get().unwrap()),
+                "label" => {
+                    *self.label.borrow_mut() =
+                        value.get::<Option<String>>().unwrap().unwrap_or_default()
+                }
+                name => unreachable!("no property `{name}`"),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "value" => self.value.get().to_value(),
+                "label" => self.label.borrow().to_value(),
+                name => unreachable!("no property `{name}`"),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Stands in for a widget: signals `ping(u32)`, `named(String)`, `moved(i32, String)`,
+    /// `focused(Option<Object>)` and `decide(u32) -> bool`, properties `value: i32` and `label: String`, with the typed
+    /// `connect_<signal>` methods the typed form of `ev_stream!` calls.
+    pub struct Emitter(ObjectSubclass<imp::Emitter>);
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl Emitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect_ping<F: Fn(&Self, u32) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("ping", false, move |args| {
+            f(&args[0].get().unwrap(), args[1].get().unwrap());
+            None
+        })
+    }
+
+    pub fn connect_named<F: Fn(&Self, &str) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("named", false, move |args| {
+            f(&args[0].get().unwrap(), args[1].get().unwrap());
+            None
+        })
+    }
+
+    pub fn connect_moved<F: Fn(&Self, i32, &str) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("moved", false, move |args| {
+            f(
+                &args[0].get().unwrap(),
+                args[1].get().unwrap(),
+                args[2].get().unwrap(),
+            );
+            None
+        })
+    }
+
+    pub fn connect_focused<F: Fn(&Self, Option<&glib::Object>) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("focused", false, move |args| {
+            let focus = args[1].get::<Option<glib::Object>>().unwrap();
+            f(&args[0].get().unwrap(), focus.as_ref());
+            None
+        })
+    }
+
+    pub fn connect_decide<F: Fn(&Self, u32) -> bool + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("decide", false, move |args| {
+            Some(f(&args[0].get().unwrap(), args[1].get().unwrap()).to_value())
+        })
+    }
+
+    pub fn ping(&self, n: u32) {
+        self.emit_by_name::<()>("ping", &[&n]);
+    }
+
+    pub fn named(&self, name: &str) {
+        self.emit_by_name::<()>("named", &[&name]);
+    }
+
+    pub fn moved(&self, x: i32, label: &str) {
+        self.emit_by_name::<()>("moved", &[&x, &label]);
+    }
+
+    pub fn focused(&self, focus: Option<&glib::Object>) {
+        self.emit_by_name::<()>("focused", &[&focus]);
+    }
+
+    pub fn decide(&self, n: u32) -> bool {
+        self.emit_by_name::<bool>("decide", &[&n])
+    }
+}
+
+/// Whether `signal_id`, from [SignalHandlerId::as_raw], is still connected to `object`.
+pub fn handler_connected(object: &impl IsA<glib::Object>, signal_id: glib::ffi::gulong) -> bool {
+    unsafe {
+        glib::gobject_ffi::g_signal_handler_is_connected(object.as_ref().as_ptr(), signal_id)
+            != glib::ffi::GFALSE
+    }
+}
+
+/// The raw id of a handler connected to `signal` on `object`, found by glib's own
+/// bookkeeping rather than from the stream, if there is any.
+pub fn find_handler(object: &impl IsA<glib::Object>, signal: &str) -> Option<glib::ffi::gulong> {
+    let signal = glib::subclass::SignalId::lookup(signal, object.type_()).expect("no such signal");
+    let raw_id = unsafe {
+        glib::gobject_ffi::g_signal_handler_find(
+            object.as_ref().as_ptr(),
+            glib::gobject_ffi::G_SIGNAL_MATCH_ID,
+            signal.into_glib(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (raw_id != 0).then_some(raw_id)
+}
+
+/// Whether any handler, blocked or not, is still connected to `signal` on `object`.
+pub fn has_handlers(object: &impl IsA<glib::Object>, signal: &str) -> bool {
+    let signal = glib::subclass::SignalId::lookup(signal, object.type_()).expect("no such signal");
+    unsafe {
+        glib::gobject_ffi::g_signal_has_handler_pending(
+            object.as_ref().as_ptr(),
+            signal.into_glib(),
+            0,
+            glib::ffi::GTRUE,
+        ) != glib::ffi::GFALSE
+    }
+}
+
+/// Runs `f` with a new `MainContext`, acquired and pushed as the thread-default one: the
+/// tests run in parallel, each on its own thread.
+pub fn with_context<R>(f: impl FnOnce(&glib::MainContext) -> R) -> R {
+    let ctx = glib::MainContext::new();
+    let _owner = ctx.acquire().unwrap();
+    ctx.with_thread_default(|| f(&ctx)).unwrap()
+}
+
+/// Runs `future` to completion on a new thread-default `MainContext`.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    with_context(|ctx| ctx.block_on(future))
+}
+
+/// Dispatches everything ready on `ctx`, without blocking.
+pub fn flush(ctx: &glib::MainContext) {
+    while ctx.iteration(false) {}
+}
+
+/// Polls `stream` once, with a waker doing nothing.
+pub fn poll_once<S: Stream + Unpin>(stream: &mut S) -> Poll<Option<S::Item>> {
+    Pin::new(stream).poll_next(&mut Context::from_waker(noop_waker_ref()))
+}
+
+/// Polls `future` once, with a waker doing nothing.
+pub fn poll_future<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    Pin::new(future).poll(&mut Context::from_waker(noop_waker_ref()))
+}
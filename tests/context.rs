@@ -0,0 +1,176 @@
+mod common;
+
+use common::{flush, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, EvStreamExt, Settled};
+use futures::Stream;
+use glib::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::task::Poll;
+use std::time::Duration;
+
+const DELAY: Duration = Duration::from_millis(1);
+
+/// Emits `pings` into the stream built by `combinator` and takes the `immediate` items
+/// yielded right away, then checks that its timer only fires when `other` is iterated,
+/// not the thread-default context.
+fn assert_on_other_context<S, F>(pings: &[u32], immediate: usize, combinator: F) -> S::Item
+where
+    S: Stream + Unpin,
+    F: FnOnce(ev_stream_gtk_rs::EvStream<u32>, &glib::MainContext) -> S,
+{
+    with_context(|default| {
+        let other = glib::MainContext::new();
+        let _owner = other.acquire().unwrap();
+        let emitter = Emitter::new();
+        let mut stream = combinator(ev_stream!(emitter, ping, |_, n| n), &other);
+        for &n in pings {
+            emitter.ping(n);
+        }
+        for _ in 0..immediate {
+            assert!(matches!(poll_once(&mut stream), Poll::Ready(Some(_))));
+        }
+        assert!(poll_once(&mut stream).is_pending());
+
+        std::thread::sleep(DELAY * 10);
+        flush(default);
+        assert!(poll_once(&mut stream).is_pending());
+
+        flush(&other);
+        match poll_once(&mut stream) {
+            Poll::Ready(Some(item)) => item,
+            polled => panic!("expected an item, got {:?}", polled.map(|_| ())),
+        }
+    })
+}
+
+#[test]
+fn debounce_on() {
+    assert_eq!(
+        assert_on_other_context(&[1, 2], 0, |s, ctx| s.debounce_on(DELAY, ctx)),
+        2
+    );
+}
+
+#[test]
+fn throttle_on() {
+    assert_eq!(
+        assert_on_other_context(&[1, 2], 1, |s, ctx| s.throttle_latest_on(DELAY, ctx)),
+        2
+    );
+    let trailing = assert_on_other_context(&[1, 2], 0, |s, ctx| {
+        s.throttle_opts_on(DELAY, false, true, ctx)
+    });
+    assert_eq!(trailing, 2);
+}
+
+#[test]
+fn delay_each_on() {
+    assert_eq!(
+        assert_on_other_context(&[1], 0, |s, ctx| s.delay_each_on(DELAY, ctx)),
+        1
+    );
+}
+
+#[test]
+fn chunks_timeout_on() {
+    let chunk = assert_on_other_context(&[1, 2], 0, |s, ctx| s.chunks_timeout_on(4, DELAY, ctx));
+    assert_eq!(chunk, vec![1, 2]);
+}
+
+#[test]
+fn settle_marker_on() {
+    let marker = assert_on_other_context(&[1], 1, |s, ctx| s.settle_marker_on(DELAY, ctx));
+    assert!(matches!(marker, Settled::Quiescent));
+}
+
+#[test]
+fn batch_per_iteration_on() {
+    let batch = assert_on_other_context(&[1, 2], 0, |s, ctx| s.batch_per_iteration_on(ctx));
+    assert_eq!(batch, vec![1, 2]);
+}
+
+#[test]
+fn with_deadline_on() {
+    with_context(|default| {
+        let other = glib::MainContext::new();
+        let _owner = other.acquire().unwrap();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).with_deadline_on(DELAY, &other);
+        emitter.ping(1);
+        let Poll::Ready(Some((1, mut deadline))) = poll_once(&mut stream) else {
+            panic!("expected the item");
+        };
+        std::thread::sleep(DELAY * 10);
+        flush(default);
+        assert!(poll_future(&mut deadline).is_pending());
+        flush(&other);
+        assert!(poll_future(&mut deadline).is_ready());
+    });
+}
+
+#[test]
+fn end_after_on() {
+    with_context(|default| {
+        let other = glib::MainContext::new();
+        let _owner = other.acquire().unwrap();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).end_after_on(DELAY, &other);
+        assert!(poll_once(&mut stream).is_pending());
+        std::thread::sleep(DELAY * 10);
+        flush(default);
+        assert!(poll_once(&mut stream).is_pending());
+        flush(&other);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn for_each_concurrent_local_on() {
+    with_context(|default| {
+        let other = glib::MainContext::new();
+        let _owner = other.acquire().unwrap();
+        let emitter = Emitter::new();
+        let ran = Rc::new(Cell::new(0));
+        let mut done = Box::pin(
+            ev_stream!(emitter, ping, |_, n| n).for_each_concurrent_local_on(
+                None,
+                {
+                    let ran = ran.clone();
+                    move |n| {
+                        let ran = ran.clone();
+                        async move { ran.set(ran.get() + n) }
+                    }
+                },
+                &other,
+            ),
+        );
+        emitter.ping(1);
+        emitter.ping(2);
+        assert!(poll_future(&mut done).is_pending());
+        flush(default);
+        assert_eq!(ran.get(), 0);
+        flush(&other);
+        assert_eq!(ran.get(), 3);
+    });
+}
+
+#[test]
+fn buffer_unordered_local_on() {
+    with_context(|default| {
+        let other = glib::MainContext::new();
+        let _owner = other.acquire().unwrap();
+        let emitter = Emitter::new();
+        let mut outputs = ev_stream!(emitter, ping, |_, n| n).buffer_unordered_local_on(
+            None,
+            |n| async move { n * 10 },
+            &other,
+        );
+        emitter.ping(1);
+        assert!(poll_once(&mut outputs).is_pending());
+        flush(default);
+        assert!(poll_once(&mut outputs).is_pending());
+        flush(&other);
+        assert_eq!(poll_once(&mut outputs), Poll::Ready(Some(10)));
+    });
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{flush, has_handlers, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream_cross_thread, main_thread_channel};
+use futures::StreamExt;
+use glib::prelude::*;
+use std::sync::mpsc;
+
+#[test]
+fn the_items_reach_a_consumer_on_another_thread() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let weak = glib::SendWeakRef::from(emitter.downgrade());
+        let (created, stream_created) = mpsc::channel();
+        let consumer = std::thread::spawn({
+            let ctx = ctx.clone();
+            move || {
+                let pings = ev_stream_cross_thread!(ctx, weak, ping, |_, n| n);
+                created.send(()).unwrap();
+                futures::executor::block_on(pings.take(3).collect::<Vec<_>>())
+            }
+        });
+        stream_created.recv().unwrap();
+        // Connects on this thread, which owns the context.
+        flush(ctx);
+        assert!(has_handlers(&emitter, "ping"));
+
+        for n in 1..=3 {
+            emitter.ping(n);
+        }
+        flush(ctx);
+        assert_eq!(consumer.join().unwrap(), [1, 2, 3]);
+
+        // The stream was dropped on the other thread: the forwarding task disconnects.
+        flush(ctx);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn a_finalized_object_yields_an_empty_stream() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let weak = glib::SendWeakRef::from(emitter.downgrade());
+        drop(emitter);
+        let consumer = std::thread::spawn({
+            let ctx = ctx.clone();
+            move || ev_stream_cross_thread!(ctx, weak, ping, |_, n| n)
+        });
+        let pings = consumer.join().unwrap();
+        flush(ctx);
+        assert_eq!(
+            futures::executor::block_on(pings.collect::<Vec<_>>()),
+            Vec::<u32>::new()
+        );
+    });
+}
+
+#[test]
+fn main_thread_channel_delivers_the_items_sent_by_a_worker() {
+    with_context(|ctx| {
+        let (progress, updates) = main_thread_channel::<u32>();
+        let worker = std::thread::spawn(move || {
+            for n in 1..=3 {
+                progress.send(n).unwrap();
+            }
+        });
+        // Ends once the worker dropped its sender.
+        assert_eq!(ctx.block_on(updates.collect::<Vec<_>>()), [1, 2, 3]);
+        worker.join().unwrap();
+    });
+}
+
+#[test]
+fn dropping_the_main_thread_stream_aborts_its_task() {
+    with_context(|ctx| {
+        let (progress, updates) = main_thread_channel::<u32>();
+        assert!(updates.object().is_none());
+        flush(ctx);
+        drop(updates);
+        flush(ctx);
+        assert!(!ctx.pending());
+        assert!(progress.send(1).is_err());
+    });
+}
@@ -0,0 +1,63 @@
+//! Against the session bus, which these tests need: they're ignored by default, run them
+//! with `cargo test --features gio -- --ignored` where one is available (e.g. under
+//! `dbus-run-session`).
+mod common;
+
+use common::{flush, with_context};
+use ev_stream_gtk_rs::{dbus_signal_stream, TryRecvError};
+use gio::prelude::*;
+use std::time::Duration;
+
+const PATH: &str = "/org/ranfdev/EvStream";
+const INTERFACE: &str = "org.ranfdev.EvStream";
+// The signals make a round trip through the bus daemon.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn session_bus() -> gio::DBusConnection {
+    gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE).expect("no session bus")
+}
+
+fn emit(connection: &gio::DBusConnection, member: &str, n: u32) {
+    connection
+        .emit_signal(None, PATH, INTERFACE, member, Some(&(n,).to_variant()))
+        .unwrap();
+}
+
+#[test]
+#[ignore = "needs a session bus"]
+fn the_parameters_of_the_matching_signals_are_yielded() {
+    with_context(|_| {
+        let connection = session_bus();
+        let mut pings = dbus_signal_stream(
+            &connection,
+            connection.unique_name().as_deref(),
+            Some(INTERFACE),
+            Some("Ping"),
+            Some(PATH),
+        );
+        // Delivered in order: it would come first if it matched.
+        emit(&connection, "Other", 0);
+        emit(&connection, "Ping", 1);
+        emit(&connection, "Ping", 2);
+        for n in [1, 2] {
+            let parameters = pings.next_timeout(TIMEOUT).expect("no signal received");
+            assert_eq!(parameters.get::<(u32,)>(), Some((n,)));
+        }
+    });
+}
+
+#[test]
+#[ignore = "needs a session bus"]
+fn disconnecting_unsubscribes() {
+    with_context(|ctx| {
+        let connection = session_bus();
+        let mut pings = dbus_signal_stream(&connection, None, Some(INTERFACE), None, Some(PATH));
+        assert!(pings.is_connected());
+
+        pings.disconnect();
+        assert!(!pings.is_connected());
+        // Unsubscribing frees the callback, and the sender it holds, from the context.
+        flush(ctx);
+        assert_eq!(pings.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
@@ -0,0 +1,135 @@
+//! The `debug-subscriptions` feature.
+mod common;
+
+use common::{poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::channel::DIVERGENCE_WARNING_THRESHOLD;
+use ev_stream_gtk_rs::{debug_snapshot, ev_stream, Overflow};
+use glib::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+/// The warnings of the crate logged by `f`.
+fn warnings(f: impl FnOnce()) -> Vec<String> {
+    let logged = Arc::new(Mutex::new(Vec::new()));
+    let handler = glib::log_set_handler(
+        Some("ev-stream-gtk-rs"),
+        glib::LogLevels::LEVEL_WARNING,
+        false,
+        false,
+        {
+            let logged = logged.clone();
+            move |_, _, message| logged.lock().unwrap().push(message.to_owned())
+        },
+    );
+    f();
+    glib::log_remove_handler(Some("ev-stream-gtk-rs"), handler);
+    let logged = logged.lock().unwrap();
+    logged.clone()
+}
+
+#[test]
+fn an_unbounded_stream_warns_once_its_backlog_reaches_the_threshold() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let _stream = ev_stream!(emitter, ping, |_, n| n);
+        let below = warnings(|| (1..DIVERGENCE_WARNING_THRESHOLD).for_each(|_| emitter.ping(0)));
+        assert_eq!(below, Vec::<String>::new());
+
+        let reached = warnings(|| emitter.ping(0));
+        assert_eq!(reached.len(), 1);
+        assert!(
+            reached[0].contains(&format!(
+                "{DIVERGENCE_WARNING_THRESHOLD} items of type `u32`"
+            )),
+            "{reached:?}"
+        );
+
+        // Once only, not for every item past the threshold.
+        assert!(warnings(|| emitter.ping(0)).is_empty());
+    });
+}
+
+#[test]
+fn a_bounded_stream_never_warns() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let _stream = ev_stream!(emitter, ping, |_, n| n)
+            .bounded(DIVERGENCE_WARNING_THRESHOLD * 2, Overflow::DropNewest);
+        let logged =
+            warnings(|| (0..DIVERGENCE_WARNING_THRESHOLD * 2).for_each(|_| emitter.ping(0)));
+        assert!(logged.is_empty());
+    });
+}
+
+#[test]
+fn debug_snapshot_lists_the_live_streams_of_the_thread() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        assert!(debug_snapshot().is_empty());
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        let names = ev_stream!(emitter, "named", |args| args.len());
+        emitter.ping(1);
+        emitter.ping(2);
+
+        let snapshot = debug_snapshot();
+        assert_eq!(snapshot.len(), 2);
+        let ping = snapshot.iter().find(|info| info.id == pings.id()).unwrap();
+        assert_eq!(
+            (
+                ping.object_type,
+                ping.signal.as_str(),
+                ping.is_connected,
+                ping.pending
+            ),
+            ("EvStreamTestEmitter", "ping", true, 2)
+        );
+        let named = snapshot.iter().find(|info| info.id == names.id()).unwrap();
+        assert_eq!(
+            (
+                named.object_type,
+                named.signal.as_str(),
+                named.is_connected,
+                named.pending
+            ),
+            ("EvStreamTestEmitter", "named", true, 0)
+        );
+
+        pings.disconnect();
+        let snapshot = debug_snapshot();
+        let ping = snapshot.iter().find(|info| info.id == pings.id()).unwrap();
+        assert!(!ping.is_connected);
+
+        drop(pings);
+        let ids: Vec<_> = debug_snapshot().iter().map(|info| info.id).collect();
+        assert_eq!(ids, [names.id()]);
+        drop(names);
+        assert!(debug_snapshot().is_empty());
+    });
+}
+
+#[test]
+fn dropping_a_stream_never_polled_warns() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let forgotten = warnings(|| drop(ev_stream!(emitter, ping, |_, n| n)));
+        assert_eq!(
+            forgotten,
+            [
+                "stream of `u32` on `EvStreamTestEmitter` dropped without being polled: was it \
+              spawned?"
+            ]
+        );
+
+        let polled = warnings(|| {
+            let mut pings = ev_stream!(emitter, ping, |_, n| n);
+            assert_eq!(poll_once(&mut pings), Poll::Pending);
+        });
+        assert!(polled.is_empty(), "{polled:?}");
+        // Pulling the items synchronously counts as consuming them.
+        let received = warnings(|| {
+            let mut pings = ev_stream!(emitter, ping, |_, n| n);
+            assert_eq!(pings.try_recv(), Ok(None));
+        });
+        assert!(received.is_empty(), "{received:?}");
+    });
+}
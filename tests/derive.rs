@@ -0,0 +1,84 @@
+//! `#[derive(FromSignalArgs)]`, with the `derive` feature.
+mod common;
+
+use common::{poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{signal_args_stream, FromSignalArgs};
+use glib::prelude::*;
+use std::task::Poll;
+
+#[derive(Debug, PartialEq, FromSignalArgs)]
+struct Pinged {
+    emitter: Emitter,
+    n: u32,
+}
+
+#[test]
+fn a_derived_struct_is_built_from_each_emission() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = signal_args_stream::<Pinged>(&emitter, "ping");
+        emitter.ping(1);
+        assert_eq!(
+            poll_once(&mut stream),
+            Poll::Ready(Some(Pinged {
+                emitter: emitter.clone(),
+                n: 1
+            }))
+        );
+    });
+}
+
+#[derive(Debug, PartialEq, FromSignalArgs)]
+struct Moved {
+    emitter: Emitter,
+    x: i32,
+    label: String,
+}
+
+#[derive(Debug, PartialEq, FromSignalArgs)]
+struct Point(Emitter, i32, i32);
+
+#[test]
+fn a_derived_struct_takes_the_arguments_in_field_order() {
+    let emitter = Emitter::new();
+    let args = [emitter.to_value(), 3.to_value(), "a".to_value()];
+    assert_eq!(
+        Moved::from_signal_args(&args),
+        Some(Moved {
+            emitter: emitter.clone(),
+            x: 3,
+            label: "a".to_owned()
+        })
+    );
+    let args = [emitter.to_value(), 1.to_value(), 2.to_value()];
+    assert_eq!(
+        Point::from_signal_args(&args),
+        Some(Point(emitter.clone(), 1, 2))
+    );
+}
+
+#[test]
+fn a_derived_struct_rejects_mismatching_arguments() {
+    let emitter = Emitter::new();
+    // Too few, too many.
+    assert_eq!(
+        Moved::from_signal_args(&[emitter.to_value(), 3.to_value()]),
+        None
+    );
+    let args = [
+        emitter.to_value(),
+        3.to_value(),
+        "a".to_value(),
+        "b".to_value(),
+    ];
+    assert_eq!(Moved::from_signal_args(&args), None);
+    // Wrong type.
+    let args = [emitter.to_value(), "3".to_value(), "a".to_value()];
+    assert_eq!(Moved::from_signal_args(&args), None);
+    let args = [
+        glib::Object::new::<glib::Object>().to_value(),
+        3.to_value(),
+        "a".to_value(),
+    ];
+    assert_eq!(Moved::from_signal_args(&args), None);
+}
@@ -0,0 +1,816 @@
+mod common;
+
+use common::{block_on, flush, has_handlers, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{
+    channel, ev_stream, property_stream, DynEvStream, EvStreamControl, EvStreamExt, TryRecvError,
+};
+use futures::channel::oneshot;
+use futures::{Stream, StreamExt};
+use glib::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[test]
+fn distinct_until_changed_skips_consecutive_duplicates() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n).distinct_until_changed();
+        for n in [1, 1, 2, 2, 2, 1, 3, 3] {
+            emitter.ping(n);
+        }
+        let mut stream = stream.take(4);
+        assert_eq!(block_on(stream.by_ref().collect::<Vec<_>>()), [1, 2, 1, 3]);
+        drop(stream);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn batch_per_iteration_collects_a_burst_into_one_batch() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut batches = ev_stream!(emitter, ping, |_, n| n).batch_per_iteration();
+        for n in 1..=3 {
+            emitter.ping(n);
+        }
+        // Flushed on idle, not right away.
+        assert_eq!(poll_once(&mut batches), Poll::Pending);
+        emitter.ping(4);
+        flush(ctx);
+        assert_eq!(poll_once(&mut batches), Poll::Ready(Some(vec![1, 2, 3, 4])));
+
+        emitter.ping(5);
+        assert_eq!(poll_once(&mut batches), Poll::Pending);
+        flush(ctx);
+        assert_eq!(poll_once(&mut batches), Poll::Ready(Some(vec![5])));
+
+        emitter.ping(6);
+        drop(emitter);
+        // The last batch is yielded when the stream ends.
+        assert_eq!(poll_once(&mut batches), Poll::Ready(Some(vec![6])));
+        assert_eq!(poll_once(&mut batches), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn coalesce_on_resume_collapses_a_backlog_queued_before_the_first_poll() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).coalesce_on_resume(3);
+        // The main loop was blocked: the backlog piled up unpolled.
+        for n in 1..=10 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(10)));
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+
+        // Below the threshold, every item comes through.
+        for n in 11..=13 {
+            emitter.ping(n);
+        }
+        let items: Vec<_> = (0..3).map(|_| poll_once(&mut stream)).collect();
+        assert_eq!(items, [11, 12, 13].map(|n| Poll::Ready(Some(n))));
+    });
+}
+
+/// Panics when polled again once it ended, as a stream which isn't fused may.
+struct Unfused<S> {
+    stream: S,
+    ended: bool,
+}
+
+impl<S: Stream + Unpin> Stream for Unfused<S> {
+    type Item = S::Item;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        assert!(!self.ended, "polled after the end");
+        let item = Pin::new(&mut self.stream).poll_next(cx);
+        self.ended = matches!(item, Poll::Ready(None));
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+#[test]
+fn coalesce_on_resume_ends_once_the_object_is_finalized_inside_a_backlog() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = Unfused {
+            stream: ev_stream!(emitter, ping, |_, n| n),
+            ended: false,
+        };
+        let mut stream = pings.coalesce_on_resume(3);
+        for n in 1..=5 {
+            emitter.ping(n);
+        }
+        drop(emitter);
+        // The drain reaches the end: the inner stream isn't polled anymore.
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(5)));
+        assert_eq!(stream.size_hint(), (0, Some(0)));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Row {
+    id: u32,
+    label: &'static str,
+}
+
+const ROWS: [(u32, &str); 5] = [(1, "a"), (1, "b"), (2, "b"), (2, "c"), (1, "c")];
+
+#[test]
+fn distinct_by_key_ignores_the_fields_outside_the_key() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| {
+            let (id, label) = ROWS[n as usize];
+            Row { id, label }
+        })
+        .distinct_by_key(|row| row.id);
+        for n in 0..ROWS.len() as u32 {
+            emitter.ping(n);
+        }
+        let mut stream = stream.take(3);
+        assert_eq!(
+            block_on(stream.by_ref().collect::<Vec<_>>()),
+            [
+                Row { id: 1, label: "a" },
+                Row { id: 2, label: "b" },
+                Row { id: 1, label: "c" },
+            ]
+        );
+    });
+}
+
+#[test]
+fn map_ok_and_map_err_transform_their_variant_and_keep_the_control_methods() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| if n % 2 == 0 {
+            Ok(n)
+        } else {
+            Err(n)
+        })
+        .map_ok(|n| n * 10)
+        .map_err(|n| format!("odd {n}"));
+        for n in 1..=4 {
+            emitter.ping(n);
+        }
+        let items: Vec<_> = (0..4).map(|_| poll_once(&mut stream)).collect();
+        assert_eq!(
+            items,
+            [
+                Err("odd 1".to_owned()),
+                Ok(20),
+                Err("odd 3".to_owned()),
+                Ok(40)
+            ]
+            .map(|item| Poll::Ready(Some(item)))
+        );
+
+        assert!(stream.is_connected());
+        assert_eq!(stream.object(), Some(emitter.clone().upcast()));
+        stream.disconnect();
+        assert!(!stream.is_connected());
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+/// Disconnects the stream built by `combinator` through its [EvStreamControl] methods.
+fn assert_controls<S, F>(combinator: F)
+where
+    S: EvStreamControl,
+    F: FnOnce(ev_stream_gtk_rs::EvStream<u32>) -> S,
+{
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = combinator(ev_stream!(emitter, ping, |_, n| n));
+        assert!(stream.is_connected());
+        assert_eq!(stream.object(), Some(emitter.clone().upcast()));
+        stream.disconnect();
+        assert!(!stream.is_connected());
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn the_single_stream_combinators_keep_the_control_methods() {
+    let window = Duration::from_millis(25);
+    assert_controls(|s| s.debounce(window));
+    assert_controls(|s| s.throttle(window));
+    assert_controls(|s| s.chunks_timeout(4, window));
+    assert_controls(|s| s.batch_per_iteration());
+    assert_controls(|s| s.delay_each(window));
+    assert_controls(|s| s.with_deadline(window));
+    assert_controls(|s| s.end_after(window));
+    assert_controls(|s| s.settle_marker(window));
+    assert_controls(|s| s.distinct_until_changed());
+    assert_controls(|s| s.dedupe_within(window));
+    assert_controls(|s| s.distinct_by_key(|n| n % 2));
+    assert_controls(|s| s.gate(futures::stream::pending::<bool>(), true));
+    assert_controls(|s| s.group_by(|n| n % 2));
+    assert_controls(|s| s.then_scan(0, |_, _| std::future::ready(())));
+    assert_controls(|s| s.scan_until(0, |sum, n| Some(*sum + n)));
+    assert_controls(|s| s.coalesce_on_resume(3));
+}
+
+#[test]
+fn scan_until_is_disconnected_once_it_ended() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream =
+            ev_stream!(emitter, ping, |_, n| n).scan_until((), |_, n| (n < 2).then_some(n));
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert!(!stream.is_connected());
+        assert_eq!(stream.object(), None);
+        stream.disconnect();
+    });
+}
+
+#[derive(Debug, PartialEq)]
+enum Msg {
+    Ping(u32),
+    Named(String),
+    Label(String),
+}
+
+impl From<u32> for Msg {
+    fn from(n: u32) -> Self {
+        Msg::Ping(n)
+    }
+}
+
+#[test]
+fn map_into_converts_with_from_and_keeps_the_control_methods() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut msgs = ev_stream!(emitter, ping, |_, n| n).map_into::<Msg>();
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut msgs), Poll::Ready(Some(Msg::Ping(1))));
+        assert_eq!(poll_once(&mut msgs), Poll::Ready(Some(Msg::Ping(2))));
+        assert_eq!(msgs.object(), Some(emitter.clone().upcast()));
+        assert!(msgs.is_connected());
+        msgs.disconnect();
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut msgs), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn chains_of_different_types_can_be_stored_as_dyn_ev_streams() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut subscriptions: Vec<DynEvStream<Msg>> = vec![
+            ev_stream!(emitter, ping, |_, n| n)
+                .filter(|n| std::future::ready(n % 2 == 0))
+                .map(Msg::Ping)
+                .into_dyn(),
+            ev_stream!(emitter, named, |_, name| name.to_owned())
+                .map(Msg::Named)
+                .into_dyn(),
+            property_stream::<String>(&emitter, "label")
+                .map(Msg::Label)
+                .into_dyn(),
+        ];
+        emitter.ping(1);
+        emitter.ping(2);
+        emitter.named("a");
+        emitter.set_property("label", "b");
+        let items: Vec<_> = subscriptions.iter_mut().map(poll_once).collect();
+        assert_eq!(
+            items,
+            [
+                Msg::Ping(2),
+                Msg::Named("a".to_owned()),
+                Msg::Label("b".to_owned())
+            ]
+            .map(|item| Poll::Ready(Some(item)))
+        );
+
+        drop(subscriptions);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert!(!has_handlers(&emitter, "named"));
+        assert!(!has_handlers(&emitter, "notify"));
+    });
+}
+
+#[test]
+fn gate_buffering_flushes_the_queued_items_once_open() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let gate = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n)
+            .gate(ev_stream!(gate, ping, |_, open| open != 0), true);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+
+        gate.ping(1);
+        emitter.ping(3);
+        let items: Vec<_> = (0..3).map(|_| poll_once(&mut stream)).collect();
+        assert_eq!(items, [1, 2, 3].map(|n| Poll::Ready(Some(n))));
+
+        gate.ping(0);
+        emitter.ping(4);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+    });
+}
+
+#[test]
+fn gate_without_buffering_drops_the_items_of_the_closed_gate() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let gate = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n)
+            .gate(ev_stream!(gate, ping, |_, open| open != 0), false);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+
+        gate.ping(1);
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(3)));
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+    });
+}
+
+#[test]
+fn gate_ending_closed_discards_the_items_instead_of_queueing_them() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let gate = Emitter::new();
+        let item = Rc::new(());
+        let token = item.clone();
+        let mut stream = ev_stream!(emitter, ping, |_, _n| token.clone())
+            .gate(ev_stream!(gate, ping, |_, open| open != 0), true);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        // Besides the one of the callback, the queued item.
+        assert_eq!(Rc::strong_count(&item), 3);
+
+        // The gate ends closed: the queued item and the later ones are dropped.
+        drop(gate);
+        emitter.ping(2);
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        assert_eq!(Rc::strong_count(&item), 2);
+        drop(emitter);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert_eq!(Rc::strong_count(&item), 1);
+    });
+}
+
+#[test]
+fn dropping_a_combinator_mid_wait_removes_its_main_loop_source() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut debounced = ev_stream!(emitter, ping, |_, n| n).debounce(Duration::from_millis(1));
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut debounced), Poll::Pending);
+        drop(debounced);
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!ctx.pending());
+
+        let mut batches = ev_stream!(emitter, ping, |_, n| n).batch_per_iteration();
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut batches), Poll::Pending);
+        drop(batches);
+        assert!(!ctx.pending());
+    });
+}
+
+#[test]
+fn group_by_routes_two_interleaved_keys_to_their_group() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut groups = ev_stream!(emitter, ping, |_, n| n).group_by(|n| n % 2);
+        for n in 1..=5 {
+            emitter.ping(n);
+        }
+        let Poll::Ready(Some((1, odd))) = poll_once(&mut groups) else {
+            panic!("expected the odd group first");
+        };
+        let Poll::Ready(Some((0, even))) = poll_once(&mut groups) else {
+            panic!("expected the even group");
+        };
+        assert!(poll_once(&mut groups).is_pending());
+        let received = |group: &channel::Receiver<u32>| {
+            std::iter::from_fn(|| group.try_recv().unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(received(&odd), [1, 3, 5]);
+        assert_eq!(received(&even), [2, 4]);
+
+        // A dropped group is started again by the next item with its key.
+        drop(even);
+        emitter.ping(6);
+        let Poll::Ready(Some((0, even))) = poll_once(&mut groups) else {
+            panic!("expected a new even group");
+        };
+        assert_eq!(received(&even), [6]);
+
+        // The groups end with the stream.
+        drop(emitter);
+        assert!(matches!(poll_once(&mut groups), Poll::Ready(None)));
+        assert_eq!(odd.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
+
+#[test]
+fn then_scan_waits_for_each_future_before_the_next_item() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let delays = Rc::new(RefCell::new(VecDeque::new()));
+        let mut totals = Box::pin(ev_stream!(emitter, ping, |_, n| n).then_scan(0, {
+            let started = started.clone();
+            let delays = delays.clone();
+            move |total: &mut u32, n| {
+                *total += n;
+                started.borrow_mut().push(n);
+                // Completes when the test sends on the other end.
+                let (done, delay) = oneshot::channel::<()>();
+                delays.borrow_mut().push_back(done);
+                let total = *total;
+                async move {
+                    let _ = delay.await;
+                    total
+                }
+            }
+        }));
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut totals), Poll::Pending);
+        assert_eq!(poll_once(&mut totals), Poll::Pending);
+        assert_eq!(*started.borrow(), [1]);
+
+        delays.borrow_mut().pop_front().unwrap().send(()).unwrap();
+        assert_eq!(poll_once(&mut totals), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut totals), Poll::Pending);
+        assert_eq!(*started.borrow(), [1, 2]);
+
+        delays.borrow_mut().pop_front().unwrap().send(()).unwrap();
+        assert_eq!(poll_once(&mut totals), Poll::Ready(Some(3)));
+    });
+}
+
+#[test]
+fn for_each_concurrent_local_runs_at_most_limit_tasks_at_once() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let running = Rc::new(Cell::new(0));
+        let most_running = Rc::new(Cell::new(0));
+        let delays = Rc::new(RefCell::new(VecDeque::new()));
+        let mut done = Box::pin(
+            ev_stream!(emitter, ping, |_, n| n).for_each_concurrent_local(2, {
+                let started = started.clone();
+                let running = running.clone();
+                let most_running = most_running.clone();
+                let delays = delays.clone();
+                move |n| {
+                    started.borrow_mut().push(n);
+                    let (done, delay) = oneshot::channel::<()>();
+                    delays.borrow_mut().push_back(done);
+                    let running = running.clone();
+                    let most_running = most_running.clone();
+                    async move {
+                        running.set(running.get() + 1);
+                        most_running.set(most_running.get().max(running.get()));
+                        let _ = delay.await;
+                        running.set(running.get() - 1);
+                    }
+                }
+            }),
+        );
+        for n in 1..=4 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_future(&mut done), Poll::Pending);
+        flush(ctx);
+        assert_eq!(*started.borrow(), [1, 2]);
+        assert_eq!(running.get(), 2);
+
+        // Each completed task makes room for exactly one more.
+        delays.borrow_mut().pop_front().unwrap().send(()).unwrap();
+        flush(ctx);
+        assert_eq!(poll_future(&mut done), Poll::Pending);
+        flush(ctx);
+        assert_eq!(*started.borrow(), [1, 2, 3]);
+        assert_eq!(running.get(), 2);
+
+        drop(emitter);
+        while let Some(delay) = delays.borrow_mut().pop_front() {
+            delay.send(()).unwrap();
+        }
+        flush(ctx);
+        assert_eq!(poll_future(&mut done), Poll::Pending);
+        flush(ctx);
+        assert_eq!(*started.borrow(), [1, 2, 3, 4]);
+        delays.borrow_mut().pop_front().unwrap().send(()).unwrap();
+        flush(ctx);
+        assert_eq!(poll_future(&mut done), Poll::Ready(()));
+        assert_eq!(most_running.get(), 2);
+    });
+}
+
+#[test]
+fn buffer_unordered_local_runs_at_most_limit_tasks_and_yields_every_output() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let running = Rc::new(Cell::new(0));
+        let most_running = Rc::new(Cell::new(0));
+        let delays = Rc::new(RefCell::new(Vec::new()));
+        let mut outputs = ev_stream!(emitter, ping, |_, n| n).buffer_unordered_local(2, {
+            let started = started.clone();
+            let running = running.clone();
+            let most_running = most_running.clone();
+            let delays = delays.clone();
+            move |n| {
+                started.borrow_mut().push(n);
+                let (done, delay) = oneshot::channel::<()>();
+                delays.borrow_mut().push(done);
+                let running = running.clone();
+                let most_running = most_running.clone();
+                async move {
+                    running.set(running.get() + 1);
+                    most_running.set(most_running.get().max(running.get()));
+                    let _ = delay.await;
+                    running.set(running.get() - 1);
+                    n * 10
+                }
+            }
+        });
+        for n in 1..=4 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut outputs), Poll::Pending);
+        flush(ctx);
+        assert_eq!(*started.borrow(), [1, 2]);
+        assert_eq!(running.get(), 2);
+
+        // The second task completes first: its output comes first, out of order, and its
+        // room goes to the third item.
+        delays.borrow_mut().remove(1).send(()).unwrap();
+        flush(ctx);
+        assert_eq!(poll_once(&mut outputs), Poll::Ready(Some(20)));
+        assert_eq!(*started.borrow(), [1, 2, 3]);
+
+        // The buffered items are still mapped once the object is gone, then it ends.
+        drop(emitter);
+        let mut rest = Vec::new();
+        for _ in 0..8 {
+            let delays: Vec<_> = delays.borrow_mut().drain(..).collect();
+            for delay in delays {
+                delay.send(()).unwrap();
+            }
+            flush(ctx);
+            match poll_once(&mut outputs) {
+                Poll::Ready(Some(output)) => rest.push(output),
+                Poll::Ready(None) => break,
+                Poll::Pending => {}
+            }
+        }
+        rest.sort_unstable();
+        assert_eq!(rest, [10, 30, 40]);
+        assert_eq!(*started.borrow(), [1, 2, 3, 4]);
+        assert_eq!(most_running.get(), 2);
+    });
+}
+
+#[test]
+fn take_while_connected_drops_the_buffered_items_once_the_object_died() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut eager = ev_stream!(emitter, ping, |_, n| n).take_while_connected();
+        let mut draining = ev_stream!(emitter, ping, |_, n| n);
+        for n in 1..=3 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut eager), Poll::Ready(Some(1)));
+        drop(emitter);
+        assert_eq!(poll_once(&mut eager), Poll::Ready(None));
+        assert_eq!(poll_once(&mut eager), Poll::Ready(None));
+        assert_eq!(futures::Stream::size_hint(&eager), (0, Some(0)));
+
+        // Unlike the stream on its own, which yields its buffered items first.
+        assert_eq!(draining.drain_now(), [1, 2, 3]);
+        assert_eq!(poll_once(&mut draining), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn flatten_options_skips_the_none_items() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut even =
+            ev_stream!(emitter, ping, |_, n| (n % 2 == 0).then_some(n)).flatten_options();
+        for n in 1..=6 {
+            emitter.ping(n);
+        }
+        let items: Vec<_> = (0..4).map(|_| poll_once(&mut even)).collect();
+        assert_eq!(
+            items,
+            [
+                Poll::Ready(Some(2)),
+                Poll::Ready(Some(4)),
+                Poll::Ready(Some(6)),
+                Poll::Pending
+            ]
+        );
+        emitter.ping(7);
+        assert_eq!(poll_once(&mut even), Poll::Pending);
+        assert_eq!(even.object(), Some(emitter.clone().upcast()));
+        drop(emitter);
+        assert_eq!(poll_once(&mut even), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn prepend_yields_the_recorded_items_before_any_live_one() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings =
+            ev_stream!(emitter, ping, |_, n| n).prepend(futures::stream::iter(vec![10, 20]));
+        // Emitted before the recorded items were consumed, still yielded after them.
+        emitter.ping(1);
+        let items: Vec<_> = (0..4).map(|_| poll_once(&mut pings)).collect();
+        assert_eq!(
+            items,
+            [
+                Poll::Ready(Some(10)),
+                Poll::Ready(Some(20)),
+                Poll::Ready(Some(1)),
+                Poll::Pending
+            ]
+        );
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(2)));
+        assert_eq!(pings.object(), Some(emitter.clone().upcast()));
+    });
+}
+
+#[test]
+fn with_interval_pairs_each_item_with_the_time_since_the_previous_one() {
+    with_context(|_| {
+        const SPACING: Duration = Duration::from_millis(20);
+        let emitter = Emitter::new();
+        let mut timed = ev_stream!(emitter, ping, |_, n| n).with_interval();
+        emitter.ping(1);
+        assert!(matches!(
+            poll_once(&mut timed),
+            Poll::Ready(Some((1, None)))
+        ));
+
+        std::thread::sleep(SPACING);
+        emitter.ping(2);
+        emitter.ping(3);
+        let Poll::Ready(Some((2, Some(spaced)))) = poll_once(&mut timed) else {
+            panic!("expected the second item with an interval");
+        };
+        assert!(spaced >= SPACING, "{spaced:?}");
+        let Poll::Ready(Some((3, Some(burst)))) = poll_once(&mut timed) else {
+            panic!("expected the third item with an interval");
+        };
+        assert!(burst < spaced, "{burst:?} >= {spaced:?}");
+    });
+}
+
+#[test]
+fn map_with_object_sees_none_once_the_object_is_finalized() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        emitter.set_property("value", 7);
+        let mut values = ev_stream!(emitter, ping, |_, n| n).map_with_object(|object, n| {
+            // The state of the object when the item is consumed.
+            let value = object.map(|object| object.property::<i32>("value"));
+            (n, value)
+        });
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut values), Poll::Ready(Some((1, Some(7)))));
+        emitter.set_property("value", 8);
+        assert_eq!(poll_once(&mut values), Poll::Ready(Some((2, Some(8)))));
+
+        emitter.ping(3);
+        drop(emitter);
+        assert_eq!(poll_once(&mut values), Poll::Ready(Some((3, None))));
+        assert_eq!(poll_once(&mut values), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn dedupe_within_skips_a_duplicate_only_inside_the_window() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream =
+            ev_stream!(emitter, ping, |_, n| n).dedupe_within(Duration::from_millis(50));
+        emitter.ping(1);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        // Inside the window: dropped.
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        // A different item always passes.
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(2)));
+
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        std::thread::sleep(Duration::from_millis(60));
+        // Outside the window: passed.
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(2)));
+    });
+}
+
+#[test]
+fn try_for_each_local_stops_and_disconnects_at_the_first_error() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let seen = RefCell::new(Vec::new());
+        let mut processing =
+            Box::pin(ev_stream!(emitter, ping, |_, n| n).try_for_each_local(|n| {
+                seen.borrow_mut().push(n);
+                std::future::ready(if n == 2 { Err(n) } else { Ok(()) })
+            }));
+        emitter.ping(1);
+        assert_eq!(poll_future(&mut processing), Poll::Pending);
+        emitter.ping(2);
+        emitter.ping(3);
+        assert_eq!(poll_future(&mut processing), Poll::Ready(Err(2)));
+        // Item 3 was buffered, but never processed.
+        assert_eq!(*seen.borrow(), [1, 2]);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn try_for_each_local_resolves_to_ok_once_the_stream_ends() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut processing = Box::pin(
+            ev_stream!(emitter, ping, |_, n| n)
+                .try_for_each_local(|_| std::future::ready(Ok::<(), ()>(()))),
+        );
+        emitter.ping(1);
+        assert_eq!(poll_future(&mut processing), Poll::Pending);
+        drop(emitter);
+        assert_eq!(poll_future(&mut processing), Poll::Ready(Ok(())));
+    });
+}
+
+#[test]
+fn scan_until_counts_up_to_its_threshold_then_disconnects() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut counts = ev_stream!(emitter, ping, |_, _| ()).scan_until(0, |count, ()| {
+            *count += 1;
+            (*count <= 3).then_some(*count)
+        });
+        for n in 0..5 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut counts), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut counts), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut counts), Poll::Ready(Some(3)));
+        assert!(has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut counts), Poll::Ready(None));
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut counts), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn on_backlog_reports_the_buffered_items_above_the_threshold() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let reported = RefCell::new(Vec::new());
+        let mut pings = ev_stream!(emitter, ping, |_, n| n)
+            .on_backlog(2, |pending| reported.borrow_mut().push(pending));
+        emitter.ping(1);
+        emitter.ping(2);
+        // At the threshold: not reported.
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+        assert!(reported.borrow().is_empty());
+
+        for n in 3..=5 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(3)));
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(4)));
+        assert_eq!(*reported.borrow(), [4, 3]);
+    });
+}
@@ -0,0 +1,100 @@
+//! The combinators logging through the `log` crate, with the `log` feature.
+mod common;
+
+use common::{poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, EvStreamExt};
+use glib::prelude::*;
+use std::sync::{Mutex, Once};
+use std::task::Poll;
+use std::thread::ThreadId;
+
+/// A record logged by a test, with its level, target and message.
+type Logged = (log::Level, String, String);
+
+/// Keeps the records, for the thread of the test which logged them.
+struct Capture(Mutex<Vec<(ThreadId, Logged)>>);
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+impl log::Log for Capture {
+    fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        let logged = (
+            record.level(),
+            record.target().to_owned(),
+            record.args().to_string(),
+        );
+        self.0
+            .lock()
+            .unwrap()
+            .push((std::thread::current().id(), logged));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Takes the records logged so far by the calling thread, installing the logger first.
+fn logged() -> Vec<Logged> {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+    let thread = std::thread::current().id();
+    let mut records = CAPTURE.0.lock().unwrap();
+    let (own, others) = records.drain(..).partition(|(id, _)| *id == thread);
+    *records = others;
+    own.into_iter().map(|(_, logged)| logged).collect()
+}
+
+#[test]
+fn log_each_logs_every_item_at_its_level_and_passes_it_through() {
+    with_context(|_| {
+        logged();
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n).log_each("pings", log::Level::Info);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(2)));
+        assert_eq!(
+            logged(),
+            [
+                (log::Level::Info, "pings".to_owned(), "1".to_owned()),
+                (log::Level::Info, "pings".to_owned(), "2".to_owned()),
+            ]
+        );
+    });
+}
+
+#[test]
+fn unwrap_or_log_skips_the_errors_and_logs_them_at_its_level() {
+    with_context(|_| {
+        logged();
+        let emitter = Emitter::new();
+        let mut focus = ev_stream!(emitter, "focused", |args| args[1].get::<glib::Object>())
+            .unwrap_or_log(log::Level::Warn);
+        let other = glib::Object::new::<glib::Object>();
+        // Doesn't make an `Object` out of `None`.
+        emitter.focused(None);
+        emitter.focused(Some(&other));
+        assert_eq!(poll_once(&mut focus), Poll::Ready(Some(other)));
+        assert_eq!(poll_once(&mut focus), Poll::Pending);
+
+        let err = None::<glib::Object>
+            .to_value()
+            .get::<glib::Object>()
+            .unwrap_err();
+        assert_eq!(
+            logged(),
+            [(
+                log::Level::Warn,
+                "ev_stream_gtk_rs::logging".to_owned(),
+                format!("skipped an emission: {err}"),
+            )]
+        );
+    });
+}
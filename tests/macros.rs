@@ -0,0 +1,435 @@
+mod common;
+
+use common::{has_handlers, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{
+    ev_stream, ev_stream_after, ev_stream_any, ev_stream_decide, ev_stream_weak_item, EvStream,
+};
+use futures::Stream;
+use glib::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::task::Poll;
+
+/// A component keeping its widget in a field, which isn't `Clone`.
+struct Component {
+    emitter: Emitter,
+}
+
+impl Component {
+    fn pings(&self) -> EvStream<u32> {
+        ev_stream!(&self.emitter, ping, |_, n| n)
+    }
+}
+
+#[test]
+fn subscribes_to_a_borrowed_field_from_a_ref_self_method() {
+    with_context(|_| {
+        let component = Component {
+            emitter: Emitter::new(),
+        };
+        let mut pings = component.pings();
+        component.emitter.ping(1);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+        drop(pings);
+        assert!(!has_handlers(&component.emitter, "ping"));
+    });
+}
+
+#[test]
+fn untyped_form_connects_with_connect_local_when_the_context_is_owned() {
+    with_context(|ctx| {
+        assert!(ctx.is_owner());
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, "ping", |args| args[1].get::<u32>().unwrap());
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+        drop(pings);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn untyped_form_connects_the_same_without_an_owned_context() {
+    std::thread::spawn(|| {
+        assert!(!glib::MainContext::ref_thread_default().is_owner());
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, "ping", |args| args[1].get::<u32>().unwrap());
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+
+        drop(pings);
+        assert!(!has_handlers(&emitter, "ping"));
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn underscore_prefixed_captures_are_accepted() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut names = ev_stream!(emitter, named, |_emitter, name| name.to_owned());
+        // The default body still clones the underscore-prefixed arguments, only `_` is skipped.
+        let mut pairs = ev_stream!(emitter, ping, |_emitter, _n|);
+        let mut values = ev_stream!(emitter, ping, |_, _n|);
+        emitter.named("a");
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut names), Poll::Ready(Some("a".to_owned())));
+        assert_eq!(
+            poll_once(&mut pairs),
+            Poll::Ready(Some((emitter.clone(), 1)))
+        );
+        assert_eq!(poll_once(&mut values), Poll::Ready(Some(1)));
+    });
+}
+
+#[test]
+fn weak_items_dont_keep_the_object_alive() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut typed = ev_stream_weak_item!(emitter, ping, |e, _|);
+        let mut untyped = ev_stream_weak_item!(emitter, "ping", |args|);
+        emitter.ping(1);
+        emitter.ping(2);
+        let first = match poll_once(&mut typed) {
+            Poll::Ready(Some(weak)) => weak,
+            _ => panic!("expected an item"),
+        };
+        assert_eq!(first.upgrade().as_ref(), Some(&emitter));
+
+        drop(emitter);
+        // Both the taken and the still buffered items only point to the finalized object.
+        assert!(first.upgrade().is_none());
+        let Poll::Ready(Some(buffered)) = poll_once(&mut typed) else {
+            panic!("expected the buffered item");
+        };
+        assert!(buffered.upgrade().is_none());
+        let Poll::Ready(Some(buffered)) = poll_once(&mut untyped) else {
+            panic!("expected the buffered item");
+        };
+        assert!(buffered.upgrade().is_none());
+    });
+}
+
+#[derive(Debug, PartialEq)]
+enum Msg {
+    Ping(u32),
+    Named(String),
+}
+
+#[test]
+fn ev_stream_any_keeps_the_emission_order_across_signals() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut events = ev_stream_any!(emitter, {
+            ping: |_, n| Msg::Ping(n),
+            named: |_, name| Msg::Named(name.to_owned()),
+            "ping": |args| Msg::Ping(args[1].get::<u32>().unwrap() + 100),
+        });
+        emitter.named("a");
+        emitter.ping(1);
+        emitter.named("b");
+        assert_eq!(
+            events.drain_now(),
+            [
+                Msg::Named("a".to_owned()),
+                Msg::Ping(1),
+                Msg::Ping(101),
+                Msg::Named("b".to_owned()),
+            ]
+        );
+        drop(events);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert!(!has_handlers(&emitter, "named"));
+    });
+}
+
+#[test]
+fn emitting_after_the_receiver_was_dropped_doesnt_unwind_into_glib() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        // Handlers outliving their receiver, which dropping an `EvStream` never leaves.
+        for stream in [
+            ev_stream!(emitter, "ping", |args| args.len()),
+            ev_stream!(emitter, ping, |_, n| n as usize),
+        ] {
+            let Ok((_, _, receiver)) = stream.into_raw_parts() else {
+                panic!("the stream was made of a signal handler");
+            };
+            drop(receiver);
+        }
+        // An unwinding panic would abort the test binary here.
+        emitter.ping(1);
+        emitter.ping(2);
+    });
+}
+
+#[derive(Debug, PartialEq)]
+struct Pinged {
+    emitter: Emitter,
+    n: u32,
+}
+
+#[test]
+fn the_body_can_build_a_named_struct_from_the_arguments() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |emitter, n| Pinged {
+            emitter: emitter.clone(),
+            n,
+        });
+        emitter.ping(1);
+        assert_eq!(
+            poll_once(&mut stream),
+            Poll::Ready(Some(Pinged {
+                emitter: emitter.clone(),
+                n: 1
+            }))
+        );
+    });
+}
+
+#[test]
+fn streams_the_signal_of_a_bare_glib_object() {
+    with_context(|_| {
+        let object = glib::Object::new::<glib::Object>();
+        let mut names = ev_stream!(object, "notify", |args| args[1]
+            .get::<glib::ParamSpec>()
+            .unwrap()
+            .name()
+            .to_owned());
+        let pspec = glib::ParamSpecInt::builder("size").build();
+        object.emit_by_name::<()>("notify", &[&pspec]);
+        assert_eq!(poll_once(&mut names), Poll::Ready(Some("size".to_owned())));
+        drop(names);
+        assert!(!has_handlers(&object, "notify"));
+    });
+}
+
+#[test]
+fn trailing_commas_are_accepted_in_every_form() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut streams = [
+            ev_stream!(emitter, ping, |_, n|,),
+            ev_stream!(emitter, ping, |_, n| n * 10,),
+            ev_stream!(emitter, ping, |_emitter, n| n * 100,),
+            ev_stream!(emitter, "ping", |args| args[1].get::<u32>().unwrap(),),
+            // As rustfmt lays out a long call.
+            ev_stream!(emitter, ping, |_, n| n * 1000,),
+        ];
+        emitter.ping(1);
+        let items: Vec<_> = streams.iter_mut().map(poll_once).collect();
+        assert_eq!(items, [1, 10, 100, 1, 1000].map(|n| Poll::Ready(Some(n))));
+    });
+}
+
+/// The number of pings buffered by a stream of `T`, which nothing else determines.
+fn count_pings_as<T>(emitter: &Emitter, pings: u32) -> usize
+where
+    T: for<'a> glib::value::FromValue<'a> + 'static,
+{
+    let mut stream = ev_stream!(emitter, "ping"::<T>, |args| args[1].get().unwrap());
+    for n in 0..pings {
+        emitter.ping(n);
+    }
+    stream.drain_now().len()
+}
+
+#[test]
+fn the_item_type_can_be_given_in_a_generic_context() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        assert_eq!(count_pings_as::<u32>(&emitter, 3), 3);
+        assert_eq!(count_pings_as::<glib::Value>(&emitter, 2), 2);
+    });
+}
+
+#[test]
+fn ev_stream_decide_returns_the_decision_and_streams_the_arguments() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut decided = ev_stream_decide!(emitter, decide, |_, n| n % 2 == 0, |_emitter, n| n);
+        assert!(emitter.decide(2));
+        assert!(!emitter.decide(3));
+        assert_eq!(decided.drain_now(), [2, 3]);
+
+        let mut untyped = ev_stream_decide!(
+            emitter,
+            "decide",
+            |args: &[glib::Value]| Some((args[1].get::<u32>().unwrap() > 10).to_value()),
+            |args| args[1].get::<u32>().unwrap()
+        );
+        drop(decided);
+        assert!(emitter.decide(11));
+        assert!(!emitter.decide(4));
+        assert_eq!(untyped.drain_now(), [11, 4]);
+    });
+}
+
+#[test]
+fn a_null_object_argument_is_cloned_into_none() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut focus = ev_stream!(emitter, focused, |emitter, focus|);
+        let mut untyped = ev_stream!(emitter, "focused", |args| args[1]
+            .get::<Option<glib::Object>>()
+            .unwrap());
+        let other = glib::Object::new::<glib::Object>();
+        emitter.focused(Some(&other));
+        emitter.focused(None);
+        assert_eq!(
+            focus.drain_now(),
+            [
+                (emitter.clone(), Some(other.clone())),
+                (emitter.clone(), None)
+            ]
+        );
+        assert_eq!(untyped.drain_now(), [Some(other), None]);
+    });
+}
+
+#[test]
+fn ev_stream_after_runs_after_the_handlers_connected_later() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let before = Rc::new(RefCell::new(ev_stream!(emitter, ping, |_, n| n)));
+        let after = Rc::new(RefCell::new(ev_stream_after!(
+            emitter,
+            ping,
+            |_emitter: Emitter, n: u32| n
+        )));
+        // What each stream had buffered when a handler connected after both of them ran.
+        let buffered = Rc::new(Cell::new((0, 0)));
+        emitter.connect_ping({
+            let (before, after, buffered) = (before.clone(), after.clone(), buffered.clone());
+            move |_, _| {
+                let len = |stream: &RefCell<EvStream<u32>>| stream.borrow().size_hint().0;
+                buffered.set((len(&before), len(&after)));
+            }
+        });
+        emitter.ping(1);
+        assert_eq!(buffered.get(), (1, 0));
+        assert_eq!(after.borrow_mut().drain_now(), [1]);
+
+        // The untyped form too, and the typed one skips arguments of another type.
+        let mut untyped = ev_stream_after!(emitter, "ping", |args| args.len());
+        let mut mismatched = ev_stream_after!(emitter, ping, |_emitter: Emitter, n: String| n);
+        emitter.ping(2);
+        assert_eq!(buffered.get(), (2, 0));
+        assert_eq!(untyped.drain_now(), [2]);
+        assert!(mismatched.drain_now().is_empty());
+    });
+}
+
+struct View {
+    model: Emitter,
+}
+
+impl View {
+    fn model(&self) -> Emitter {
+        self.model.clone()
+    }
+}
+
+#[test]
+fn a_source_kept_alive_elsewhere_stays_connected_through_a_temporary() {
+    with_context(|_| {
+        let view = View {
+            model: Emitter::new(),
+        };
+        let mut typed = ev_stream!(view.model(), ping, |_, n| n);
+        let mut untyped = ev_stream!(view.model(), "ping", |args| args[1].get::<u32>().unwrap());
+        view.model.ping(1);
+        assert_eq!(poll_once(&mut typed), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut untyped), Poll::Ready(Some(1)));
+    });
+}
+
+#[test]
+fn a_temporary_holding_the_last_reference_yields_an_ended_stream() {
+    with_context(|_| {
+        let mut typed = ev_stream!(Emitter::new(), ping, |_, n| n);
+        assert!(!typed.is_connected());
+        assert_eq!(poll_once(&mut typed), Poll::Ready(None));
+        let mut untyped = ev_stream!(Emitter::new(), "ping", |args| args.len());
+        assert_eq!(poll_once(&mut untyped), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn ev_stream_any_disconnects_the_handlers_on_each_object() {
+    with_context(|_| {
+        let (button, entry) = (Emitter::new(), Emitter::new());
+        let mut events = ev_stream_any!({
+            button.ping => |_, n| Msg::Ping(n),
+            entry.named => |_, name| Msg::Named(name.to_owned()),
+            entry.ping => |_, n| Msg::Ping(n + 100),
+        });
+        entry.named("a");
+        button.ping(1);
+        entry.ping(2);
+        assert_eq!(
+            events.drain_now(),
+            [Msg::Named("a".to_owned()), Msg::Ping(1), Msg::Ping(102)]
+        );
+
+        drop(events);
+        assert!(!has_handlers(&button, "ping"));
+        assert!(!has_handlers(&entry, "named"));
+        assert!(!has_handlers(&entry, "ping"));
+    });
+}
+
+#[test]
+fn ev_stream_any_keeps_the_handlers_of_the_other_objects_once_one_is_gone() {
+    with_context(|_| {
+        let (button, entry) = (Emitter::new(), Emitter::new());
+        let mut events = ev_stream_any!({
+            button.ping => |_, n| Msg::Ping(n),
+            entry.named => |_, name| Msg::Named(name.to_owned()),
+        });
+        drop(button);
+        entry.named("a");
+        assert_eq!(events.drain_now(), [Msg::Named("a".to_owned())]);
+        drop(events);
+        assert!(!has_handlers(&entry, "named"));
+    });
+}
+
+/// The shape of the gtk4 `connect_<event>` methods of the event controllers, whose
+/// handler returns a `glib::Propagation`, on top of the `decide` signal.
+trait ConnectKeyPressed {
+    fn connect_key_pressed<F: Fn(&Emitter, u32) -> glib::Propagation + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId;
+}
+
+impl ConnectKeyPressed for Emitter {
+    fn connect_key_pressed<F: Fn(&Emitter, u32) -> glib::Propagation + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_decide(move |emitter, keyval| f(emitter, keyval).is_stop())
+    }
+}
+
+#[test]
+fn typed_form_returns_a_propagation_to_handlers_expecting_one() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut proceeding = ev_stream!(emitter, key_pressed, |_emitter, keyval| keyval);
+        assert!(!emitter.decide(1));
+        assert_eq!(proceeding.drain_now(), [1]);
+        drop(proceeding);
+
+        let mut stopping = ev_stream!(
+            emitter,
+            key_pressed,
+            |_emitter, keyval| keyval => glib::Propagation::Stop
+        );
+        assert!(emitter.decide(2));
+        assert_eq!(stopping.drain_now(), [2]);
+    });
+}
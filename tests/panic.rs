@@ -0,0 +1,145 @@
+//! A panic inside a glib signal handler aborts the process: each case runs in a child
+//! process, re-running this test binary with only the test that panics.
+mod common;
+
+use common::{with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, ev_stream_decide, property_stream};
+use glib::prelude::*;
+use std::process::Command;
+
+/// Set in the child process, to the name of the test to panic in.
+const CHILD: &str = "EV_STREAM_PANIC_CHILD";
+
+/// Runs `test` in a child process and asserts that it aborted with the message of the
+/// crate, after the panic message `panic`.
+fn assert_aborts(test: &str, panic: &str) {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD, test)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "the child didn't abort: {stderr}");
+    assert!(stderr.contains(panic), "{stderr}");
+    assert!(
+        stderr.contains("panicked inside a glib signal handler"),
+        "{stderr}"
+    );
+}
+
+/// Whether this is the child process running `test`.
+fn is_child(test: &str) -> bool {
+    std::env::var(CHILD).is_ok_and(|child| child == test)
+}
+
+#[test]
+fn child_body() {
+    if !is_child("child_body") {
+        return;
+    }
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let _pings = ev_stream!(emitter, ping, |_, n| if n > 0 {
+            panic!("body panicked on {n}")
+        } else {
+            n
+        });
+        emitter.ping(1);
+    });
+}
+
+#[test]
+fn child_untyped_body() {
+    if !is_child("child_untyped_body") {
+        return;
+    }
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let _pings = ev_stream!(emitter, "ping", |args| if args.is_empty() {
+            0
+        } else {
+            panic!("untyped body")
+        });
+        emitter.ping(1);
+    });
+}
+
+#[test]
+fn child_decide() {
+    if !is_child("child_decide") {
+        return;
+    }
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let _decisions = ev_stream_decide!(
+            emitter,
+            decide,
+            |_, n: u32| if n > 0 {
+                panic!("decide panicked")
+            } else {
+                true
+            },
+            |_emitter, n| n
+        );
+        emitter.decide(1);
+    });
+}
+
+#[test]
+fn child_property_of_another_type() {
+    if !is_child("child_property_of_another_type") {
+        return;
+    }
+    with_context(|_| {
+        let emitter = Emitter::new();
+        // `value` is an `i32`.
+        let _values = property_stream::<String>(&emitter, "value");
+        emitter.set_property("value", 1);
+    });
+}
+
+#[test]
+#[cfg(not(feature = "catch-send-panic"))]
+fn child_strict_send_after_drop() {
+    if !is_child("child_strict_send_after_drop") {
+        return;
+    }
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n).strict();
+        // Keeps the handler connected while the receiver is gone.
+        let Ok((_object, _signal_ids, receiver)) = pings.into_raw_parts() else {
+            unreachable!("the stream has a live object and a signal handler");
+        };
+        drop(receiver);
+        emitter.ping(1);
+    });
+}
+
+#[test]
+fn a_panicking_body_aborts() {
+    assert_aborts("child_body", "body panicked on 1");
+    assert_aborts("child_untyped_body", "untyped body");
+}
+
+#[test]
+fn a_panicking_decide_aborts() {
+    assert_aborts("child_decide", "decide panicked");
+}
+
+#[test]
+fn a_property_of_another_type_aborts() {
+    assert_aborts(
+        "child_property_of_another_type",
+        "Failed to get cast value to a different type",
+    );
+}
+
+#[test]
+#[cfg(not(feature = "catch-send-panic"))]
+fn a_strict_send_after_the_drop_aborts() {
+    assert_aborts(
+        "child_strict_send_after_drop",
+        "sent after its stream was dropped",
+    );
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, GlobalPause, PauseMode};
+use futures::task::{waker, ArcWake};
+use futures::{Stream, StreamExt};
+use glib::prelude::*;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[derive(Default)]
+struct Woken(AtomicBool);
+
+impl ArcWake for Woken {
+    fn wake_by_ref(woken: &Arc<Self>) {
+        woken.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn a_buffering_pause_withholds_the_items_until_it_is_dropped() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut doubled = ev_stream!(emitter, ping, |_, n| n).map(|n| n * 2);
+        let pause = GlobalPause::new(PauseMode::Buffer);
+        emitter.ping(1);
+        emitter.ping(2);
+        let woken = Arc::new(Woken::default());
+        let waker = waker(woken.clone());
+        let poll = Pin::new(&mut doubled).poll_next(&mut Context::from_waker(&waker));
+        assert_eq!(poll, Poll::Pending);
+
+        drop(pause);
+        assert!(woken.0.load(Ordering::SeqCst));
+        assert_eq!(poll_once(&mut doubled), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut doubled), Poll::Ready(Some(4)));
+    });
+}
+
+#[test]
+fn a_dropping_pause_discards_the_items_emitted_meanwhile() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        emitter.ping(1);
+        let pause = GlobalPause::new(PauseMode::Drop);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut pings), Poll::Pending);
+        drop(pause);
+        emitter.ping(3);
+        // Buffered before the pause: kept.
+        assert_eq!(pings.drain_now(), [1, 3]);
+    });
+}
+
+#[test]
+fn nested_pauses_drop_the_items_while_any_dropping_one_is_held() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        let buffering = GlobalPause::new(PauseMode::Buffer);
+        let dropping = GlobalPause::new(PauseMode::Drop);
+        emitter.ping(1);
+        drop(dropping);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut pings), Poll::Pending);
+        drop(buffering);
+        assert_eq!(pings.drain_now(), [2]);
+    });
+}
+
+#[test]
+fn a_buffering_pause_withholds_the_items_from_the_non_async_paths() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        let pause = GlobalPause::new(PauseMode::Buffer);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(pings.try_recv(), Ok(None));
+        assert!(pings.drain_now().is_empty());
+        let mut buf = Vec::new();
+        assert_eq!(pings.recv_many(2, &mut buf), 0);
+
+        drop(pause);
+        assert_eq!(pings.drain_now(), [1, 2]);
+    });
+}
+
+#[test]
+fn a_buffering_pause_withholds_the_items_from_the_subscribers() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        let mut subscriber = pings.subscribe();
+        let pause = GlobalPause::new(PauseMode::Buffer);
+        emitter.ping(1);
+        assert_eq!(subscriber.try_recv(), Ok(None));
+        assert_eq!(poll_once(&mut subscriber), Poll::Pending);
+
+        drop(pause);
+        assert_eq!(poll_once(&mut subscriber), Poll::Ready(Some(1)));
+        drop(pings);
+    });
+}
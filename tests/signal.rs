@@ -0,0 +1,269 @@
+mod common;
+
+use common::{has_handlers, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{
+    check_signal, destroyed, ev_stream, properties_stream, property_stream,
+    property_stream_emit_on_rebind, property_stream_no_echo, raw_signal_stream,
+    subclass_signal_stream, try_ev_stream, typed_signal_stream, EvStream, FromSignalArgs,
+};
+use futures::Stream;
+use glib::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::task::Poll;
+
+#[test]
+fn typed_signal_stream_connects_once_downcast() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let object: glib::Object = emitter.clone().upcast();
+        let mut stream = typed_signal_stream(object, |emitter: &Emitter| {
+            ev_stream!(emitter, ping, |_, n| n)
+        })
+        .unwrap();
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+    });
+}
+
+#[test]
+fn typed_signal_stream_reports_a_failed_cast() {
+    with_context(|_| {
+        let object = glib::Object::new::<glib::Object>();
+        let error = typed_signal_stream(object.clone(), |emitter: &Emitter| {
+            ev_stream!(emitter, ping, |_, n| n)
+        })
+        .err()
+        .expect("the cast should fail");
+        assert_eq!(error.object, object);
+        assert_eq!(error.expected, Emitter::static_type());
+        assert_eq!(
+            error.to_string(),
+            "expected an object of type `EvStreamTestEmitter`, got `GObject`"
+        );
+    });
+}
+
+#[test]
+fn property_stream_only_follows_its_property() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut values = property_stream::<i32>(&emitter, "value");
+        emitter.set_property("label", "ignored");
+        emitter.set_property("value", 3);
+        emitter.set_property("label", "ignored again");
+        assert_eq!(values.drain_now(), [3]);
+    });
+}
+
+#[test]
+fn property_stream_no_echo_skips_the_changes_of_its_setter() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (mut values, set_value) = property_stream_no_echo::<i32>(&emitter, "value");
+        set_value(1);
+        assert_eq!(emitter.property::<i32>("value"), 1);
+        assert!(values.drain_now().is_empty());
+
+        // Other changes still come through, even right after an echo.
+        emitter.set_property("value", 2);
+        set_value(3);
+        emitter.set_property("value", 4);
+        assert_eq!(values.drain_now(), [2, 4]);
+    });
+}
+
+struct Ping(u32);
+
+impl FromSignalArgs for Ping {
+    fn from_signal_args(args: &[glib::Value]) -> Option<Self> {
+        match args {
+            [_, n] => Some(Self(n.get().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn subclass_signal_stream_connects_to_a_registered_signal() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = subclass_signal_stream::<Ping>(&emitter, "ping").unwrap();
+        emitter.ping(1);
+        assert!(matches!(poll_once(&mut pings), Poll::Ready(Some(Ping(1)))));
+    });
+}
+
+#[test]
+fn subclass_signal_stream_rejects_a_signal_the_type_doesnt_have() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let Err(error) = subclass_signal_stream::<Ping>(&emitter, "pong") else {
+            panic!("`pong` isn't a signal of the emitter");
+        };
+        assert_eq!(error.type_, Emitter::static_type());
+        assert_eq!(error.signal, "pong");
+        assert!(!has_handlers(&emitter, "ping"));
+
+        // Registered by another type.
+        let object = glib::Object::new::<glib::Object>();
+        assert!(subclass_signal_stream::<Ping>(&object, "ping").is_err());
+    });
+}
+
+#[test]
+fn try_ev_stream_reports_an_unknown_signal_instead_of_connecting() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let Err(err) = try_ev_stream!(emitter, "pnig", |args| args.len()) else {
+            panic!("connected to a signal which doesn't exist");
+        };
+        assert_eq!(
+            err.to_string(),
+            "type `EvStreamTestEmitter` has no signal `pnig`"
+        );
+        assert_eq!(err.type_, Emitter::static_type());
+        assert!(check_signal(&emitter, "ping").is_ok());
+        assert!(check_signal(&emitter, "notify::label").is_ok());
+        assert!(check_signal(&emitter, "pnig").is_err());
+        assert!(!has_handlers(&emitter, "ping"));
+
+        let mut pings =
+            try_ev_stream!(emitter, "ping", |args| args[1].get::<u32>().unwrap()).unwrap();
+        let mut labels = try_ev_stream!(emitter, "notify::label", |args| args.len()).unwrap();
+        emitter.ping(1);
+        emitter.set_property("label", "a");
+        emitter.set_property("value", 1);
+        assert_eq!(poll_once(&mut pings), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut labels), Poll::Ready(Some(2)));
+        assert_eq!(poll_once(&mut labels), Poll::Pending);
+    });
+}
+
+#[test]
+fn destroyed_resolves_once_the_last_reference_is_dropped() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let other_ref = emitter.clone();
+        let mut finalized = Box::pin(destroyed(&emitter));
+        assert_eq!(poll_future(&mut finalized), Poll::Pending);
+        drop(emitter);
+        assert_eq!(poll_future(&mut finalized), Poll::Pending);
+        drop(other_ref);
+        assert_eq!(poll_future(&mut finalized), Poll::Ready(()));
+
+        // Dropped before the object: its callback is removed.
+        let emitter = Emitter::new();
+        drop(destroyed(&emitter));
+        drop(emitter);
+    });
+}
+
+#[test]
+fn raw_signal_stream_yields_every_argument_of_a_multi_argument_signal() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut raw = raw_signal_stream(&emitter, "moved", false);
+        let mut untyped = ev_stream!(emitter, "moved", |args|);
+        emitter.moved(-3, "left");
+        let Poll::Ready(Some(args)) = poll_once(&mut raw) else {
+            panic!("expected the arguments");
+        };
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[0].get::<Emitter>().unwrap(), emitter);
+        assert_eq!(args[1].get::<i32>().unwrap(), -3);
+        assert_eq!(args[2].get::<String>().unwrap(), "left");
+        let Poll::Ready(Some(same)) = poll_once(&mut untyped) else {
+            panic!("expected the arguments");
+        };
+        assert_eq!(same[2].get::<String>().unwrap(), "left");
+    });
+}
+
+#[test]
+fn raw_signal_stream_connects_after_the_other_handlers_if_asked() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let before = Rc::new(RefCell::new(raw_signal_stream(&emitter, "ping", false)));
+        let after = Rc::new(RefCell::new(raw_signal_stream(&emitter, "ping", true)));
+        // What each stream had buffered when a handler connected after both of them ran.
+        let buffered = Rc::new(Cell::new((0, 0)));
+        emitter.connect_ping({
+            let (before, after, buffered) = (before.clone(), after.clone(), buffered.clone());
+            move |_, _| {
+                let len = |stream: &RefCell<EvStream<_>>| stream.borrow().size_hint().0;
+                buffered.set((len(&before), len(&after)));
+            }
+        });
+        emitter.ping(1);
+        assert_eq!(buffered.get(), (1, 0));
+        assert_eq!(after.borrow_mut().drain_now().len(), 1);
+    });
+}
+
+#[test]
+fn property_stream_emit_on_rebind_yields_the_value_of_the_new_object() {
+    with_context(|_| {
+        let (first, second) = (Emitter::new(), Emitter::new());
+        first.set_property("value", 1);
+        second.set_property("value", 7);
+        let mut values = property_stream_emit_on_rebind::<i32>(&first, "value");
+        // Nothing when first connecting.
+        assert_eq!(values.try_recv(), Ok(None));
+        first.set_property("value", 2);
+
+        values.rebind(&second).unwrap();
+        assert_eq!(values.drain_now(), [2, 7]);
+        second.set_property("value", 8);
+        first.set_property("value", 3);
+        assert_eq!(values.drain_now(), [8]);
+    });
+}
+
+#[test]
+fn property_stream_doesnt_emit_on_rebind() {
+    with_context(|_| {
+        let (first, second) = (Emitter::new(), Emitter::new());
+        second.set_property("value", 7);
+        let mut values = property_stream::<i32>(&first, "value");
+        values.rebind(&second).unwrap();
+        assert_eq!(values.try_recv(), Ok(None));
+        second.set_property("value", 8);
+        assert_eq!(values.drain_now(), [8]);
+    });
+}
+
+#[test]
+fn properties_stream_only_yields_the_watched_properties() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut changes = properties_stream(&emitter, &["value"]);
+        emitter.set_property("label", "skipped");
+        emitter.set_property("value", 3);
+        let changes = changes.drain_now();
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.get::<i32>().unwrap()))
+            .collect();
+        assert_eq!(changes, [("value", 3)]);
+    });
+}
+
+#[test]
+fn properties_stream_watches_several_properties_with_one_handler() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut changes = properties_stream(&emitter, &["value", "label"]);
+        assert!(has_handlers(&emitter, "notify"));
+        emitter.set_property("value", 3);
+        emitter.set_property("label", "hi");
+        let names: Vec<_> = changes
+            .drain_now()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["value", "label"]);
+        drop(changes);
+        assert!(!has_handlers(&emitter, "notify"));
+    });
+}
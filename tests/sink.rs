@@ -0,0 +1,47 @@
+mod common;
+
+use common::{block_on, with_context, Emitter};
+use ev_stream_gtk_rs::{emit_stream, ev_stream, property_sink, property_stream};
+use futures::{stream, SinkExt, StreamExt};
+use glib::prelude::*;
+
+#[test]
+fn emitted_values_round_trip_through_a_stream_of_the_same_signal() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        let mut sink = emit_stream(&emitter, "ping");
+        block_on(
+            stream::iter([1u32, 2, 3])
+                .map(|n| Ok(vec![n.to_value()]))
+                .forward(&mut sink),
+        )
+        .unwrap();
+        assert_eq!(pings.drain_now(), [1, 2, 3]);
+    });
+}
+
+#[test]
+fn sending_to_a_finalized_object_is_ignored() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut sink = emit_stream(&emitter, "ping");
+        drop(emitter);
+        block_on(sink.send(vec![1u32.to_value()])).unwrap();
+    });
+}
+
+#[test]
+fn a_value_round_trips_through_property_sink_and_property_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut values = property_stream::<i32>(&emitter, "value");
+        let mut sink = property_sink::<i32>(&emitter, "value");
+        block_on(sink.send(5)).unwrap();
+        assert_eq!(emitter.property::<i32>("value"), 5);
+        assert_eq!(values.drain_now(), [5]);
+
+        drop(emitter);
+        block_on(sink.send(6)).unwrap();
+    });
+}
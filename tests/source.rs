@@ -0,0 +1,90 @@
+mod common;
+
+use common::{poll_once, with_context};
+use ev_stream_gtk_rs::{source_stream, RawCallback, SignalSource};
+use glib::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::task::Poll;
+
+/// An in-memory source, calling the callbacks of a signal in the order they connected.
+#[derive(Default)]
+struct MockSource {
+    next_id: Cell<u32>,
+    callbacks: RefCell<Vec<(u32, String, Rc<RawCallback>)>>,
+}
+
+impl MockSource {
+    fn emit(&self, name: &str, args: &[glib::Value]) {
+        let callbacks: Vec<_> = self
+            .callbacks
+            .borrow()
+            .iter()
+            .filter(|(_, signal, _)| signal == name)
+            .map(|(_, _, callback)| callback.clone())
+            .collect();
+        for callback in callbacks {
+            callback(args);
+        }
+    }
+
+    fn connected(&self) -> usize {
+        self.callbacks.borrow().len()
+    }
+}
+
+impl SignalSource for MockSource {
+    type HandlerId = u32;
+
+    fn connect_raw(&self, name: &str, callback: RawCallback) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.callbacks
+            .borrow_mut()
+            .push((id, name.to_owned(), Rc::new(callback)));
+        id
+    }
+
+    fn disconnect_raw(&self, id: u32) {
+        self.callbacks
+            .borrow_mut()
+            .retain(|(other, ..)| *other != id);
+    }
+}
+
+#[test]
+fn source_stream_yields_the_emissions_of_a_mock_source() {
+    with_context(|_| {
+        let source = Rc::new(MockSource::default());
+        let mut values = source_stream(&source, "changed", |args| args[0].get::<u32>().unwrap());
+        source.emit("changed", &[1u32.to_value()]);
+        source.emit("other", &[2u32.to_value()]);
+        source.emit("changed", &[3u32.to_value()]);
+        assert_eq!(values.drain_now(), [1, 3]);
+        assert!(values.is_connected());
+        assert_eq!(values.object(), None);
+    });
+}
+
+#[test]
+fn source_stream_disconnects_from_the_source_on_drop() {
+    with_context(|_| {
+        let source = Rc::new(MockSource::default());
+        let values = source_stream(&source, "changed", |_| ());
+        assert_eq!(source.connected(), 1);
+        drop(values);
+        assert_eq!(source.connected(), 0);
+    });
+}
+
+#[test]
+fn source_stream_ends_once_the_source_is_dropped() {
+    with_context(|_| {
+        let source = Rc::new(MockSource::default());
+        let mut values = source_stream(&source, "changed", |args| args[0].get::<u32>().unwrap());
+        source.emit("changed", &[1u32.to_value()]);
+        drop(source);
+        assert_eq!(poll_once(&mut values), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut values), Poll::Ready(None));
+    });
+}
@@ -0,0 +1,902 @@
+mod common;
+
+use common::{
+    block_on, find_handler, flush, handler_connected, has_handlers, poll_future, poll_once,
+    with_context, Emitter,
+};
+use ev_stream_gtk_rs::{
+    channel, ev_stream, ev_stream_with_id, mpsc, BoxedEvStream, EvStream, Overflow, RebindError,
+    TryRecvError,
+};
+use futures::StreamExt;
+use glib::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+#[test]
+fn next_timeout_returns_an_item_emitted_while_waiting() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n);
+        ctx.spawn_local({
+            let emitter = emitter.clone();
+            async move {
+                glib::timeout_future(Duration::from_millis(1)).await;
+                emitter.ping(7);
+            }
+        });
+        assert_eq!(stream.next_timeout(Duration::from_secs(5)), Some(7));
+    });
+}
+
+#[test]
+fn next_timeout_returns_none_once_the_duration_elapsed() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n);
+        let start = Instant::now();
+        assert_eq!(stream.next_timeout(Duration::from_millis(10)), None);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        // The stream is still usable afterwards.
+        emitter.ping(1);
+        assert_eq!(stream.next_timeout(Duration::from_millis(10)), Some(1));
+    });
+}
+
+#[test]
+fn reentrant_emissions_from_the_consumer_are_delivered_in_order() {
+    let emitter = Emitter::new();
+    let stream = ev_stream!(emitter, ping, |_, n| n);
+    let mut seen = Vec::new();
+    emitter.ping(1);
+    block_on(stream.take(5).for_each(|n| {
+        seen.push(n);
+        // Re-emits synchronously while the item is being processed.
+        if n == 1 {
+            emitter.ping(2);
+            emitter.ping(3);
+        } else if n == 2 {
+            emitter.ping(4);
+        } else if n == 3 {
+            emitter.ping(5);
+        }
+        std::future::ready(())
+    }));
+    assert_eq!(seen, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn last_send_dropped_follows_the_overflows_of_a_bounded_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).bounded(2, Overflow::DropNewest);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert!(!stream.last_send_dropped());
+        emitter.ping(3);
+        assert!(stream.last_send_dropped());
+        assert_eq!(stream.dropped_count(), 1);
+        assert_eq!(stream.drain_now(), [1, 2]);
+        emitter.ping(4);
+        assert!(!stream.last_send_dropped());
+        assert_eq!(stream.dropped_count(), 1);
+        assert_eq!(stream.drain_now(), [4]);
+    });
+}
+
+#[test]
+fn drop_oldest_makes_room_and_counts_as_a_dropped_send() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).bounded(2, Overflow::DropOldest);
+        for n in 1..=4 {
+            emitter.ping(n);
+        }
+        assert!(stream.last_send_dropped());
+        assert_eq!(stream.dropped_count(), 2);
+        assert_eq!(stream.drain_now(), [3, 4]);
+    });
+}
+
+#[test]
+fn new_still_accepts_an_mpsc_receiver() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (s, r) = mpsc::unbounded();
+        let signal_id = emitter.connect_ping(move |_, n| {
+            let _ = s.unbounded_send(n);
+        });
+        let mut stream = ev_stream_gtk_rs::EvStream::new(
+            emitter.upcast_ref::<glib::Object>().downgrade(),
+            signal_id,
+            r,
+        );
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        assert_eq!(stream.try_recv(), Ok(Some(2)));
+        assert_eq!(stream.try_recv(), Ok(None));
+
+        // The bound applies to the forwarded items too.
+        let mut stream = stream.latest_only();
+        for n in 3..=5 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(5)));
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+
+        drop(stream);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn a_forwarded_mpsc_channel_ends_the_stream_once_its_senders_are_gone() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (s, r) = mpsc::unbounded();
+        let signal_id = emitter.connect_ping(|_, _| ());
+        let mut stream = ev_stream_gtk_rs::EvStream::new(
+            emitter.upcast_ref::<glib::Object>().downgrade(),
+            signal_id,
+            r,
+        );
+        s.unbounded_send(1).unwrap();
+        drop(s);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn control_methods_still_work_after_boxing() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut boxed: BoxedEvStream<u32> = ev_stream!(emitter, ping, |_, n| n).boxed();
+        assert!(boxed.is_connected());
+        assert_eq!(boxed.object(), Some(emitter.clone().upcast()));
+
+        emitter.ping(1);
+        boxed.disconnect();
+        assert!(!boxed.is_connected());
+        assert!(!has_handlers(&emitter, "ping"));
+        // The items buffered before are still delivered.
+        assert_eq!(poll_once(&mut boxed), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut boxed), Poll::Ready(None));
+
+        let boxed = ev_stream!(emitter, ping, |_, n| n).boxed();
+        drop(emitter);
+        assert!(!boxed.is_connected());
+        assert_eq!(boxed.object(), None);
+    });
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the receiver is already closed")]
+fn new_catches_an_already_closed_receiver() {
+    let emitter = Emitter::new();
+    let (s, r) = channel::channel::<u32>();
+    drop(s);
+    let signal_id = emitter.connect_ping(|_, _| ());
+    let _ = EvStream::new(
+        emitter.upcast_ref::<glib::Object>().downgrade(),
+        signal_id,
+        r,
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the signal handler isn't connected to the object")]
+fn new_catches_a_handler_of_another_object() {
+    let emitter = Emitter::new();
+    let other = Emitter::new();
+    let (_s, r) = channel::channel::<u32>();
+    let signal_id = other.connect_ping(|_, _| ());
+    let _ = EvStream::new(
+        emitter.upcast_ref::<glib::Object>().downgrade(),
+        signal_id,
+        r,
+    );
+}
+
+#[test]
+fn lifecycle_calls_overlapping_an_emission_or_a_teardown_dont_panic() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        let token = stream.token();
+        // Runs right after the handler of the stream, during the same emission.
+        emitter.connect_ping(move |emitter, n| {
+            assert!(token.is_connected());
+            if n == 1 {
+                emitter.ping(2);
+            }
+        });
+        let mut stream = stream.on_disconnect({
+            let emitter = emitter.clone();
+            move || emitter.ping(3)
+        });
+        emitter.ping(1);
+        assert_eq!(stream.drain_now(), [1, 2]);
+        assert!(stream.is_connected());
+
+        // The callback emits while the stream is being disconnected.
+        stream.disconnect();
+        assert!(!stream.is_connected());
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+/// A stream of `emitter` counting the runs of its `on_disconnect` callback.
+fn counted(emitter: &Emitter) -> (EvStream<u32>, Rc<Cell<u32>>) {
+    let runs = Rc::new(Cell::new(0));
+    let stream = ev_stream!(emitter, ping, |_, n| n).on_disconnect({
+        let runs = runs.clone();
+        move || runs.set(runs.get() + 1)
+    });
+    (stream, runs)
+}
+
+#[test]
+fn on_disconnect_runs_once_on_an_explicit_disconnect() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (stream, runs) = counted(&emitter);
+        stream.disconnect();
+        assert_eq!(runs.get(), 1);
+        stream.disconnect();
+        drop(stream);
+        assert_eq!(runs.get(), 1);
+    });
+}
+
+#[test]
+fn on_disconnect_runs_once_on_drop() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (stream, runs) = counted(&emitter);
+        assert_eq!(runs.get(), 0);
+        drop(stream);
+        assert_eq!(runs.get(), 1);
+    });
+}
+
+#[test]
+fn on_disconnect_runs_once_on_finalization() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (stream, runs) = counted(&emitter);
+        drop(emitter);
+        assert_eq!(runs.get(), 1);
+        stream.disconnect();
+        drop(stream);
+        assert_eq!(runs.get(), 1);
+    });
+}
+
+#[test]
+fn on_disconnect_runs_right_away_on_a_disconnected_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        stream.disconnect();
+        let (_stream, runs) = {
+            let runs = Rc::new(Cell::new(0));
+            let counter = runs.clone();
+            (
+                stream.on_disconnect(move || counter.set(counter.get() + 1)),
+                runs,
+            )
+        };
+        assert_eq!(runs.get(), 1);
+    });
+}
+
+#[test]
+fn a_stream_dropped_during_an_emission_of_its_signal_is_disconnected_afterwards() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let owner: Rc<RefCell<Option<EvStream<u32>>>> = Rc::default();
+        // Frees the stream from inside a handler of the very signal it's subscribed to.
+        let freeing = emitter.connect_ping({
+            let owner = owner.clone();
+            move |_, _| drop(owner.borrow_mut().take())
+        });
+        *owner.borrow_mut() = Some(ev_stream!(emitter, ping, |_, n| n));
+        emitter.ping(1);
+        assert!(owner.borrow().is_none());
+        emitter.disconnect(freeing);
+
+        // The handler of the stream was blocked, and is only disconnected once idle.
+        assert!(has_handlers(&emitter, "ping"));
+        emitter.ping(2);
+        flush(ctx);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn the_deferred_disconnection_runs_on_the_thread_default_context() {
+    // The global default context is owned by another thread, like a worker next to the
+    // gtk main loop.
+    let default = glib::MainContext::default();
+    let _owner = default.acquire().unwrap();
+    std::thread::spawn(|| {
+        with_context(|ctx| {
+            let emitter = Emitter::new();
+            let owner: Rc<RefCell<Option<EvStream<u32>>>> = Rc::default();
+            let freeing = emitter.connect_ping({
+                let owner = owner.clone();
+                move |_, _| drop(owner.borrow_mut().take())
+            });
+            *owner.borrow_mut() = Some(ev_stream!(emitter, ping, |_, n| n));
+            emitter.ping(1);
+            emitter.disconnect(freeing);
+            assert!(has_handlers(&emitter, "ping"));
+            flush(ctx);
+            assert!(!has_handlers(&emitter, "ping"));
+        })
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn with_object_pairs_the_items_with_none_once_the_object_is_gone() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).with_object();
+        emitter.ping(1);
+        assert_eq!(
+            poll_once(&mut stream),
+            Poll::Ready(Some((Some(emitter.clone().upcast()), 1)))
+        );
+
+        emitter.ping(2);
+        drop(emitter);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some((None, 2))));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn capacity_is_the_bound_of_the_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        assert_eq!(stream.capacity(), None);
+        let stream = stream.bounded(4, Overflow::DropNewest);
+        assert_eq!(stream.capacity(), Some(4));
+        assert_eq!(stream.latest_only().capacity(), Some(1));
+    });
+}
+
+#[test]
+fn a_stale_stream_leaves_the_objects_allocated_after_its_own_alone() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        let address = emitter.as_ptr();
+        drop(emitter);
+        // Likely to reuse the address of the finalized object.
+        let others: Vec<_> = (0..16)
+            .map(|_| {
+                let other = Emitter::new();
+                other.connect_ping(|_, _| ());
+                other
+            })
+            .collect();
+        let reused = others.iter().any(|other| other.as_ptr() == address);
+
+        assert!(!stream.is_connected());
+        assert_eq!(stream.object(), None);
+        stream.disconnect();
+        drop(stream);
+        for other in &others {
+            assert!(has_handlers(other, "ping"), "reused address: {reused}");
+        }
+    });
+}
+
+#[test]
+fn aborting_disconnects_the_handler_and_ends_the_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (mut stream, handle) = ev_stream!(emitter, ping, |_, n| n).abortable();
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+
+        emitter.ping(2);
+        handle.abort();
+        assert!(handle.is_aborted());
+        // The stream is still alive, its handler isn't.
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn disconnected_resolves_on_every_teardown_route() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        let mut disconnected = Box::pin(stream.disconnected());
+        assert!(poll_future(&mut disconnected).is_pending());
+        stream.disconnect();
+        assert!(poll_future(&mut disconnected).is_ready());
+
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        let mut disconnected = Box::pin(stream.disconnected());
+        drop(stream);
+        assert!(poll_future(&mut disconnected).is_ready());
+
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        let mut disconnected = Box::pin(stream.disconnected());
+        assert!(poll_future(&mut disconnected).is_pending());
+        drop(emitter);
+        assert!(poll_future(&mut disconnected).is_ready());
+
+        // Already disconnected.
+        assert!(poll_future(&mut Box::pin(stream.disconnected())).is_ready());
+    });
+}
+
+#[test]
+fn handler_token_follows_the_connection_of_its_stream() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (mut pings, token) = ev_stream_with_id!(emitter, ping, |_, n| n);
+        let copy = token.clone();
+        assert!(token.is_connected() && copy.is_connected());
+
+        copy.block();
+        emitter.ping(1);
+        token.unblock();
+        emitter.ping(2);
+        assert_eq!(pings.drain_now(), [2]);
+
+        pings.disconnect();
+        assert!(!token.is_connected() && !copy.is_connected());
+        drop(pings);
+        assert!(!token.is_connected());
+        // Does nothing once the stream is gone.
+        token.block();
+        token.unblock();
+
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        let token = pings.token();
+        drop(pings);
+        assert!(!token.is_connected());
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+/// Whether `T` is `Send`: the inherent constant only exists when it is, otherwise the
+/// one of the blanket trait impl is picked.
+struct IsSend<T>(std::marker::PhantomData<T>);
+
+trait NotSend {
+    const SEND: bool = false;
+}
+
+impl<T> NotSend for IsSend<T> {}
+
+impl<T: Send> IsSend<T> {
+    const SEND: bool = true;
+}
+
+#[test]
+fn ev_stream_is_not_send_even_for_send_items() {
+    const { assert!(IsSend::<u32>::SEND) };
+    const { assert!(!IsSend::<EvStream<u32>>::SEND) };
+    const { assert!(!IsSend::<EvStream<String>>::SEND) };
+    const { assert!(!IsSend::<ev_stream_gtk_rs::Debounce<EvStream<u32>>>::SEND) };
+    const { assert!(!IsSend::<ev_stream_gtk_rs::HandlerToken>::SEND) };
+}
+
+#[test]
+fn every_stream_gets_its_own_id_across_threads() {
+    let ids = || {
+        with_context(|_| {
+            let emitter = Emitter::new();
+            let a = ev_stream!(emitter, ping, |_, n| n);
+            let b = ev_stream!(emitter, ping, |_, n| n);
+            let id = a.id();
+            // Kept by the configuration methods.
+            assert_eq!(a.latest_only().id(), id);
+            [id, b.id()]
+        })
+    };
+    let here = ids();
+    let there = std::thread::spawn(ids).join().unwrap();
+    let mut all = [here, there].concat();
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(all.len(), 4, "{here:?} {there:?}");
+}
+
+#[test]
+fn try_recv_reports_empty_item_and_disconnected_without_iterating() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        assert_eq!(pings.try_recv(), Ok(None));
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(pings.try_recv(), Ok(Some(1)));
+        assert_eq!(pings.try_recv(), Ok(Some(2)));
+        assert_eq!(pings.try_recv(), Ok(None));
+        assert!(!ctx.pending());
+
+        // The buffered items first, then the end, for good.
+        emitter.ping(3);
+        drop(emitter);
+        assert_eq!(pings.try_recv(), Ok(Some(3)));
+        assert_eq!(pings.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(pings.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(poll_once(&mut pings), Poll::Ready(None));
+
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        pings.disconnect();
+        assert_eq!(pings.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
+
+/// A stream of `emitter` whose items are sent through the returned sender, outside of a
+/// signal handler, so that a panicking send can be caught.
+fn with_sender(emitter: &Emitter) -> (channel::Sender<u32>, EvStream<u32>) {
+    let (s, r) = channel::channel();
+    let stream = EvStream::new(
+        emitter.upcast_ref::<glib::Object>().downgrade(),
+        emitter.connect_ping(|_, _| ()),
+        r,
+    );
+    (s, stream)
+}
+
+#[test]
+fn a_send_after_the_stream_was_dropped_fails_silently_by_default() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (s, stream) = with_sender(&emitter);
+        assert!(s.send(1).is_ok());
+        drop(stream);
+        assert!(s.send(2).is_err());
+    });
+}
+
+#[test]
+#[cfg(not(feature = "catch-send-panic"))]
+#[should_panic(expected = "item of type `u32` sent after its stream was dropped")]
+fn a_strict_stream_panics_on_a_send_after_it_was_dropped() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let (s, stream) = with_sender(&emitter);
+        let stream = stream.strict();
+        assert!(s.send(1).is_ok());
+        drop(stream);
+        let _ = s.send(2);
+    });
+}
+
+#[test]
+fn into_raw_parts_hands_the_handler_over_to_the_caller() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let Ok((object, signal_ids, mut receiver)) =
+            ev_stream!(emitter, ping, |_, n| n).into_raw_parts()
+        else {
+            panic!("the stream was made of a signal handler");
+        };
+        assert_eq!(object, emitter.clone().upcast::<glib::Object>());
+        assert_eq!(signal_ids.len(), 1);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut receiver), Poll::Ready(Some(1)));
+
+        // The caller can still disconnect the handler itself.
+        for signal_id in signal_ids {
+            object.disconnect(signal_id);
+        }
+        drop(receiver);
+        emitter.ping(2);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn a_handler_given_away_disconnects_itself_once_its_receiver_is_gone() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let stream = ev_stream!(emitter, ping, |_, n| {
+            counter.set(counter.get() + 1);
+            n
+        });
+        let Ok((_object, signal_ids, receiver)) = stream.into_raw_parts() else {
+            panic!("the stream was made of a signal handler");
+        };
+        let raw_id = unsafe { signal_ids[0].as_raw() };
+
+        // Still connected once the receiver is gone, until the next emission.
+        drop(receiver);
+        assert!(handler_connected(&emitter, raw_id));
+        emitter.ping(1);
+        assert!(!handler_connected(&emitter, raw_id));
+        assert_eq!(calls.get(), 1);
+        emitter.ping(2);
+        assert_eq!(calls.get(), 1);
+    });
+}
+
+#[test]
+fn into_raw_parts_gives_the_stream_back_once_the_object_is_gone() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let stream = ev_stream!(emitter, ping, |_, n| n);
+        emitter.ping(1);
+        drop(emitter);
+        let Err(mut stream) = stream.into_raw_parts() else {
+            panic!("split a stream whose object is gone");
+        };
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn connected_object_type_doesnt_keep_the_object_alive() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        assert_eq!(pings.connected_object_type(), Some(Emitter::static_type()));
+        assert_eq!(emitter.ref_count(), 1);
+        drop(emitter);
+        assert_eq!(pings.connected_object_type(), None);
+    });
+}
+
+#[test]
+fn select_skips_the_stream_only_once_it_is_disconnected_and_drained() {
+    use futures::stream::FusedStream;
+
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        assert!(!pings.is_terminated());
+        emitter.ping(1);
+        emitter.ping(2);
+        pings.disconnect();
+        // Disconnected, with two items still buffered.
+        assert!(!pings.is_terminated());
+
+        let mut others = futures::stream::iter([10, 20]).fuse();
+        let mut seen = Vec::new();
+        block_on(async {
+            loop {
+                futures::select_biased! {
+                    n = pings.next() => seen.push((n, pings.is_terminated())),
+                    n = others.next() => seen.push((n, pings.is_terminated())),
+                    complete => break,
+                }
+            }
+        });
+        assert_eq!(
+            seen,
+            [
+                (Some(1), false),
+                (Some(2), false),
+                (None, true),
+                // Skipped from then on.
+                (Some(10), true),
+                (Some(20), true),
+                (None, true),
+            ]
+        );
+    });
+}
+
+/// A rebindable stream of the pings of `emitter`.
+fn rebindable_pings(emitter: &Emitter) -> EvStream<u32> {
+    EvStream::rebindable(emitter, |obj, s| {
+        obj.downcast_ref::<Emitter>()
+            .unwrap()
+            .connect_ping(move |_, n| {
+                let _ = s.send(n);
+            })
+    })
+}
+
+#[test]
+fn rebind_moves_the_subscription_to_the_new_object() {
+    with_context(|_| {
+        let (first, second) = (Emitter::new(), Emitter::new());
+        let mut pings = rebindable_pings(&first);
+        first.ping(1);
+        assert_eq!(pings.rebind(&second), Ok(()));
+        assert!(!has_handlers(&first, "ping"));
+        assert!(has_handlers(&second, "ping"));
+
+        first.ping(2);
+        second.ping(3);
+        // The item buffered before the rebind is kept.
+        assert_eq!(pings.drain_now(), [1, 3]);
+    });
+}
+
+#[test]
+fn rebind_fails_on_a_stream_not_rebindable_or_disconnected() {
+    with_context(|_| {
+        let (first, second) = (Emitter::new(), Emitter::new());
+        let pings = ev_stream!(first, ping, |_, n| n);
+        assert_eq!(pings.rebind(&second), Err(RebindError::NotRebindable));
+
+        let pings = rebindable_pings(&first);
+        pings.disconnect();
+        assert_eq!(pings.rebind(&second), Err(RebindError::Disconnected));
+        assert!(!has_handlers(&second, "ping"));
+    });
+}
+
+#[test]
+fn weak_source_upgrades_to_the_object_until_it_is_finalized() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        let weak = pings.weak_source();
+        assert_eq!(
+            weak.upgrade(),
+            Some(emitter.clone().upcast::<glib::Object>())
+        );
+        drop(emitter);
+        assert_eq!(weak.upgrade(), None);
+        assert_eq!(pings.weak_source().upgrade(), None);
+    });
+}
+
+#[test]
+fn drain_now_takes_the_buffered_items_and_stays_connected() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        assert!(pings.drain_now().is_empty());
+        emitter.ping(1);
+        emitter.ping(2);
+        // A task on the main context isn't run by it.
+        ctx.spawn_local({
+            let emitter = emitter.clone();
+            async move { emitter.ping(3) }
+        });
+        assert_eq!(pings.drain_now(), [1, 2]);
+        assert!(pings.is_connected());
+        flush(ctx);
+        emitter.ping(4);
+        assert_eq!(pings.drain_now(), [3, 4]);
+    });
+}
+
+#[test]
+fn map_while_ends_and_disconnects_at_the_first_none() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut until_zero =
+            ev_stream!(emitter, ping, |_, n| n).map_while(|n| (n != 0).then(|| n * 10));
+        emitter.ping(1);
+        emitter.ping(2);
+        emitter.ping(0);
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut until_zero), Poll::Ready(Some(10)));
+        assert_eq!(poll_once(&mut until_zero), Poll::Ready(Some(20)));
+        assert!(has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut until_zero), Poll::Ready(None));
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(poll_once(&mut until_zero), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn first_resolves_with_the_first_item_and_disconnects() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut first = Box::pin(ev_stream!(emitter, ping, |_, n| n).first());
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_future(&mut first), Poll::Ready(Some(1)));
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn first_resolves_to_none_once_the_object_is_finalized() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut first = Box::pin(ev_stream!(emitter, ping, |_, n| n).first());
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+        drop(emitter);
+        assert_eq!(poll_future(&mut first), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn recv_many_moves_at_most_max_items_into_the_reused_buffer() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let mut pings = ev_stream!(emitter, ping, |_, n| n);
+        for n in 1..=5 {
+            emitter.ping(n);
+        }
+        let mut buf = Vec::with_capacity(8);
+        let allocation = buf.as_ptr();
+        assert_eq!(pings.recv_many(3, &mut buf), 3);
+        assert_eq!(buf, [1, 2, 3]);
+
+        // Appended to what the buffer holds, reusing its allocation once cleared.
+        assert_eq!(pings.recv_many(3, &mut buf), 2);
+        assert_eq!(buf, [1, 2, 3, 4, 5]);
+        buf.clear();
+        emitter.ping(6);
+        assert_eq!(pings.recv_many(3, &mut buf), 1);
+        assert_eq!(buf, [6]);
+        assert_eq!(buf.as_ptr(), allocation);
+        assert_eq!(pings.recv_many(3, &mut buf), 0);
+        assert!(pings.is_connected());
+    });
+}
+
+/// A subclass of [Emitter], whose handlers get disconnected through the upcast `Object`
+/// the streams keep.
+mod sub {
+    use super::Emitter;
+    use glib::subclass::prelude::*;
+
+    pub trait EmitterImpl: ObjectImpl {}
+
+    unsafe impl<T: EmitterImpl> IsSubclassable<T> for Emitter {}
+
+    mod imp {
+        use super::*;
+
+        #[derive(Default)]
+        pub struct SubEmitter;
+
+        #[glib::object_subclass]
+        impl ObjectSubclass for SubEmitter {
+            const NAME: &'static str = "EvStreamTestSubEmitter";
+            type Type = super::SubEmitter;
+            type ParentType = Emitter;
+        }
+
+        impl ObjectImpl for SubEmitter {}
+        impl EmitterImpl for SubEmitter {}
+    }
+
+    glib::wrapper! {
+        pub struct SubEmitter(ObjectSubclass<imp::SubEmitter>) @extends Emitter;
+    }
+}
+
+#[test]
+fn a_handler_connected_on_a_subclass_is_removed_through_the_upcast_object() {
+    with_context(|_| {
+        let sub: sub::SubEmitter = glib::Object::new();
+        let mut pings = ev_stream!(sub, "ping", |args| args[1].get::<u32>().unwrap());
+        assert_eq!(
+            pings.connected_object_type(),
+            Some(sub::SubEmitter::static_type())
+        );
+        let raw_id = find_handler(&sub, "ping").expect("no handler connected");
+        sub.upcast_ref::<Emitter>().ping(1);
+        assert_eq!(pings.drain_now(), [1]);
+
+        drop(pings);
+        assert!(!handler_connected(&sub, raw_id));
+        assert_eq!(find_handler(&sub, "ping"), None);
+    });
+}
@@ -0,0 +1,210 @@
+//! Cancelling the task consuming a stream, for every combinator: the signal handlers, the
+//! timers and the glib sources must all be released with it. With the `manual-clock`
+//! feature, to see the timers.
+mod common;
+
+use common::{flush, has_handlers, poll_future, with_context, Emitter};
+use ev_stream_gtk_rs::{
+    assert_disconnected_on_drop, ev_stream, merge, EvStream, EvStreamExt, ManualClock,
+};
+use futures::stream::{self, select};
+use futures::{FutureExt, Stream, StreamExt};
+use glib::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::task::Poll;
+use std::time::Duration;
+
+const WINDOW: Duration = Duration::from_millis(25);
+
+/// Consumes the stream built by `combinator` and emits `pings` while it does, expecting
+/// `busy` timers and glib sources pending then, and drops the consuming future mid-way:
+/// the signal handler, the timers and the sources must all be gone right away.
+fn assert_released_on_cancel<S, F>(pings: &[u32], busy: usize, combinator: F)
+where
+    S: Stream + 'static,
+    F: FnOnce(EvStream<u32>) -> S,
+{
+    with_context(|ctx| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut consumer = Box::pin(combinator(ev_stream!(emitter, ping, |_, n| n)).count());
+        assert!(poll_future(&mut consumer).is_pending());
+        for &n in pings {
+            emitter.ping(n);
+            assert!(poll_future(&mut consumer).is_pending());
+        }
+        assert!(
+            has_handlers(&emitter, "ping"),
+            "ended before the cancellation"
+        );
+        assert_eq!(clock.pending_timers() + usize::from(ctx.pending()), busy);
+
+        drop(consumer);
+        assert!(
+            !has_handlers(&emitter, "ping"),
+            "a signal handler outlived the task"
+        );
+        assert_eq!(clock.pending_timers(), 0, "a timer outlived the task");
+        assert!(!ctx.pending(), "a glib source outlived the task");
+    });
+}
+
+#[test]
+fn the_base_stream_disconnects_on_drop() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        assert_disconnected_on_drop!(emitter, ev_stream!(emitter, ping, |_, n| n));
+    });
+}
+
+#[test]
+#[should_panic(expected = "the stream isn't connected to this object")]
+fn assert_disconnected_on_drop_rejects_another_object() {
+    with_context(|_| {
+        let (emitter, other) = (Emitter::new(), Emitter::new());
+        assert_disconnected_on_drop!(other, ev_stream!(emitter, ping, |_, n| n));
+    });
+}
+
+#[test]
+#[should_panic(expected = "the stream has no signal handler connected")]
+fn assert_disconnected_on_drop_rejects_a_stream_without_handlers() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        pings.disconnect();
+        assert_disconnected_on_drop!(emitter, pings);
+    });
+}
+
+#[test]
+fn timer_combinators() {
+    assert_released_on_cancel(&[1], 1, |s| s.debounce(WINDOW));
+    assert_released_on_cancel(&[1], 1, |s| s.throttle(WINDOW));
+    assert_released_on_cancel(&[1, 2], 1, |s| s.throttle_latest(WINDOW));
+    assert_released_on_cancel(&[1], 1, |s| s.throttle_opts(WINDOW, false, true));
+    assert_released_on_cancel(&[1], 1, |s| s.delay_each(WINDOW));
+    assert_released_on_cancel(&[1], 1, |s| s.chunks_timeout(4, WINDOW));
+    assert_released_on_cancel(&[1], 1, |s| s.settle_marker(WINDOW));
+    assert_released_on_cancel(&[], 1, |s| s.end_after(WINDOW));
+    // The deadline of the item being processed.
+    assert_released_on_cancel(&[1], 1, |s| {
+        s.with_deadline(WINDOW)
+            .then(|(_, deadline)| deadline.map(|_| ()))
+    });
+}
+
+#[test]
+fn idle_combinators() {
+    assert_released_on_cancel(&[1], 1, |s| s.batch_per_iteration());
+}
+
+#[test]
+fn stateful_combinators() {
+    assert_released_on_cancel(&[1, 1, 2], 0, |s| s.distinct_until_changed());
+    assert_released_on_cancel(&[1, 1], 0, |s| s.dedupe_within(WINDOW));
+    assert_released_on_cancel(&[1, 3], 0, |s| s.distinct_by_key(|n| n % 2));
+    assert_released_on_cancel(&[1, 2], 0, |s| s.coalesce_on_resume(1));
+    assert_released_on_cancel(&[1], 0, |s| s.gate(stream::pending::<bool>(), true));
+    assert_released_on_cancel(&[1, 2], 0, |s| s.group_by(|n| n % 2));
+    assert_released_on_cancel(&[1], 0, |s| s.scan_until(0, |sum, n| Some(*sum + n)));
+    assert_released_on_cancel(&[1, 2], 0, |s| s.with_interval());
+    assert_released_on_cancel(&[1], 0, |s| s.on_backlog(0, |_| ()));
+    assert_released_on_cancel(&[1], 0, |s| s.prepend(stream::iter([0])));
+    assert_released_on_cancel(&[1], 0, |s| s.take_while_connected());
+    assert_released_on_cancel(&[1], 0, |s| s.map_into::<u64>());
+    assert_released_on_cancel(&[1], 0, |s| s.latest_only().with_drop_markers());
+    assert_released_on_cancel(&[1], 0, |s| merge(vec![s]));
+}
+
+#[test]
+fn combinators_awaiting_a_future() {
+    assert_released_on_cancel(&[1, 2], 0, |s| {
+        s.then_scan(0, |_, _| std::future::pending::<()>())
+    });
+    // Plus the source of the task spawned for the item.
+    assert_released_on_cancel(&[1], 1, |s| {
+        s.buffer_unordered_local(None, |_| std::future::pending::<()>())
+    });
+}
+
+#[test]
+fn sharing_combinators() {
+    assert_released_on_cancel(&[1], 0, |s| {
+        let (a, b) = s.share_rc();
+        select(a, b)
+    });
+    assert_released_on_cancel(&[1, 2], 0, |s| {
+        let (first, rest) = s.split_first();
+        select(first.into_stream().map(|_| 0), rest)
+    });
+}
+
+/// Counts the futures alive, decrementing on drop.
+struct Alive(Rc<Cell<usize>>);
+
+impl Alive {
+    fn new(count: &Rc<Cell<usize>>) -> Self {
+        count.set(count.get() + 1);
+        Self(count.clone())
+    }
+}
+
+impl Drop for Alive {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+#[test]
+fn the_spawned_tasks_are_aborted_with_their_consumer() {
+    with_context(|ctx| {
+        let emitter = Emitter::new();
+        let alive = Rc::new(Cell::new(0));
+        let mut consumer = Box::pin(
+            ev_stream!(emitter, ping, |_, n| n).for_each_concurrent_local(None, {
+                let alive = alive.clone();
+                move |_| {
+                    let alive = Alive::new(&alive);
+                    async move {
+                        std::future::pending::<()>().await;
+                        drop(alive);
+                    }
+                }
+            }),
+        );
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_future(&mut consumer), Poll::Pending);
+        flush(ctx);
+        assert_eq!(alive.get(), 2);
+
+        drop(consumer);
+        flush(ctx);
+        assert_eq!(alive.get(), 0);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert!(!ctx.pending());
+    });
+}
+
+#[test]
+fn a_spawned_consumer_releases_everything_once_aborted() {
+    with_context(|ctx| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let task = ctx.spawn_local(ev_stream!(emitter, ping, |_, n| n).debounce(WINDOW).count());
+        flush(ctx);
+        emitter.ping(1);
+        flush(ctx);
+        assert_eq!(clock.pending_timers(), 1);
+
+        // The handle keeps the task, and so the stream, alive.
+        task.abort();
+        drop(task);
+        flush(ctx);
+        assert!(!has_handlers(&emitter, "ping"));
+        assert_eq!(clock.pending_timers(), 0);
+        assert!(!ctx.pending());
+    });
+}
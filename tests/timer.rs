@@ -0,0 +1,463 @@
+//! The timer-based combinators in virtual time, with the `manual-clock` feature.
+mod common;
+
+use common::{has_handlers, poll_future, poll_once, with_context, Emitter};
+use ev_stream_gtk_rs::{ev_stream, EvStream, EvStreamExt, ManualClock, Settled};
+use futures::Stream;
+use glib::prelude::*;
+use std::task::Poll;
+use std::time::Duration;
+
+const STEP: Duration = Duration::from_millis(5);
+
+/// Emits `0..10` into the stream built by `combinator`, one every 10ms of virtual time,
+/// ends it at 150ms, and returns its items with the time they were yielded at, in ms.
+fn timeline<S, F>(combinator: F) -> Vec<(u128, S::Item)>
+where
+    S: Stream + Unpin,
+    F: FnOnce(EvStream<u32>) -> S,
+{
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = combinator(ev_stream!(emitter, ping, |_, n| n));
+        let mut items = Vec::new();
+        let mut collect = |stream: &mut S| {
+            while let Poll::Ready(Some(item)) = poll_once(stream) {
+                items.push((clock.elapsed().as_millis(), item));
+            }
+        };
+        while clock.elapsed() <= Duration::from_millis(150) {
+            // The timers which fired while advancing, then the emissions.
+            collect(&mut stream);
+            let now = clock.elapsed().as_millis();
+            if now % 10 == 0 && now < 100 {
+                emitter.ping(now as u32 / 10);
+            }
+            collect(&mut stream);
+            clock.advance(STEP);
+        }
+        drop(emitter);
+        assert!(matches!(poll_once(&mut stream), Poll::Ready(None)));
+        items
+    })
+}
+
+const WINDOW: Duration = Duration::from_millis(25);
+
+#[test]
+fn debounce_yields_the_last_item_once_the_burst_is_over() {
+    assert_eq!(timeline(|s| s.debounce(WINDOW)), [(115, 9)]);
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).debounce(WINDOW);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        clock.advance(WINDOW - STEP);
+        emitter.ping(2);
+        // The newer item restarts the window.
+        clock.advance(STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        clock.advance(WINDOW - STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        clock.advance(STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(2)));
+        // The item still waiting is flushed when the stream ends.
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        drop(emitter);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(3)));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+#[test]
+fn throttle_ignores_the_items_within_the_window_of_the_last_one_yielded() {
+    assert_eq!(
+        timeline(|s| s.throttle(WINDOW)),
+        [(0, 0), (30, 3), (60, 6), (90, 9)]
+    );
+}
+
+#[test]
+fn delay_each_shifts_a_timeline_by_its_delay() {
+    // Each delay starts once the previous item is out, so the items don't pile up.
+    assert_eq!(
+        timeline(|s| s.delay_each(Duration::from_millis(5))),
+        (0..10).map(|n| (n * 10 + 5, n as u32)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn throttle_opts_leading_only() {
+    assert_eq!(
+        timeline(|s| s.throttle_opts(WINDOW, true, false)),
+        [(0, 0), (30, 3), (60, 6), (90, 9)]
+    );
+}
+
+#[test]
+fn throttle_opts_trailing_only() {
+    assert_eq!(
+        timeline(|s| s.throttle_opts(WINDOW, false, true)),
+        [(25, 2), (50, 4), (75, 7), (100, 9)]
+    );
+}
+
+#[test]
+fn throttle_opts_leading_and_trailing() {
+    assert_eq!(
+        timeline(|s| s.throttle_opts(WINDOW, true, true)),
+        [(0, 0), (25, 2), (50, 4), (75, 7), (100, 9)]
+    );
+}
+
+#[test]
+fn throttle_opts_without_leading_or_trailing_yields_nothing() {
+    assert_eq!(timeline(|s| s.throttle_opts(WINDOW, false, false)), []);
+}
+
+#[test]
+fn throttle_latest_delivers_the_last_item_of_a_burst_after_the_window() {
+    assert_eq!(
+        timeline(|s| s.throttle_latest(WINDOW)),
+        [(0, 0), (25, 2), (50, 4), (75, 7), (100, 9)]
+    );
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).throttle_latest(WINDOW);
+        for n in 1..=3 {
+            emitter.ping(n);
+        }
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(1)));
+        // The burst stops mid-window.
+        clock.advance(WINDOW - STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        clock.advance(STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(3)));
+        // Then a quiet window, which yields nothing.
+        assert_eq!(clock.pending_timers(), 1);
+        clock.advance(WINDOW);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+/// Emits `pings` into the stream built by `combinator`, takes the `immediate` items
+/// yielded right away, then checks that dropping it while its timer is pending removes
+/// the timer along with the handler.
+fn assert_timer_released_on_drop<S, F>(pings: &[u32], immediate: usize, combinator: F)
+where
+    S: Stream + Unpin,
+    F: FnOnce(EvStream<u32>) -> S,
+{
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = combinator(ev_stream!(emitter, ping, |_, n| n));
+        for &n in pings {
+            emitter.ping(n);
+        }
+        for _ in 0..immediate {
+            assert!(matches!(poll_once(&mut stream), Poll::Ready(Some(_))));
+        }
+        assert!(poll_once(&mut stream).is_pending());
+        assert_eq!(clock.pending_timers(), 1);
+
+        drop(stream);
+        assert_eq!(clock.pending_timers(), 0);
+        assert!(!has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn dropping_debounce_mid_wait_removes_its_timer() {
+    assert_timer_released_on_drop(&[1], 0, |s| s.debounce(WINDOW));
+}
+
+#[test]
+fn dropping_throttle_mid_window_removes_its_timer() {
+    assert_timer_released_on_drop(&[1], 1, |s| s.throttle(WINDOW));
+    assert_timer_released_on_drop(&[1, 2], 1, |s| s.throttle_latest(WINDOW));
+    assert_timer_released_on_drop(&[1], 0, |s| s.throttle_opts(WINDOW, false, true));
+}
+
+#[test]
+fn dropping_delay_each_mid_wait_removes_its_timer() {
+    assert_timer_released_on_drop(&[1], 0, |s| s.delay_each(WINDOW));
+}
+
+#[test]
+fn dropping_chunks_timeout_mid_wait_removes_its_timer() {
+    assert_timer_released_on_drop(&[1], 0, |s| s.chunks_timeout(4, WINDOW));
+}
+
+#[test]
+fn dropping_settle_marker_mid_wait_removes_its_timer() {
+    assert_timer_released_on_drop(&[1], 1, |s| s.settle_marker(WINDOW));
+}
+
+#[test]
+fn dropping_end_after_mid_wait_removes_its_timer() {
+    assert_timer_released_on_drop(&[], 0, |s| s.end_after(WINDOW));
+}
+
+#[test]
+fn dropping_a_deadline_mid_wait_removes_its_timer() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).with_deadline(WINDOW);
+        emitter.ping(1);
+        let Poll::Ready(Some((1, deadline))) = poll_once(&mut stream) else {
+            panic!("expected the item");
+        };
+        assert_eq!(clock.pending_timers(), 1);
+        drop(deadline);
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+#[test]
+fn chunks_timeout_flushes_full_chunks_right_away() {
+    assert_eq!(
+        timeline(|s| s.chunks_timeout(2, WINDOW)),
+        [
+            (10, vec![0, 1]),
+            (30, vec![2, 3]),
+            (50, vec![4, 5]),
+            (70, vec![6, 7]),
+            (90, vec![8, 9])
+        ]
+    );
+}
+
+#[test]
+fn chunks_timeout_flushes_after_the_delay_from_the_first_item() {
+    assert_eq!(
+        timeline(|s| s.chunks_timeout(4, WINDOW)),
+        [
+            (25, vec![0, 1, 2]),
+            (55, vec![3, 4, 5]),
+            (85, vec![6, 7, 8]),
+            (115, vec![9])
+        ]
+    );
+    // A full chunk resets the timer: the next one waits from its own first item.
+    assert_eq!(
+        timeline(|s| s.chunks_timeout(3, WINDOW)),
+        [
+            (20, vec![0, 1, 2]),
+            (50, vec![3, 4, 5]),
+            (80, vec![6, 7, 8]),
+            (115, vec![9])
+        ]
+    );
+}
+
+#[test]
+fn chunks_timeout_flushes_a_partial_chunk_when_the_stream_ends() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut chunks = ev_stream!(emitter, ping, |_, n| n).chunks_timeout(4, WINDOW);
+        emitter.ping(1);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut chunks), Poll::Pending);
+        drop(emitter);
+        assert_eq!(poll_once(&mut chunks), Poll::Ready(Some(vec![1, 2])));
+        assert_eq!(poll_once(&mut chunks), Poll::Ready(None));
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+#[test]
+fn delay_each_waits_the_delay_before_every_item() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).delay_each(WINDOW);
+        for n in 1..=3 {
+            emitter.ping(n);
+        }
+        let mut yielded = Vec::new();
+        while clock.elapsed() <= WINDOW * 4 {
+            while let Poll::Ready(Some(n)) = poll_once(&mut stream) {
+                yielded.push((clock.elapsed().as_millis(), n));
+            }
+            clock.advance(STEP);
+        }
+        // One after the other: the delay of an item starts once the previous one is out.
+        assert_eq!(yielded, [(25, 1), (50, 2), (75, 3)]);
+        assert_eq!(clock.pending_timers(), 0);
+        drop(emitter);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+    });
+}
+
+#[test]
+fn a_debounce_keeps_its_state_across_a_reconnect() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let pings = ev_stream!(emitter, ping, |_, n| n);
+        let reconnector = pings.reconnector();
+        let mut debounced = pings.debounce(WINDOW);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut debounced), Poll::Pending);
+        clock.advance(Duration::from_millis(10));
+
+        assert!(reconnector.reconnect(|obj, s| {
+            obj.downcast_ref::<Emitter>()
+                .unwrap()
+                .connect_ping(move |_, n| {
+                    let _ = s.send(n * 10);
+                })
+        }));
+        // Only the new callback sends, and its item restarts the pending window.
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut debounced), Poll::Pending);
+        clock.advance(Duration::from_millis(15));
+        assert_eq!(poll_once(&mut debounced), Poll::Pending);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(poll_once(&mut debounced), Poll::Ready(Some(20)));
+
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut debounced), Poll::Pending);
+        clock.advance(WINDOW);
+        assert_eq!(poll_once(&mut debounced), Poll::Ready(Some(30)));
+        assert!(has_handlers(&emitter, "ping"));
+    });
+}
+
+#[test]
+fn each_deadline_resolves_once_its_duration_passed() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut items = ev_stream!(emitter, ping, |_, n| n).with_deadline(WINDOW);
+        emitter.ping(1);
+        let Poll::Ready(Some((1, mut first))) = poll_once(&mut items) else {
+            panic!("the first item isn't ready");
+        };
+        clock.advance(Duration::from_millis(10));
+        emitter.ping(2);
+        let Poll::Ready(Some((2, mut second))) = poll_once(&mut items) else {
+            panic!("the second item isn't ready");
+        };
+
+        clock.advance(Duration::from_millis(14));
+        assert_eq!(poll_future(&mut first), Poll::Pending);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(poll_future(&mut first), Poll::Ready(()));
+        assert_eq!(poll_future(&mut second), Poll::Pending);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(poll_future(&mut second), Poll::Ready(()));
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+#[test]
+fn end_after_ends_at_the_deadline_despite_the_emissions() {
+    assert_eq!(
+        timeline(|s| s.end_after(Duration::from_millis(45))),
+        [(0, 0), (10, 1), (20, 2), (30, 3), (40, 4)]
+    );
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).end_after(WINDOW);
+        for n in 0..5 {
+            emitter.ping(n);
+            assert_eq!(poll_once(&mut stream), Poll::Ready(Some(n)));
+            clock.advance(STEP);
+        }
+        // At 25ms: ended, although an item is ready.
+        emitter.ping(5);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert_eq!(poll_once(&mut stream), Poll::Ready(None));
+        assert_eq!(clock.pending_timers(), 0);
+    });
+}
+
+#[test]
+fn settle_marker_marks_the_end_of_a_burst() {
+    // The emissions, 10ms apart, keep resetting the quiet period.
+    let mut expected: Vec<_> = (0..10).map(|n| (n * 10, Settled::Item(n as u32))).collect();
+    expected.push((115, Settled::Quiescent));
+    assert_eq!(timeline(|s| s.settle_marker(WINDOW)), expected);
+}
+
+#[test]
+fn settle_marker_restarts_the_quiet_period_with_each_item() {
+    with_context(|_| {
+        let clock = ManualClock::install();
+        let emitter = Emitter::new();
+        let mut stream = ev_stream!(emitter, ping, |_, n| n).settle_marker(WINDOW);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(Settled::Item(1))));
+        clock.advance(WINDOW - STEP);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(Settled::Item(2))));
+        clock.advance(WINDOW - STEP);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        clock.advance(STEP);
+        assert_eq!(
+            poll_once(&mut stream),
+            Poll::Ready(Some(Settled::Quiescent))
+        );
+
+        // Once per burst: staying quiet marks nothing more.
+        clock.advance(WINDOW * 2);
+        assert_eq!(poll_once(&mut stream), Poll::Pending);
+        assert_eq!(clock.pending_timers(), 0);
+        emitter.ping(3);
+        assert_eq!(poll_once(&mut stream), Poll::Ready(Some(Settled::Item(3))));
+        clock.advance(WINDOW);
+        assert_eq!(
+            poll_once(&mut stream),
+            Poll::Ready(Some(Settled::Quiescent))
+        );
+    });
+}
+
+#[test]
+fn a_nested_clock_restores_the_one_it_replaced() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let outer = ManualClock::install();
+        let inner = ManualClock::install();
+        let mut delayed = ev_stream!(emitter, ping, |_, n| n).delay_each(WINDOW);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut delayed), Poll::Pending);
+        assert_eq!((outer.pending_timers(), inner.pending_timers()), (0, 1));
+        inner.advance(WINDOW);
+        assert_eq!(poll_once(&mut delayed), Poll::Ready(Some(1)));
+
+        drop(inner);
+        emitter.ping(2);
+        assert_eq!(poll_once(&mut delayed), Poll::Pending);
+        assert_eq!(outer.pending_timers(), 1);
+        outer.advance(WINDOW);
+        assert_eq!(poll_once(&mut delayed), Poll::Ready(Some(2)));
+    });
+}
+
+#[test]
+fn a_clock_dropped_out_of_order_leaves_the_later_one_installed() {
+    with_context(|_| {
+        let emitter = Emitter::new();
+        let outer = ManualClock::install();
+        let inner = ManualClock::install();
+        drop(outer);
+        let mut delayed = ev_stream!(emitter, ping, |_, n| n).delay_each(WINDOW);
+        emitter.ping(1);
+        assert_eq!(poll_once(&mut delayed), Poll::Pending);
+        assert_eq!(inner.pending_timers(), 1);
+        inner.advance(WINDOW);
+        assert_eq!(poll_once(&mut delayed), Poll::Ready(Some(1)));
+    });
+}
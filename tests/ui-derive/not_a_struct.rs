@@ -0,0 +1,9 @@
+use ev_stream_gtk_rs::FromSignalArgs;
+
+#[derive(FromSignalArgs)]
+enum Event {
+    Clicked,
+    Moved(i32, i32),
+}
+
+fn main() {}
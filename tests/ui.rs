@@ -0,0 +1,10 @@
+//! The friendly errors of the `compile_error!` arms of `ev_stream!`, the `must_use`
+//! lint on streams dropped unpolled, and the errors of `#[derive(FromSignalArgs)]` with the
+//! `derive` feature.
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    #[cfg(feature = "derive")]
+    t.compile_fail("tests/ui-derive/*.rs");
+}
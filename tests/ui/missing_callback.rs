@@ -0,0 +1,6 @@
+use ev_stream_gtk_rs::{ev_stream, glib};
+
+fn main() {
+    let object = glib::Object::new::<glib::Object>();
+    let _ = ev_stream!(object, "notify");
+}
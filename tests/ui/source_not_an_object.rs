@@ -0,0 +1,10 @@
+use ev_stream_gtk_rs::{ev_stream, glib};
+use glib::prelude::*;
+
+/// Not a glib object, whether it's `Clone` or not.
+#[derive(Clone)]
+struct Model;
+
+fn main() {
+    let _ = ev_stream!(Model, "changed", |args|);
+}
@@ -0,0 +1,10 @@
+#![deny(unused_must_use)]
+
+use ev_stream_gtk_rs::glib::prelude::*;
+use ev_stream_gtk_rs::{ev_stream, glib, EvStreamExt};
+
+fn main() {
+    let object = glib::Object::new::<glib::Object>();
+    ev_stream!(object, "notify", |args|);
+    ev_stream!(object, "notify", |args|).debounce(std::time::Duration::from_millis(10));
+}
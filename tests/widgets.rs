@@ -0,0 +1,424 @@
+//! Needs a display: run with `--features gtk4`, under `xvfb-run` on CI.
+use ev_stream_gtk_rs::widgets::*;
+use ev_stream_gtk_rs::EvStream;
+use gtk::prelude::*;
+use std::time::Duration;
+
+/// The next item, iterating the main loop for at most 5 seconds.
+fn next_item<T>(stream: &mut EvStream<T>) -> T {
+    stream
+        .next_timeout(Duration::from_secs(5))
+        .expect("no item within 5 seconds")
+}
+
+#[gtk::test]
+fn adjustment_value_stream_always_delivers_the_last_value() {
+    let adj = gtk::Adjustment::new(0.0, 0.0, 100.0, 1.0, 10.0, 0.0);
+    let mut values = adjustment_value_stream(&adj);
+    for value in 1..=50 {
+        adj.set_value(value.into());
+    }
+    assert_eq!(values.try_recv(), Ok(Some(50.0)));
+    assert_eq!(values.try_recv(), Ok(None));
+    adj.set_value(3.0);
+    assert_eq!(values.try_recv(), Ok(Some(3.0)));
+}
+
+#[gtk::test]
+fn size_allocate_stream_follows_the_allocation() {
+    let area = gtk::DrawingArea::new();
+    let window = gtk::Window::builder()
+        .default_width(200)
+        .default_height(100)
+        .child(&area)
+        .build();
+    let mut sizes = size_allocate_stream(&area);
+    window.present();
+    let current =
+        |area: &gtk::DrawingArea| (area.width(), area.height(), area.allocated_baseline());
+    let mut first = next_item(&mut sizes);
+    while first.0 == 0 {
+        first = next_item(&mut sizes);
+    }
+    assert_eq!(first, current(&area));
+
+    window.set_default_size(400, 300);
+    let second = next_item(&mut sizes);
+    assert_ne!(second, first);
+    assert!(second.0 > first.0 && second.1 > first.1);
+    window.destroy();
+}
+
+#[gtk::test]
+fn entry_text_stream_yields_the_text_of_entries_and_search_entries() {
+    let entry = gtk::Entry::new();
+    let mut texts = entry_text_stream(&entry);
+    entry.set_text("he");
+    entry.set_text("hello");
+    assert_eq!(texts.drain_now(), ["he", "hello"]);
+
+    let search = gtk::SearchEntry::new();
+    let mut searches = entry_text_stream(&search);
+    search.set_text("query");
+    assert_eq!(searches.drain_now(), ["query"]);
+}
+
+#[gtk::test]
+fn children_stream_reports_added_and_removed_children() {
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    let first = gtk::Label::new(Some("first"));
+    container.append(&first);
+    // Only the changes after the subscription are reported.
+    let mut children = children_stream(&container);
+    let second = gtk::Label::new(Some("second"));
+    container.append(&second);
+    container.remove(&first);
+
+    let events = children.drain_now();
+    let [ChildEvent::Added(added), ChildEvent::Removed(removed)] = &events[..] else {
+        panic!("unexpected events: {events:?}");
+    };
+    assert_eq!(added, second.upcast_ref::<gtk::Widget>());
+    assert_eq!(removed, first.upcast_ref::<gtk::Widget>());
+}
+
+#[gtk::test]
+fn text_buffer_stream_yields_the_full_text_after_each_insertion() {
+    let buffer = gtk::TextBuffer::new(None);
+    let mut texts = text_buffer_stream(&buffer);
+    buffer.insert(&mut buffer.end_iter(), "hello");
+    buffer.insert(&mut buffer.end_iter(), " world");
+    buffer.insert(&mut buffer.start_iter(), "> ");
+    assert_eq!(texts.drain_now(), ["hello", "hello world", "> hello world"]);
+}
+
+#[gtk::test]
+fn visible_child_stream_yields_the_name_of_the_new_child() {
+    let stack = gtk::Stack::new();
+    stack.add_named(&gtk::Label::new(None), Some("first"));
+    stack.add_named(&gtk::Label::new(None), Some("second"));
+    let mut names = visible_child_stream(&stack);
+    stack.set_visible_child_name("second");
+    stack.set_visible_child_name("first");
+    assert_eq!(
+        names.drain_now(),
+        [Some("second".to_owned()), Some("first".to_owned())]
+    );
+}
+
+#[gtk::test]
+fn display_monitors_stream_yields_on_a_monitors_list_change() {
+    let display = gtk::gdk::Display::default().expect("no display");
+    let mut changes = display_monitors_stream(&display);
+    // Real monitors can't be plugged in: emit the same `items-changed` GDK does.
+    display.monitors().items_changed(0, 0, 0);
+    next_item(&mut changes);
+    assert!(changes.drain_now().is_empty());
+}
+
+#[gtk::test]
+fn scroll_stream_delivers_the_deltas_and_returns_the_propagation() {
+    let controller = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+    let mut deltas = scroll_stream(&controller, gtk::glib::Propagation::Stop);
+    let handled = controller.emit_by_name::<bool>("scroll", &[&1.5, &-2.0]);
+    assert!(handled);
+    assert_eq!(deltas.drain_now(), [(1.5, -2.0)]);
+
+    // A burst keeps only the latest 64 deltas.
+    for n in 0..100 {
+        controller.emit_by_name::<bool>("scroll", &[&0.0, &f64::from(n)]);
+    }
+    let kept = deltas.drain_now();
+    assert_eq!(kept.len(), 64);
+    assert_eq!(kept.first(), Some(&(0.0, 36.0)));
+    assert_eq!(kept.last(), Some(&(0.0, 99.0)));
+}
+
+#[gtk::test]
+fn selection_changed_stream_reports_the_changed_range() {
+    let store = gtk::gio::ListStore::new::<gtk::StringObject>();
+    for name in ["a", "b", "c", "d"] {
+        store.append(&gtk::StringObject::new(name));
+    }
+    let selection = gtk::SingleSelection::new(Some(store));
+    selection.set_selected(0);
+    let mut changes = selection_changed_stream(&selection);
+    selection.set_selected(3);
+    // From the previously selected item to the new one.
+    assert_eq!(changes.drain_now(), [(0, 4)]);
+    selection.set_selected(1);
+    assert_eq!(changes.drain_now(), [(1, 3)]);
+}
+
+#[gtk::test]
+fn toggled_stream_yields_the_new_state_of_toggle_and_check_buttons() {
+    let toggle = gtk::ToggleButton::new();
+    let mut states = toggled_stream(&toggle);
+    toggle.set_active(true);
+    toggle.set_active(false);
+    toggle.set_active(true);
+    assert_eq!(states.drain_now(), [true, false, true]);
+
+    let check = gtk::CheckButton::new();
+    let mut states = check_button_toggled_stream(&check);
+    check.set_active(true);
+    check.set_active(false);
+    assert_eq!(states.drain_now(), [true, false]);
+}
+
+#[gtk::test]
+fn window_active_stream_yields_the_active_state_on_each_notify() {
+    let window = gtk::Window::new();
+    let mut states = window_active_stream(&window);
+    // `is-active` is read-only, only the window manager changes it: notify as GTK does.
+    window.notify("is-active");
+    assert_eq!(states.drain_now(), [window.is_active()]);
+    window.notify("title");
+    assert!(states.drain_now().is_empty());
+    window.destroy();
+}
+
+#[gtk::test]
+fn ev_stream_decide_answers_query_tooltip_and_streams_the_position() {
+    let label = gtk::Label::new(Some("tooltip"));
+    let mut queries = ev_stream_gtk_rs::ev_stream_decide!(
+        label,
+        query_tooltip,
+        |_, x, _, _, _| x < 50,
+        |_label, x, y, _keyboard, _tooltip| (x, y)
+    );
+    // GTK only emits `query-tooltip` for a hovered, mapped widget: emit as it does.
+    let tooltip = gtk::glib::Object::new::<gtk::Tooltip>();
+    let emit = |x: i32| label.emit_by_name::<bool>("query-tooltip", &[&x, &4, &false, &tooltip]);
+    assert!(emit(10));
+    assert!(!emit(60));
+    assert_eq!(queries.drain_now(), [(10, 4), (60, 4)]);
+}
+
+#[gtk::test]
+fn resize_stream_yields_the_new_size_of_the_drawing_area() {
+    let area = gtk::DrawingArea::new();
+    let window = gtk::Window::builder()
+        .default_width(200)
+        .default_height(100)
+        .child(&area)
+        .build();
+    let mut sizes = resize_stream(&area);
+    window.present();
+    let mut first = next_item(&mut sizes);
+    while first.0 == 0 {
+        first = next_item(&mut sizes);
+    }
+    assert_eq!(first, (area.width(), area.height()));
+
+    window.set_default_size(400, 300);
+    let second = next_item(&mut sizes);
+    assert!(
+        second.0 > first.0 && second.1 > first.1,
+        "{second:?} <= {first:?}"
+    );
+    assert_eq!(second, (area.width(), area.height()));
+    window.destroy();
+}
+
+#[gtk::test]
+fn fullscreened_and_maximized_streams_follow_their_own_property() {
+    let window = gtk::Window::new();
+    let mut fullscreened = fullscreened_stream(&window);
+    let mut maximized = maximized_stream(&window);
+    // Both properties only change once the window manager applied the state, which
+    // notifies them: notify as GTK does.
+    window.notify("fullscreened");
+    assert_eq!(fullscreened.drain_now(), [window.is_fullscreen()]);
+    assert!(maximized.drain_now().is_empty());
+
+    window.notify("maximized");
+    window.notify("maximized");
+    assert_eq!(
+        maximized.drain_now(),
+        [window.is_maximized(), window.is_maximized()]
+    );
+    assert!(fullscreened.drain_now().is_empty());
+    window.destroy();
+}
+
+#[gtk::test]
+fn drag_update_stream_delivers_the_latest_offset() {
+    let gesture = gtk::GestureDrag::new();
+    let mut offsets = drag_update_stream(&gesture);
+    for step in 1..=10 {
+        let offset = f64::from(step);
+        gesture.emit_by_name::<()>("drag-update", &[&offset, &(offset * 2.0)]);
+    }
+    assert_eq!(offsets.drain_now(), [(10.0, 20.0)]);
+    gesture.emit_by_name::<()>("drag-update", &[&-1.0f64, &0.5f64]);
+    assert_eq!(offsets.try_recv(), Ok(Some((-1.0, 0.5))));
+}
+
+#[gtk::test]
+fn entry_activate_stream_yields_the_text_when_enter_is_pressed() {
+    let entry = gtk::Entry::new();
+    let mut activations = entry_activate_stream(&entry);
+    entry.set_text("first");
+    entry.set_text("query");
+    // Typing alone doesn't activate.
+    assert!(activations.drain_now().is_empty());
+    entry.emit_activate();
+    entry.set_text("");
+    entry.emit_activate();
+    assert_eq!(activations.drain_now(), ["query", ""]);
+}
+
+#[gtk::test]
+fn connected_object_type_is_the_type_of_the_button_until_it_is_finalized() {
+    let button = gtk::Button::new();
+    let clicks = ev_stream_gtk_rs::ev_stream!(button, clicked, |_button| ());
+    assert_eq!(
+        clicks.connected_object_type(),
+        Some(gtk::Button::static_type())
+    );
+    drop(button);
+    assert_eq!(clicks.connected_object_type(), None);
+}
+
+#[gtk::test]
+fn menu_items_changed_stream_yields_the_position_and_counts() {
+    let menu = gtk::gio::Menu::new();
+    let mut changes = menu_items_changed_stream(&menu);
+    menu.append(Some("Open"), Some("app.open"));
+    menu.append(Some("Quit"), Some("app.quit"));
+    menu.insert(1, Some("Save"), Some("app.save"));
+    menu.remove(0);
+    assert_eq!(
+        changes.drain_now(),
+        [(0, 0, 1), (1, 0, 1), (1, 0, 1), (0, 1, 0)]
+    );
+}
+
+#[gtk::test]
+fn selected_stream_yields_the_new_position() {
+    let dropdown = gtk::DropDown::from_strings(&["a", "b", "c"]);
+    let mut selections = selected_stream(&dropdown);
+    dropdown.set_selected(2);
+    dropdown.set_selected(0);
+    assert_eq!(selections.drain_now(), [2, 0]);
+}
+
+/// The number of `EventControllerLegacy` on `widget`.
+fn legacy_controllers(widget: &impl IsA<gtk::Widget>) -> usize {
+    let controllers = widget.observe_controllers();
+    (0..controllers.n_items())
+        .filter_map(|i| controllers.item(i))
+        .filter(|controller| controller.is::<gtk::EventControllerLegacy>())
+        .count()
+}
+
+// gtk4-rs can't build a button release event, so only the value changes are emitted here.
+#[gtk::test]
+fn adjustment_commit_stream_ignores_the_value_changes_and_removes_its_controller() {
+    let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0);
+    let controllers = legacy_controllers(&scale);
+    let mut commits = adjustment_commit_stream(&scale);
+    assert_eq!(legacy_controllers(&scale), controllers + 1);
+    for value in [10.0, 20.0, 30.0] {
+        scale.set_value(value);
+    }
+    assert_eq!(commits.try_recv(), Ok(None));
+
+    drop(commits);
+    assert_eq!(legacy_controllers(&scale), controllers);
+}
+
+#[gtk::test]
+fn rebind_moves_the_clicks_from_a_button_to_another() {
+    let (first, second) = (gtk::Button::new(), gtk::Button::new());
+    let mut clicks = EvStream::rebindable(&first, |obj, s| {
+        obj.downcast_ref::<gtk::Button>()
+            .unwrap()
+            .connect_clicked(move |button| {
+                let _ = s.send(button.clone());
+            })
+    });
+    assert_eq!(clicks.rebind(&second), Ok(()));
+    first.emit_clicked();
+    second.emit_clicked();
+    assert_eq!(clicks.drain_now(), [second.clone()]);
+}
+
+#[gtk::test]
+fn page_switched_stream_yields_the_index_of_the_new_page() {
+    let notebook = gtk::Notebook::new();
+    let pages: Vec<_> = (0..3).map(|_| gtk::Label::new(None)).collect();
+    for page in &pages {
+        notebook.append_page(page, None::<&gtk::Widget>);
+    }
+    let mut switches = page_switched_stream(&notebook);
+    notebook.emit_by_name::<()>("switch-page", &[&pages[2], &2u32]);
+    assert_eq!(switches.drain_now(), [2]);
+}
+
+#[gtk::test]
+fn ev_stream_connects_to_key_pressed_and_returns_its_propagation() {
+    let controller = gtk::EventControllerKey::new();
+    let mut keys = ev_stream_gtk_rs::ev_stream!(controller, key_pressed, |_, keyval, _, _| keyval);
+    let stopped = controller.emit_by_name::<bool>(
+        "key-pressed",
+        &[&*gtk::gdk::Key::a, &0u32, &gtk::gdk::ModifierType::empty()],
+    );
+    assert!(!stopped);
+    assert_eq!(keys.drain_now(), [gtk::gdk::Key::a]);
+    drop(keys);
+
+    let mut stopping = ev_stream_gtk_rs::ev_stream!(
+        controller,
+        key_pressed,
+        |_, keyval, _, _| keyval => gtk::glib::Propagation::Stop
+    );
+    let stopped = controller.emit_by_name::<bool>(
+        "key-pressed",
+        &[&*gtk::gdk::Key::b, &0u32, &gtk::gdk::ModifierType::empty()],
+    );
+    assert!(stopped);
+    assert_eq!(stopping.drain_now(), [gtk::gdk::Key::b]);
+}
+
+#[gtk::test]
+fn reveal_finished_stream_yields_the_state_once_the_transition_is_over() {
+    let revealer = gtk::Revealer::builder()
+        .transition_duration(0)
+        .child(&gtk::Label::new(Some("details")))
+        .build();
+    let mut finished = reveal_finished_stream(&revealer);
+    revealer.set_reveal_child(true);
+    assert!(next_item(&mut finished));
+    revealer.set_reveal_child(false);
+    assert!(!next_item(&mut finished));
+}
+
+#[gtk::test]
+fn search_started_and_stopped_streams_follow_their_signal() {
+    let search = gtk::SearchEntry::new();
+    let mut started = search_started_stream(&search);
+    let mut stopped = search_stopped_stream(&search);
+    search.emit_by_name::<()>("search-started", &[]);
+    assert_eq!(started.drain_now(), [()]);
+    assert!(stopped.drain_now().is_empty());
+    search.emit_by_name::<()>("stop-search", &[]);
+    search.emit_by_name::<()>("stop-search", &[]);
+    assert_eq!(stopped.drain_now(), [(), ()]);
+    assert!(started.drain_now().is_empty());
+}
+
+#[gtk::test]
+fn destroy_stream_yields_once_then_ends() {
+    let window = gtk::Window::new();
+    let mut destroyed = destroy_stream(&window);
+    assert_eq!(destroyed.try_recv(), Ok(None));
+    window.destroy();
+    assert_eq!(destroyed.try_recv(), Ok(Some(())));
+    // The sender was dropped with the first emission.
+    assert_eq!(
+        destroyed.try_recv(),
+        Err(ev_stream_gtk_rs::TryRecvError::Disconnected)
+    );
+}